@@ -0,0 +1,43 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_types
+
+@group(1) @binding(0)
+var<uniform> mesh: Mesh;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    // per instance world matrix, one column per attribute
+    @location(3) i_model_0: vec4<f32>,
+    @location(4) i_model_1: vec4<f32>,
+    @location(5) i_model_2: vec4<f32>,
+    @location(6) i_model_3: vec4<f32>,
+    @location(7) i_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+// a fixed key light is plenty for props
+const LIGHT_DIR: vec3<f32> = vec3<f32>(0.3, 0.9, 0.3);
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let model = mat4x4<f32>(vertex.i_model_0, vertex.i_model_1, vertex.i_model_2, vertex.i_model_3);
+    let world_position = model * vec4<f32>(vertex.position, 1.0);
+    let world_normal = normalize((model * vec4<f32>(vertex.normal, 0.0)).xyz);
+
+    var out: VertexOutput;
+    out.clip_position = view.view_proj * world_position;
+    let light = 0.35 + 0.65 * max(dot(world_normal, normalize(LIGHT_DIR)), 0.0);
+    out.color = vec4<f32>(vertex.i_color.rgb * light, vertex.i_color.a);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
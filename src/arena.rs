@@ -0,0 +1,898 @@
+use super::arena_validation::{self, Severity};
+use super::boost_pads::{BoostPad, PadGlow};
+use super::cli::StartupArgs;
+use super::game_state::GameState;
+use super::marks::{EnemyGoal, GoalPost, PlayerGoal, Team};
+use super::storage::{self, Versioned};
+use super::{MatchEntities, BOARD_DIM, GOAL_GAP, PLAYER_SPAWN};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::utils::BoxedFuture;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const DEFAULT_ARENA: &str = "assets/arenas/default.arena.ron";
+// where the default arena was saved before arenas became .arena.ron assets
+const LEGACY_DEFAULT_ARENA: &str = "assets/arenas/default.ron";
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world
+            .get_resource::<StartupArgs>()
+            .and_then(|args| args.arena.clone());
+        app.insert_resource(Arena::open(path))
+            .add_asset::<ArenaAsset>()
+            .init_asset_loader::<ArenaAssetLoader>()
+            .add_event::<ArenaReloadedEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_arena_system))
+            // the asset server only watches for changes in develop builds
+            .add_startup_system(watch_arena_system)
+            .add_system(arena_reload_system);
+    }
+}
+
+// `--validate-arena <file.ron>` reports problems instead of starting the game, failing
+// if there are any. run from main before the app exists, so it prints rather than logs
+pub fn validate_file(path: &Path) -> ExitCode {
+    let issues = match ArenaDescriptor::load(path) {
+        Ok(descriptor) => arena_validation::validate(&descriptor),
+        Err(err) => {
+            eprintln!("Can't read arena {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+    for issue in issues.iter() {
+        eprintln!("{}", issue);
+    }
+    println!("{}: {} problems", path.display(), issues.len());
+    if issues.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+// a `.arena.ron` file read through the asset server, which can watch it for edits
+#[derive(TypeUuid)]
+#[uuid = "6b52eb96-24fd-4651-9126-c20fbd979eae"]
+pub struct ArenaAsset(pub ArenaDescriptor);
+
+#[derive(Default)]
+struct ArenaAssetLoader;
+
+impl AssetLoader for ArenaAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?;
+            let descriptor =
+                storage::parse::<ArenaDescriptor>(text).map_err(bevy::asset::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(ArenaAsset(descriptor)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["arena.ron"]
+    }
+}
+
+// the descriptor was replaced from disk, everything spawned from it is stale
+pub struct ArenaReloadedEvent;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PieceKind {
+    Floor,
+    Wall,
+    GoalPost,
+    // the trigger volume that counts goals against the away side
+    GoalSensor,
+    // the same at the home end, goals here count against the home side
+    HomeGoalSensor,
+    // hollow, open towards the pitch, the ball settles inside it after a goal
+    GoalNet,
+    Prop,
+    // a sensor disc on the floor, see boost_pads.rs
+    BoostPad,
+}
+
+// what a boost pad does to whatever drives over it
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize, Reflect)]
+pub enum PadEffect {
+    // the player runs faster for a moment
+    #[default]
+    Speed,
+    // the ball is launched the way the pad faces
+    Launch,
+}
+
+// props are drawn instanced, one draw call per shape however many there are
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum PropShape {
+    #[default]
+    Box,
+    Sphere,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArenaPiece {
+    pub name: String,
+    pub kind: PieceKind,
+    pub position: [f32; 3],
+    // euler angles in degrees, applied x then y then z
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "default_scale")]
+    pub scale: [f32; 3],
+    pub half_extents: [f32; 3],
+    #[serde(default = "default_color")]
+    pub color: [f32; 3],
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    // only used by props
+    #[serde(default)]
+    pub shape: PropShape,
+    // props sharing a group are culled together, see CullingSettings
+    #[serde(default)]
+    pub group: Option<String>,
+    // only used by boost pads
+    #[serde(default)]
+    pub pad: PadEffect,
+}
+
+fn default_color() -> [f32; 3] {
+    [1.0, 0.0, 0.0]
+}
+
+fn default_scale() -> [f32; 3] {
+    [1.0; 3]
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl ArenaPiece {
+    pub fn new(name: &str, kind: PieceKind, position: Vec3, half_extents: Vec3) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            position: position.to_array(),
+            rotation: [0.0; 3],
+            scale: default_scale(),
+            half_extents: half_extents.to_array(),
+            color: default_color(),
+            visible: !matches!(kind, PieceKind::GoalSensor | PieceKind::HomeGoalSensor),
+            shape: PropShape::Box,
+            group: None,
+            pad: PadEffect::default(),
+        }
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    pub fn transform(&self) -> Transform {
+        let [x, y, z] = self.rotation.map(f32::to_radians);
+        Transform {
+            translation: Vec3::from(self.position),
+            rotation: Quat::from_euler(EulerRot::XYZ, x, y, z),
+            scale: Vec3::from(self.scale),
+        }
+    }
+
+    // world size of the piece once its scale is applied
+    pub fn extents(&self) -> Vec3 {
+        Vec3::from(self.half_extents) * Vec3::from(self.scale)
+    }
+
+    // axis aligned min and max corners around the rotated, scaled piece
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let tf = self.transform();
+        let rotation = Mat3::from_quat(tf.rotation);
+        let half = self.extents();
+        let extent = Vec3::new(
+            rotation.row(0).abs().dot(half),
+            rotation.row(1).abs().dot(half),
+            rotation.row(2).abs().dot(half),
+        );
+        (tf.translation - extent, tf.translation + extent)
+    }
+
+    // editors move pieces around through their transform, this folds it back in
+    pub fn set_transform(&mut self, transform: &Transform) {
+        let (x, y, z) = transform.rotation.to_euler(EulerRot::XYZ);
+        self.position = transform.translation.to_array();
+        self.rotation = [x, y, z].map(f32::to_degrees);
+        self.scale = transform.scale.to_array();
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpawnPoint {
+    pub team: Team,
+    pub position: [f32; 3],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArenaDescriptor {
+    pub name: String,
+    pub pieces: Vec<ArenaPiece>,
+    pub spawns: Vec<SpawnPoint>,
+    #[serde(default)]
+    pub lod: LodSettings,
+    #[serde(default)]
+    pub culling: CullingSettings,
+    #[serde(default)]
+    pub sky: SkySettings,
+    // scenery around the board, none by default
+    #[serde(default)]
+    pub stadium: Option<StadiumSettings>,
+}
+
+// the gradient drawn behind the arena, colours are linear rgb
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkySettings {
+    pub zenith: [f32; 3],
+    pub horizon: [f32; 3],
+    // below the horizon, only seen past the edges of the pitch
+    pub ground: [f32; 3],
+    // ambient light brightness, its colour comes from the sky
+    pub ambient: f32,
+}
+
+// a gltf scene placed around the board, see stadium.rs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StadiumSettings {
+    // relative to assets/
+    pub scene: String,
+    pub position: [f32; 3],
+    pub scale: f32,
+    pub colliders: StadiumColliders,
+}
+
+impl Default for StadiumSettings {
+    fn default() -> Self {
+        Self {
+            scene: "models/stadium.glb".to_string(),
+            position: [0.0; 3],
+            scale: 1.0,
+            colliders: StadiumColliders::default(),
+        }
+    }
+}
+
+// which of the scene's meshes the ball can hit
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum StadiumColliders {
+    // purely visual
+    #[default]
+    None,
+    // only the nodes named *-col, which are hidden either way
+    Proxies,
+    // every visible mesh, as a trimesh
+    Meshes,
+}
+
+impl Default for SkySettings {
+    fn default() -> Self {
+        Self {
+            zenith: [0.2, 0.4, 0.8],
+            horizon: [0.75, 0.85, 0.95],
+            ground: [0.15, 0.17, 0.15],
+            ambient: 0.14,
+        }
+    }
+}
+
+// distances from the game camera at which props drop detail
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LodSettings {
+    pub low_distance: f32,
+    pub impostor_distance: f32,
+    // past impostor_distance props become flat camera-facing cards, or vanish without
+    pub impostors: bool,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            low_distance: 25.0,
+            impostor_distance: 60.0,
+            impostors: true,
+        }
+    }
+}
+
+// keeps dense arenas cheap when most of the props are off screen
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CullingSettings {
+    // test props against the camera frustum, grouped props by their shared bounds
+    pub frustum: bool,
+    // grows the bounds before testing, negative values cull more aggressively
+    pub margin: f32,
+    pub volumes: Vec<VisibilityVolume>,
+}
+
+impl Default for CullingSettings {
+    fn default() -> Self {
+        Self {
+            frustum: true,
+            margin: 0.0,
+            volumes: Vec::new(),
+        }
+    }
+}
+
+// while the camera is inside a volume only the listed prop groups are drawn,
+// props without a group are never hidden by volumes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VisibilityVolume {
+    pub name: String,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    pub visible_groups: Vec<String>,
+}
+
+impl VisibilityVolume {
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(Vec3::from(self.min)).all() && point.cmple(Vec3::from(self.max)).all()
+    }
+}
+
+impl CullingSettings {
+    // None when no volume holds the camera, otherwise the groups it may see
+    pub fn visible_groups(&self, camera: Vec3) -> Option<Vec<&str>> {
+        let mut inside = self
+            .volumes
+            .iter()
+            .filter(|volume| volume.contains(camera))
+            .peekable();
+        inside.peek()?;
+        Some(
+            inside
+                .flat_map(|volume| volume.visible_groups.iter().map(String::as_str))
+                .collect(),
+        )
+    }
+}
+
+// the original hand-built pitch, used whenever no arena file is around
+impl Default for ArenaDescriptor {
+    fn default() -> Self {
+        let (width, height, length) = BOARD_DIM;
+        let floor = ArenaPiece {
+            color: [0.0; 3],
+            ..ArenaPiece::new(
+                "floor",
+                PieceKind::Floor,
+                Vec3::ZERO,
+                Vec3::new(width, height, length) / 2.0,
+            )
+        };
+        let mut pieces = vec![
+            floor,
+            ArenaPiece::new(
+                "LeftSideWall",
+                PieceKind::Wall,
+                Vec3::new(width / 2.0 + 0.5, 1.0, 0.0),
+                Vec3::new(0.5, 1.0, length / 2.0),
+            ),
+            ArenaPiece::new(
+                "RightSideWall",
+                PieceKind::Wall,
+                Vec3::new(-width / 2.0 - 0.5, 1.0, 0.0),
+                Vec3::new(0.5, 1.0, length / 2.0),
+            ),
+        ];
+        pieces.extend(goal(Team::Away, 0.0, GOAL_GAP - 0.5));
+        pieces.extend(goal(Team::Home, 0.0, GOAL_GAP - 0.5));
+        // a speed pad on either flank at halfway
+        for (name, x) in [("LeftBoostPad", width / 4.0), ("RightBoostPad", -width / 4.0)] {
+            pieces.push(ArenaPiece {
+                color: [0.0, 0.8, 1.0],
+                ..ArenaPiece::new(
+                    name,
+                    PieceKind::BoostPad,
+                    Vec3::new(x, height / 2.0 + 0.05, 0.0),
+                    Vec3::new(0.6, 0.05, 0.6),
+                )
+            });
+        }
+        Self {
+            name: "default".to_string(),
+            pieces,
+            spawns: vec![
+                SpawnPoint {
+                    team: Team::Home,
+                    position: PLAYER_SPAWN.to_array(),
+                },
+                SpawnPoint {
+                    team: Team::Away,
+                    position: [0.0, 1.1, length / 2.0 - 1.5],
+                },
+            ],
+            lod: LodSettings::default(),
+            culling: CullingSettings::default(),
+            sky: SkySettings::default(),
+            stadium: None,
+        }
+    }
+}
+
+// posts on the end line, a crossbar and a net behind them, the sensor just past
+// the line and the end wall either side of the mouth; home defends the -z end
+pub fn goal(defending: Team, x: f32, mouth: f32) -> Vec<ArenaPiece> {
+    let (width, height, length) = BOARD_DIM;
+    let (prefix, wall, side, sensor, turn, color) = match defending {
+        Team::Away => (
+            "Enemy",
+            "FrontWall",
+            1.0,
+            PieceKind::GoalSensor,
+            0.0,
+            [1.0, 0.0, 0.0],
+        ),
+        Team::Home => (
+            "Home",
+            "BackWall",
+            -1.0,
+            PieceKind::HomeGoalSensor,
+            180.0,
+            [0.0, 0.0, 1.0],
+        ),
+    };
+    let line = side * length / 2.0;
+    let floor_top = height / 2.0;
+    let post = Vec3::new(0.1, 0.75, 0.1);
+    let net_depth = 0.75;
+
+    let mut pieces = vec![
+        ArenaPiece::new(
+            &format!("{}GoalRight", prefix),
+            PieceKind::GoalPost,
+            Vec3::new(x - mouth - post.x, floor_top + post.y, line),
+            post,
+        ),
+        ArenaPiece::new(
+            &format!("{}GoalLeft", prefix),
+            PieceKind::GoalPost,
+            Vec3::new(x + mouth + post.x, floor_top + post.y, line),
+            post,
+        ),
+        ArenaPiece::new(
+            &format!("{}Crossbar", prefix),
+            PieceKind::GoalPost,
+            Vec3::new(x, floor_top + post.y * 2.0 + 0.05, line),
+            Vec3::new(mouth + post.x * 2.0, 0.1, post.z),
+        ),
+        // the net is built open toward -z, so the home one is turned around
+        ArenaPiece {
+            color: [1.0; 3],
+            rotation: [0.0, turn, 0.0],
+            ..ArenaPiece::new(
+                &format!("{}GoalNet", prefix),
+                PieceKind::GoalNet,
+                Vec3::new(x, floor_top + post.y + 0.05, line + side * net_depth),
+                Vec3::new(mouth + post.x, post.y + 0.05, net_depth),
+            )
+        },
+        ArenaPiece::new(
+            &format!("{}GoalCollider", prefix),
+            sensor,
+            Vec3::new(x, floor_top + post.y, line + side * 0.1),
+            Vec3::new(mouth, post.y, 0.1),
+        ),
+    ];
+
+    let inner = [x - mouth - post.x * 2.0, x + mouth + post.x * 2.0];
+    for (name, from, to) in [
+        (format!("{}Right", wall), -width / 2.0, inner[0]),
+        (format!("{}Left", wall), inner[1], width / 2.0),
+    ] {
+        if to - from <= 0.0 {
+            continue;
+        }
+        pieces.push(
+            ArenaPiece::new(
+                &name,
+                PieceKind::Wall,
+                Vec3::new((from + to) / 2.0, 1.0, line + side * 0.2),
+                Vec3::new((to - from) / 2.0, 1.0, 0.2),
+            )
+            .hidden(),
+        );
+    }
+    for piece in pieces.iter_mut() {
+        if piece.kind == PieceKind::GoalPost {
+            piece.color = color;
+        }
+    }
+    pieces
+}
+
+// an open box, the side facing the pitch left out, as quads
+fn net_panels(half: Vec3) -> [[Vec3; 4]; 5] {
+    let Vec3 { x, y, z } = half;
+    [
+        // back
+        [
+            Vec3::new(-x, -y, z),
+            Vec3::new(x, -y, z),
+            Vec3::new(x, y, z),
+            Vec3::new(-x, y, z),
+        ],
+        // sides
+        [
+            Vec3::new(-x, -y, -z),
+            Vec3::new(-x, -y, z),
+            Vec3::new(-x, y, z),
+            Vec3::new(-x, y, -z),
+        ],
+        [
+            Vec3::new(x, -y, z),
+            Vec3::new(x, -y, -z),
+            Vec3::new(x, y, -z),
+            Vec3::new(x, y, z),
+        ],
+        // roof and the ground it rests on past the end of the floor
+        [
+            Vec3::new(-x, y, -z),
+            Vec3::new(-x, y, z),
+            Vec3::new(x, y, z),
+            Vec3::new(x, y, -z),
+        ],
+        [
+            Vec3::new(-x, -y, -z),
+            Vec3::new(x, -y, -z),
+            Vec3::new(x, -y, z),
+            Vec3::new(-x, -y, z),
+        ],
+    ]
+}
+
+fn net_triangles(half: Vec3) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for panel in net_panels(half) {
+        let base = vertices.len() as u32;
+        vertices.extend(panel);
+        indices.push([base, base + 1, base + 2]);
+        indices.push([base, base + 2, base + 3]);
+    }
+    (vertices, indices)
+}
+
+fn net_mesh(half: Vec3) -> Mesh {
+    let (vertices, indices) = net_triangles(half);
+    let normals: Vec<[f32; 3]> = vertices
+        .chunks(4)
+        .flat_map(|quad| {
+            let normal = (quad[1] - quad[0]).cross(quad[2] - quad[0]).normalize();
+            [normal.to_array(); 4]
+        })
+        .collect();
+    let uvs: Vec<[f32; 2]> = vertices
+        .chunks(4)
+        .flat_map(|_| [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]])
+        .collect();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vertices.iter().map(|v| v.to_array()).collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices.concat())));
+    mesh
+}
+
+impl Versioned for ArenaDescriptor {
+    const VERSION: u32 = 1;
+}
+
+impl ArenaDescriptor {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        storage::read(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::write(path, self)
+    }
+}
+
+// the descriptor the arena was built from and where it gets saved back to
+#[derive(Resource)]
+pub struct Arena {
+    pub descriptor: ArenaDescriptor,
+    pub path: PathBuf,
+}
+
+impl Arena {
+    // the --arena file, otherwise the default arena file if one was saved
+    pub fn open(path: Option<PathBuf>) -> Self {
+        let path = path.unwrap_or_else(default_arena_path);
+
+        let descriptor = match ArenaDescriptor::load(&path) {
+            Ok(descriptor) => playable(descriptor, &path).unwrap_or_else(|| {
+                warn!(
+                    "{} is not playable, using the default arena",
+                    path.display()
+                );
+                ArenaDescriptor::default()
+            }),
+            Err(err) => {
+                if path.exists() {
+                    warn!("Ignoring invalid arena {}: {}", path.display(), err);
+                }
+                ArenaDescriptor::default()
+            }
+        };
+        Self { descriptor, path }
+    }
+}
+
+// an arena saved under the old name is moved over once, so it isn't silently dropped.
+// if it can't be moved it is read from where it is
+fn default_arena_path() -> PathBuf {
+    let (legacy, path) = (PathBuf::from(LEGACY_DEFAULT_ARENA), PathBuf::from(DEFAULT_ARENA));
+    if path.exists() || !legacy.exists() {
+        return path;
+    }
+    match std::fs::rename(&legacy, &path) {
+        Ok(()) => {
+            info!("Moved {} to {}", legacy.display(), path.display());
+            path
+        }
+        Err(err) => {
+            warn!("Couldn't move {}: {}", legacy.display(), err);
+            legacy
+        }
+    }
+}
+
+// warns about every issue, and gives the descriptor back only if none are errors
+fn playable(descriptor: ArenaDescriptor, path: &Path) -> Option<ArenaDescriptor> {
+    let issues = arena_validation::validate(&descriptor);
+    for issue in issues.iter() {
+        warn!("{}: {}", path.display(), issue);
+    }
+    if issues.iter().any(|issue| issue.severity == Severity::Error) {
+        None
+    } else {
+        Some(descriptor)
+    }
+}
+
+#[derive(Resource)]
+struct ArenaWatch(Handle<ArenaAsset>);
+
+// only files under assets/ can go through the asset server
+fn watch_arena_system(mut commands: Commands, asset_server: Res<AssetServer>, arena: Res<Arena>) {
+    let Ok(relative) = arena.path.strip_prefix("assets") else {
+        info!(
+            "{} is outside assets/, it won't be reloaded",
+            arena.path.display()
+        );
+        return;
+    };
+    commands.insert_resource(ArenaWatch(asset_server.load(relative.to_path_buf())));
+}
+
+// edits on disk replace the descriptor, unless they are just the editor's own save
+fn arena_reload_system(
+    mut asset_events: EventReader<AssetEvent<ArenaAsset>>,
+    mut reload_events: EventWriter<ArenaReloadedEvent>,
+    mut arena: ResMut<Arena>,
+    assets: Res<Assets<ArenaAsset>>,
+    watch: Option<Res<ArenaWatch>>,
+) {
+    let Some(watch) = watch else {
+        asset_events.clear();
+        return;
+    };
+    for event in asset_events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+        if *handle != watch.0 {
+            continue;
+        }
+        let Some(ArenaAsset(descriptor)) = assets.get(handle) else {
+            continue;
+        };
+        if storage::to_string(descriptor) == storage::to_string(&arena.descriptor) {
+            continue;
+        }
+        let path = arena.path.clone();
+        let Some(descriptor) = playable(descriptor.clone(), &path) else {
+            warn!(
+                "{} is not playable, keeping the current arena",
+                path.display()
+            );
+            continue;
+        };
+        info!("Reloaded {}", path.display());
+        arena.descriptor = descriptor;
+        reload_events.send(ArenaReloadedEvent);
+    }
+}
+
+// links a spawned entity back to its entry in the descriptor
+#[derive(Component)]
+pub struct ArenaPieceId(pub usize);
+
+pub fn piece_collider(piece: &ArenaPiece) -> Collider {
+    let [hx, hy, hz] = piece.half_extents;
+    match (piece.kind, piece.shape) {
+        (PieceKind::Prop, PropShape::Sphere) => Collider::ball(hx),
+        (PieceKind::BoostPad, _) => Collider::cylinder(hy, hx),
+        (PieceKind::GoalNet, _) => {
+            let (vertices, indices) = net_triangles(Vec3::new(hx, hy, hz));
+            Collider::trimesh(vertices, indices)
+        }
+        _ => Collider::cuboid(hx, hy, hz),
+    }
+}
+
+pub fn spawn_piece(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    id: usize,
+    piece: &ArenaPiece,
+) -> Entity {
+    let [hx, hy, hz] = piece.half_extents;
+    let mut entity = commands.spawn((
+        piece_collider(piece),
+        ArenaPieceId(id),
+        Name::new(piece.name.clone()),
+    ));
+    // home defends the -z end, away the +z end
+    if piece.kind == PieceKind::GoalPost {
+        entity.insert(GoalPost(if piece.position[2] > 0.0 {
+            Team::Away
+        } else {
+            Team::Home
+        }));
+    }
+
+    if matches!(
+        piece.kind,
+        PieceKind::GoalSensor | PieceKind::HomeGoalSensor
+    ) {
+        entity.insert((
+            TransformBundle::from(piece.transform()),
+            Sensor,
+            ActiveCollisionTypes::default() | ActiveCollisionTypes::DYNAMIC_STATIC,
+            ActiveEvents::COLLISION_EVENTS,
+        ));
+        if piece.kind == PieceKind::GoalSensor {
+            entity.insert(EnemyGoal);
+        } else {
+            entity.insert(PlayerGoal);
+        }
+        return entity.id();
+    }
+
+    // kinematic players and the dynamic ball both have to be seen overlapping it
+    if piece.kind == PieceKind::BoostPad {
+        let [r, g, b] = piece.color;
+        entity
+            .insert((
+                TransformBundle::from(piece.transform()),
+                VisibilityBundle::default(),
+                Sensor,
+                ActiveCollisionTypes::default()
+                    | ActiveCollisionTypes::KINEMATIC_STATIC
+                    | ActiveCollisionTypes::DYNAMIC_STATIC,
+                BoostPad::new(piece.pad),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(Mesh::from(shape::Circle::new(hx))),
+                        material: materials.add(StandardMaterial {
+                            base_color: Color::rgba(r, g, b, 0.8),
+                            emissive: Color::rgb(r, g, b) * 2.0,
+                            alpha_mode: AlphaMode::Blend,
+                            ..default()
+                        }),
+                        // the circle faces +z, lay it flat just above the pad
+                        transform: Transform::from_xyz(0.0, hy + 0.01, 0.0)
+                            .with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+                        visibility: Visibility {
+                            is_visible: piece.visible,
+                        },
+                        ..default()
+                    },
+                    PadGlow,
+                ));
+            });
+        return entity.id();
+    }
+
+    // drawn by the instancing batches, see instancing.rs
+    if piece.kind == PieceKind::Prop {
+        entity.insert((TransformBundle::from(piece.transform()), RigidBody::Fixed));
+        return entity.id();
+    }
+
+    let [r, g, b] = piece.color;
+    let (mesh, material) = if piece.kind == PieceKind::GoalNet {
+        // a dead net, whatever the ball's own bounce
+        entity.insert(Restitution {
+            coefficient: 0.0,
+            combine_rule: CoefficientCombineRule::Min,
+        });
+        (
+            net_mesh(Vec3::new(hx, hy, hz)),
+            StandardMaterial {
+                base_color: Color::rgba(r, g, b, 0.4),
+                alpha_mode: AlphaMode::Blend,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            },
+        )
+    } else {
+        (
+            Mesh::from(shape::Box::new(hx * 2.0, hy * 2.0, hz * 2.0)),
+            StandardMaterial {
+                base_color: Color::rgb(r, g, b),
+                ..default()
+            },
+        )
+    };
+    entity.insert((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(material),
+            transform: piece.transform(),
+            visibility: Visibility {
+                is_visible: piece.visible,
+            },
+            ..default()
+        },
+        RigidBody::Fixed,
+    ));
+    entity.id()
+}
+
+fn spawn_arena_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut entities: ResMut<MatchEntities>,
+    arena: Res<Arena>,
+    pieces: Query<(), With<ArenaPieceId>>,
+) {
+    // built on the first Play, rematches keep the arena that's already there
+    if !pieces.is_empty() {
+        return;
+    }
+    for (id, piece) in arena.descriptor.pieces.iter().enumerate() {
+        let entity = spawn_piece(&mut commands, &mut meshes, &mut materials, id, piece);
+        match piece.kind {
+            PieceKind::GoalSensor => entities.goal = Some(entity),
+            PieceKind::HomeGoalSensor => entities.home_goal = Some(entity),
+            _ => {}
+        }
+    }
+}
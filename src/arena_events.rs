@@ -0,0 +1,146 @@
+use super::determinism::GameRng;
+use super::marks::{Ball, MatchEntity};
+use super::mutators::Mutators;
+use super::squash::BallMesh;
+use super::MatchAssets;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+const WIND_GUST_IMPULSE: f32 = 4.0;
+const BONUS_BALL_COUNT: usize = 2;
+const BONUS_BALL_LIFETIME: f32 = 20.0;
+const BIG_BALL_SCALE: f32 = 2.0;
+const BIG_BALL_DURATION: f32 = 15.0;
+
+pub struct ArenaEventsPlugin;
+
+impl Plugin for ArenaEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ArenaEvent>()
+            .add_system(arena_event_system)
+            .add_system(bonus_ball_system)
+            .add_system(big_ball_system);
+    }
+}
+
+// gameplay twists that can be fired by power-ups, mutators or outside sources
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaEvent {
+    WindGust(Vec3),
+    MultiBall,
+    BigBall,
+}
+
+impl ArenaEvent {
+    pub fn random_gust(rng: &mut impl Rng) -> Self {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        ArenaEvent::WindGust(Vec3::new(angle.cos(), 0.0, angle.sin()))
+    }
+}
+
+// extra balls bounce around for a while but never count as goals
+#[derive(Component)]
+pub struct BonusBall(Timer);
+
+#[derive(Component)]
+pub struct BigBall(Timer);
+
+fn arena_event_system(
+    mut commands: Commands,
+    mut arena_events: EventReader<ArenaEvent>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+    bonus_query: Query<Entity, With<BonusBall>>,
+    match_assets: Res<MatchAssets>,
+    mutators: Res<Mutators>,
+    mut rng: ResMut<GameRng>,
+) {
+    for ev in arena_events.iter() {
+        info!("Arena event: {:?}", ev);
+        match ev {
+            ArenaEvent::WindGust(direction) => {
+                for entity in ball_query.iter().map(|(e, _)| e).chain(bonus_query.iter()) {
+                    commands.entity(entity).insert(ExternalImpulse {
+                        impulse: direction.normalize_or_zero() * WIND_GUST_IMPULSE,
+                        torque_impulse: Vec3::ZERO,
+                    });
+                }
+            }
+            ArenaEvent::MultiBall => {
+                for _ in 0..BONUS_BALL_COUNT {
+                    let x = rng.rng.gen_range(-3.0..3.0);
+                    commands.spawn((
+                        PbrBundle {
+                            mesh: match_assets.ball_mesh.clone(),
+                            material: match_assets.ball_material.clone(),
+                            transform: Transform::from_xyz(x, 4.0, 0.0),
+                            ..default()
+                        },
+                        Collider::ball(0.5),
+                        Restitution::coefficient(1.0),
+                        RigidBody::Dynamic,
+                        Ccd::enabled(),
+                        Damping {
+                            angular_damping: 1.0,
+                            linear_damping: 0.5,
+                        },
+                        BonusBall(Timer::from_seconds(BONUS_BALL_LIFETIME, TimerMode::Once)),
+                        MatchEntity,
+                        Name::new("bonus ball"),
+                    ));
+                }
+            }
+            ArenaEvent::BigBall => {
+                for (entity, _) in ball_query.iter() {
+                    commands.entity(entity).insert((
+                        Collider::ball(0.5 * mutators.ball_scale() * BIG_BALL_SCALE),
+                        BigBall(Timer::from_seconds(BIG_BALL_DURATION, TimerMode::Once)),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn bonus_ball_system(
+    mut commands: Commands,
+    mut bonus_query: Query<(Entity, &mut BonusBall)>,
+    time: Res<Time>,
+) {
+    for (entity, mut bonus) in bonus_query.iter_mut() {
+        if bonus.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// the visual mesh sits two levels below the ball, see `spawn_match_entities`
+fn big_ball_system(
+    mut commands: Commands,
+    mut big_query: Query<(Entity, &mut BigBall, &Children)>,
+    children_query: Query<&Children>,
+    mut mesh_query: Query<&mut Transform, With<BallMesh>>,
+    mutators: Res<Mutators>,
+    time: Res<Time>,
+) {
+    // grown from and shrunk back to the tiny ball when that mutator is on
+    let base = mutators.ball_scale();
+    for (entity, mut big, children) in big_query.iter_mut() {
+        let done = big.0.tick(time.delta()).finished();
+        let scale = if done { base } else { base * BIG_BALL_SCALE };
+
+        for child in children.iter() {
+            for grandchild in children_query.get(*child).into_iter().flatten() {
+                if let Ok(mut mesh_tf) = mesh_query.get_mut(*grandchild) {
+                    mesh_tf.scale = Vec3::splat(scale);
+                }
+            }
+        }
+        if done {
+            commands
+                .entity(entity)
+                .insert(Collider::ball(0.5 * base))
+                .remove::<BigBall>();
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use super::arena::{ArenaDescriptor, ArenaPiece, PieceKind};
+use super::marks::Team;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+// pieces may sink this far into each other before it counts as an overlap, walls
+// resting on the floor slab touch it by a few centimetres
+const OVERLAP_TOLERANCE: f32 = 0.1;
+// the walkable grid used to check the goals can be reached
+const CELL: f32 = 0.5;
+const BALL_RADIUS: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Warning,
+    // the game can't run on this arena
+    Error,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArenaIssue {
+    pub severity: Severity,
+    pub piece: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ArenaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match &self.piece {
+            Some(piece) => write!(f, "{}: {}: {}", level, piece, self.message),
+            None => write!(f, "{}: {}", level, self.message),
+        }
+    }
+}
+
+struct Bounds {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Bounds {
+    fn of(piece: &ArenaPiece) -> Self {
+        let (min, max) = piece.bounds();
+        Self { min, max }
+    }
+
+    fn overlap(&self, other: &Bounds) -> Vec3 {
+        self.max.min(other.max) - self.min.max(other.min)
+    }
+
+    fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+// a net's bounds are mostly the hollow the ball is meant to end up in, pads are
+// sensors the ball rolls straight over
+fn is_solid(piece: &ArenaPiece) -> bool {
+    !matches!(
+        piece.kind,
+        PieceKind::GoalSensor
+            | PieceKind::HomeGoalSensor
+            | PieceKind::GoalNet
+            | PieceKind::BoostPad
+    )
+}
+
+pub fn validate(arena: &ArenaDescriptor) -> Vec<ArenaIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |severity, piece: Option<&ArenaPiece>, message: String| {
+        issues.push(ArenaIssue {
+            severity,
+            piece: piece.map(|p| p.name.clone()),
+            message,
+        })
+    };
+
+    for piece in arena.pieces.iter() {
+        if piece.extents().cmple(Vec3::ZERO).any() {
+            issue(
+                Severity::Error,
+                Some(piece),
+                "has a zero or negative size".to_string(),
+            );
+        }
+    }
+
+    // visibility volumes that can never hold the camera, or show groups nobody is in
+    for volume in arena.culling.volumes.iter() {
+        if Vec3::from(volume.min).cmpgt(Vec3::from(volume.max)).any() {
+            issue(
+                Severity::Warning,
+                None,
+                format!("visibility volume {} has min above max", volume.name),
+            );
+        }
+        for group in volume.visible_groups.iter() {
+            if !arena
+                .pieces
+                .iter()
+                .any(|piece| piece.group.as_ref() == Some(group))
+            {
+                issue(
+                    Severity::Warning,
+                    None,
+                    format!(
+                        "visibility volume {} lists empty group {}",
+                        volume.name, group
+                    ),
+                );
+            }
+        }
+    }
+
+    // overlapping solids
+    let solids: Vec<_> = arena
+        .pieces
+        .iter()
+        .filter(|piece| is_solid(piece))
+        .map(|piece| (piece, Bounds::of(piece)))
+        .collect();
+    for (i, (a, a_bounds)) in solids.iter().enumerate() {
+        for (b, b_bounds) in solids.iter().skip(i + 1) {
+            let overlap = a_bounds.overlap(b_bounds);
+            if overlap.min_element() > OVERLAP_TOLERANCE {
+                issue(
+                    Severity::Warning,
+                    Some(*a),
+                    format!("overlaps {} by {:.2}m", b.name, overlap.min_element()),
+                );
+            }
+        }
+    }
+
+    // spawn points
+    let floors: Vec<_> = solids
+        .iter()
+        .filter(|(piece, _)| piece.kind == PieceKind::Floor)
+        .collect();
+    if floors.is_empty() {
+        issue(Severity::Error, None, "has no floor".to_string());
+    }
+    for team in [Team::Home, Team::Away] {
+        let spawns: Vec<_> = arena.spawns.iter().filter(|s| s.team == team).collect();
+        if spawns.is_empty() {
+            issue(
+                Severity::Error,
+                None,
+                format!("has no {} spawn point", team.name()),
+            );
+        }
+        for spawn in spawns {
+            let position = Vec3::from(spawn.position);
+            if let Some((piece, _)) = solids
+                .iter()
+                .find(|(piece, bounds)| piece.kind != PieceKind::Floor && bounds.contains(position))
+            {
+                issue(
+                    Severity::Error,
+                    Some(*piece),
+                    format!("covers the {} spawn point", team.name()),
+                );
+            }
+            let supported = floors.iter().any(|(_, bounds)| {
+                position.x >= bounds.min.x
+                    && position.x <= bounds.max.x
+                    && position.z >= bounds.min.z
+                    && position.z <= bounds.max.z
+                    && position.y >= bounds.max.y
+            });
+            if !supported {
+                issue(
+                    Severity::Error,
+                    None,
+                    format!("{} spawn point is not above the floor", team.name()),
+                );
+            }
+        }
+    }
+
+    // goal sensors, the match expects exactly one at the enemy end and at most
+    // one at the home end
+    let count = |kind: PieceKind| arena.pieces.iter().filter(|p| p.kind == kind).count();
+    match count(PieceKind::GoalSensor) {
+        0 => issue(Severity::Error, None, "has no goal sensor".to_string()),
+        1 => {}
+        n => issue(
+            Severity::Error,
+            None,
+            format!("has {} goal sensors, only one is supported", n),
+        ),
+    }
+    if count(PieceKind::HomeGoalSensor) > 1 {
+        issue(
+            Severity::Error,
+            None,
+            "has more than one home goal sensor".to_string(),
+        );
+    }
+    let sensors: Vec<_> = arena
+        .pieces
+        .iter()
+        .filter(|piece| {
+            matches!(
+                piece.kind,
+                PieceKind::GoalSensor | PieceKind::HomeGoalSensor
+            )
+        })
+        .collect();
+    let floor_top = floors
+        .iter()
+        .map(|(_, bounds)| bounds.max.y)
+        .fold(f32::MIN, f32::max);
+    for sensor in sensors.iter() {
+        let bounds = Bounds::of(sensor);
+        if bounds.min.y > floor_top + BALL_RADIUS * 2.0 {
+            issue(
+                Severity::Warning,
+                Some(*sensor),
+                "floats high enough for a rolling ball to pass underneath".to_string(),
+            );
+        }
+        if let Some((piece, _)) = solids.iter().find(|(piece, solid)| {
+            piece.kind != PieceKind::Floor
+                && solid.contains(bounds.min)
+                && solid.contains(bounds.max)
+        }) {
+            issue(
+                Severity::Error,
+                Some(*sensor),
+                format!("is buried inside {}", piece.name),
+            );
+        }
+    }
+
+    // every goal has to be reachable by a ball rolling from the home spawn
+    let home = arena.spawns.iter().find(|s| s.team == Team::Home);
+    if let (Some(home), false) = (home, floors.is_empty()) {
+        let reachable = reachable_cells(&solids, &floors, Vec3::from(home.position));
+        for sensor in sensors.iter() {
+            let bounds = Bounds::of(sensor);
+            if !reachable.touches(&bounds) {
+                issue(
+                    Severity::Error,
+                    Some(*sensor),
+                    "can't be reached from the home spawn".to_string(),
+                );
+            }
+        }
+    }
+
+    issues
+}
+
+struct CellGrid {
+    origin: Vec2,
+    width: usize,
+    height: usize,
+    open: Vec<bool>,
+}
+
+impl CellGrid {
+    fn cell(&self, x: f32, z: f32) -> Option<usize> {
+        let cx = ((x - self.origin.x) / CELL).floor();
+        let cz = ((z - self.origin.y) / CELL).floor();
+        if cx < 0.0 || cz < 0.0 || cx as usize >= self.width || cz as usize >= self.height {
+            return None;
+        }
+        Some(cz as usize * self.width + cx as usize)
+    }
+
+    fn center(&self, i: usize) -> Vec2 {
+        self.origin + (Vec2::new((i % self.width) as f32, (i / self.width) as f32) + 0.5) * CELL
+    }
+
+    // whether any open cell lies under the footprint, give or take a ball radius
+    fn touches(&self, bounds: &Bounds) -> bool {
+        (0..self.open.len()).any(|i| {
+            let c = self.center(i);
+            self.open[i]
+                && c.x >= bounds.min.x - BALL_RADIUS
+                && c.x <= bounds.max.x + BALL_RADIUS
+                && c.y >= bounds.min.z - BALL_RADIUS
+                && c.y <= bounds.max.z + BALL_RADIUS
+        })
+    }
+}
+
+// flood fills the floor from the start point, treating anything standing in the
+// ball's height band as a wall
+fn reachable_cells(
+    solids: &[(&ArenaPiece, Bounds)],
+    floors: &[&(&ArenaPiece, Bounds)],
+    start: Vec3,
+) -> CellGrid {
+    let min = floors
+        .iter()
+        .fold(Vec3::splat(f32::MAX), |m, (_, b)| m.min(b.min));
+    let max = floors
+        .iter()
+        .fold(Vec3::splat(f32::MIN), |m, (_, b)| m.max(b.max));
+    let floor_top = max.y;
+    let origin = Vec2::new(min.x, min.z);
+    let width = ((max.x - min.x) / CELL).ceil() as usize;
+    let height = ((max.z - min.z) / CELL).ceil() as usize;
+    let mut grid = CellGrid {
+        origin,
+        width,
+        height,
+        open: vec![false; width * height],
+    };
+
+    let blocked = |c: Vec2| {
+        let on_floor = floors
+            .iter()
+            .any(|(_, b)| c.x >= b.min.x && c.x <= b.max.x && c.y >= b.min.z && c.y <= b.max.z);
+        !on_floor
+            || solids.iter().any(|(piece, b)| {
+                piece.kind != PieceKind::Floor
+                    && b.min.y < floor_top + BALL_RADIUS * 2.0
+                    && b.max.y > floor_top
+                    && c.x > b.min.x
+                    && c.x < b.max.x
+                    && c.y > b.min.z
+                    && c.y < b.max.z
+            })
+    };
+
+    let Some(first) = grid.cell(start.x, start.z) else {
+        return grid;
+    };
+    let mut queue = VecDeque::from([first]);
+    let mut seen = vec![false; width * height];
+    seen[first] = true;
+    while let Some(i) = queue.pop_front() {
+        if blocked(grid.center(i)) {
+            continue;
+        }
+        grid.open[i] = true;
+        let (x, z) = (i % width, i / width);
+        let neighbours = [
+            (x > 0).then(|| i - 1),
+            (x + 1 < width).then(|| i + 1),
+            (z > 0).then(|| i - width),
+            (z + 1 < height).then(|| i + width),
+        ];
+        for n in neighbours.into_iter().flatten() {
+            if !seen[n] {
+                seen[n] = true;
+                queue.push_back(n);
+            }
+        }
+    }
+    grid
+}
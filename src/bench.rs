@@ -0,0 +1,146 @@
+use super::cli::StartupArgs;
+use super::determinism::GameRng;
+use super::marks::{MatchEntity, Player};
+use super::perf_overlay::PhysicsCounters;
+use super::rematch::RematchEvent;
+use super::MatchAssets;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+const DEFAULT_BALLS: usize = 200;
+const DEFAULT_FRAMES: u32 = 600;
+// frames left out of the averages while the match spawns and shaders compile
+const WARMUP_FRAMES: u32 = 60;
+
+// usage: --bench [balls] [--bench-frames <n>]
+pub struct BenchPlugin {
+    pub balls: usize,
+    pub frames: u32,
+}
+
+impl BenchPlugin {
+    pub fn from_args(args: &StartupArgs) -> Option<Self> {
+        let balls = args.bench?;
+        Some(Self {
+            balls: balls.unwrap_or(DEFAULT_BALLS),
+            frames: args.bench_frames.unwrap_or(DEFAULT_FRAMES),
+        })
+    }
+}
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        info!(
+            "Benchmarking {} balls over {} frames",
+            self.balls, self.frames
+        );
+        app.insert_resource(Bench {
+            balls: self.balls,
+            frames: self.frames,
+            ..default()
+        })
+        .add_startup_system(start_bench_system)
+        .add_system(bench_launch_system)
+        .add_system_to_stage(CoreStage::Last, bench_measure_system);
+    }
+}
+
+#[derive(Resource, Default)]
+struct Bench {
+    balls: usize,
+    frames: u32,
+    launched: bool,
+    // measured frames since the launch
+    frame: u32,
+    frame_time: f64,
+    step_time: f64,
+}
+
+// straight into a match, rapier only keeps timings once its counters are on, and
+// only with its profiler feature, which Cargo.toml turns on
+fn start_bench_system(
+    mut rematch_events: EventWriter<RematchEvent>,
+    mut counters: ResMut<PhysicsCounters>,
+    mut rapier_context: ResMut<RapierContext>,
+) {
+    rematch_events.send(RematchEvent);
+    counters.acquire(&mut rapier_context);
+}
+
+// the whole field of balls goes in at once, flying every which way
+fn bench_launch_system(
+    mut commands: Commands,
+    mut bench: ResMut<Bench>,
+    mut rng: ResMut<GameRng>,
+    match_assets: Res<MatchAssets>,
+    player_query: Query<(), With<Player>>,
+) {
+    if bench.launched || player_query.is_empty() {
+        return;
+    }
+    bench.launched = true;
+
+    for _ in 0..bench.balls {
+        let position = Vec3::new(
+            rng.rng.gen_range(-4.0..4.0),
+            rng.rng.gen_range(1.0..6.0),
+            rng.rng.gen_range(-9.0..9.0),
+        );
+        let velocity = Vec3::new(
+            rng.rng.gen_range(-10.0..10.0),
+            rng.rng.gen_range(0.0..8.0),
+            rng.rng.gen_range(-10.0..10.0),
+        );
+        commands.spawn((
+            PbrBundle {
+                mesh: match_assets.ball_mesh.clone(),
+                material: match_assets.ball_material.clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            Collider::ball(0.5),
+            Restitution::coefficient(1.0),
+            RigidBody::Dynamic,
+            Velocity::linear(velocity),
+            Ccd::enabled(),
+            MatchEntity,
+            Name::new("bench ball"),
+        ));
+    }
+}
+
+fn bench_measure_system(
+    mut bench: ResMut<Bench>,
+    mut exit: EventWriter<AppExit>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+    mut since_launch: Local<u32>,
+) {
+    if !bench.launched {
+        return;
+    }
+    *since_launch += 1;
+    if *since_launch <= WARMUP_FRAMES {
+        return;
+    }
+
+    bench.frame += 1;
+    bench.frame_time += time.delta_seconds_f64() * 1000.0;
+    bench.step_time += rapier_context.pipeline.counters.step_time();
+    if bench.frame < bench.frames {
+        return;
+    }
+
+    let frames = bench.frame as f64;
+    info!(
+        "Bench: {} balls, {} frames, {:.2} ms per frame ({:.0} fps), {:.2} ms per physics step",
+        bench.balls,
+        bench.frame,
+        bench.frame_time / frames,
+        1000.0 * frames / bench.frame_time,
+        bench.step_time / frames
+    );
+    exit.send(AppExit);
+}
@@ -0,0 +1,382 @@
+use super::devices::KeyboardSet;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy_egui::egui;
+use std::collections::HashMap;
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputMap::default())
+            .insert_resource(ScanNames::default())
+            .insert_resource(Rebinding::default())
+            .add_system(learn_scan_names_system)
+            .add_system(capture_rebind_system);
+    }
+}
+
+// what each keyboard set binds, in the order of `InputMap::sets`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Kick,
+    Sprint,
+    Dash,
+    Jump,
+    Boost,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Kick,
+        Action::Sprint,
+        Action::Dash,
+        Action::Jump,
+        Action::Boost,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "Up",
+            Action::MoveBack => "Down",
+            Action::MoveLeft => "Left",
+            Action::MoveRight => "Right",
+            Action::Kick => "Kick",
+            Action::Sprint => "Sprint",
+            Action::Dash => "Dash",
+            Action::Jump => "Jump",
+            Action::Boost => "Boost",
+        }
+    }
+}
+
+// physical positions of the WASD cluster and space bar, as reported by winit
+#[cfg(not(target_os = "macos"))]
+const WASD_SPACE: [u32; 5] = [17, 31, 30, 32, 57];
+#[cfg(target_os = "macos")]
+const WASD_SPACE: [u32; 5] = [13, 1, 0, 2, 49];
+
+// scancodes follow the key's position, so movement stays under the same fingers on
+// AZERTY or Dvorak; keycodes follow the printed symbol, for keys where that matters
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Scan(u32),
+    Key(KeyCode),
+}
+
+pub struct KeyState<'a> {
+    pub keys: &'a Input<KeyCode>,
+    pub scans: &'a Input<ScanCode>,
+}
+
+impl Binding {
+    pub fn pressed(&self, state: &KeyState) -> bool {
+        match self {
+            Binding::Scan(code) => state.scans.pressed(ScanCode(*code)),
+            Binding::Key(key) => state.keys.pressed(*key),
+        }
+    }
+
+    pub fn just_pressed(&self, state: &KeyState) -> bool {
+        match self {
+            Binding::Scan(code) => state.scans.just_pressed(ScanCode(*code)),
+            Binding::Key(key) => state.keys.just_pressed(*key),
+        }
+    }
+
+    pub fn just_released(&self, state: &KeyState) -> bool {
+        match self {
+            Binding::Scan(code) => state.scans.just_released(ScanCode(*code)),
+            Binding::Key(key) => state.keys.just_released(*key),
+        }
+    }
+}
+
+// every action's keys and buttons; game code asks this rather than for raw keys
+#[derive(Resource)]
+pub struct InputMap {
+    // one binding per Action for each keyboard set
+    pub sets: [[Binding; 9]; 2],
+    // pausing isn't tied to a player, any keyboard can do it
+    pub pause: Binding,
+    // sticks always move, only the buttons are mapped
+    pub pad_kick: Vec<GamepadButtonType>,
+    pub pad_sprint: GamepadButtonType,
+    pub pad_dash: GamepadButtonType,
+    pub pad_jump: GamepadButtonType,
+    pub pad_boost: GamepadButtonType,
+    pub pad_pause: GamepadButtonType,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            sets: [
+                {
+                    let [up, down, left, right, kick] = WASD_SPACE.map(Binding::Scan);
+                    [
+                        up,
+                        down,
+                        left,
+                        right,
+                        kick,
+                        Binding::Key(KeyCode::LShift),
+                        Binding::Key(KeyCode::LControl),
+                        Binding::Key(KeyCode::LAlt),
+                        Binding::Key(KeyCode::Tab),
+                    ]
+                },
+                [
+                    Binding::Key(KeyCode::Up),
+                    Binding::Key(KeyCode::Down),
+                    Binding::Key(KeyCode::Left),
+                    Binding::Key(KeyCode::Right),
+                    Binding::Key(KeyCode::RControl),
+                    Binding::Key(KeyCode::RShift),
+                    Binding::Key(KeyCode::RAlt),
+                    Binding::Key(KeyCode::Numpad0),
+                    Binding::Key(KeyCode::Numpad1),
+                ],
+            ],
+            pause: Binding::Key(KeyCode::P),
+            // A, or X for players used to kicking with it
+            pad_kick: vec![GamepadButtonType::South, GamepadButtonType::West],
+            pad_sprint: GamepadButtonType::RightTrigger2,
+            pad_dash: GamepadButtonType::East,
+            pad_jump: GamepadButtonType::North,
+            pad_boost: GamepadButtonType::RightTrigger,
+            pad_pause: GamepadButtonType::Start,
+        }
+    }
+}
+
+impl InputMap {
+    pub fn key(&self, set: KeyboardSet, action: Action) -> &Binding {
+        &self.sets[set as usize][action as usize]
+    }
+
+    pub fn pause_pressed(
+        &self,
+        keys: &KeyState,
+        gamepads: &Gamepads,
+        buttons: &Input<GamepadButton>,
+    ) -> bool {
+        // escape pauses whatever pause is bound to
+        self.pause.just_pressed(keys)
+            || keys.keys.just_pressed(KeyCode::Escape)
+            || gamepads
+                .iter()
+                .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, self.pad_pause)))
+    }
+}
+
+// winit reports the symbol printed on each key alongside its scancode, so the
+// names in the UI match whatever layout is active once a key has been pressed
+#[derive(Resource, Default)]
+pub struct ScanNames(HashMap<u32, KeyCode>);
+
+impl ScanNames {
+    pub fn display(&self, binding: &Binding) -> String {
+        match binding {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Scan(code) => match self.0.get(code) {
+                Some(key) => format!("{:?}", key),
+                None => default_scan_name(*code),
+            },
+        }
+    }
+}
+
+// until a key is seen we only know where it sits on a US board
+fn default_scan_name(code: u32) -> String {
+    match WASD_SPACE.iter().position(|c| *c == code) {
+        Some(index) => ["W", "S", "A", "D", "Space"][index].to_string(),
+        None => format!("Scan {}", code),
+    }
+}
+
+fn learn_scan_names_system(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut names: ResMut<ScanNames>,
+) {
+    for event in keyboard_events.iter() {
+        if let Some(key) = event.key_code {
+            if names.0.get(&event.scan_code) != Some(&key) {
+                names.0.insert(event.scan_code, key);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RebindTarget {
+    Key(usize, Action),
+    Pause,
+    PadKick,
+    PadSprint,
+    PadDash,
+    PadJump,
+    PadBoost,
+    PadPause,
+}
+
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    pending: Option<RebindTarget>,
+    // bind the printed symbol rather than the physical key
+    pub by_symbol: bool,
+}
+
+impl Rebinding {
+    // escape is cancelling the capture, not pausing
+    pub fn capturing(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+fn capture_rebind_system(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+    buttons: Res<Input<GamepadButton>>,
+) {
+    let Some(target) = rebinding.pending else {
+        keyboard_events.clear();
+        return;
+    };
+
+    for event in keyboard_events.iter() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        if event.key_code == Some(KeyCode::Escape) {
+            rebinding.pending = None;
+            return;
+        }
+        let binding = match (rebinding.by_symbol, event.key_code) {
+            (true, Some(key)) => Binding::Key(key),
+            _ => Binding::Scan(event.scan_code),
+        };
+        match target {
+            RebindTarget::Key(set, action) => input_map.sets[set][action as usize] = binding,
+            RebindTarget::Pause => input_map.pause = binding,
+            // pad actions wait for a button
+            RebindTarget::PadKick
+            | RebindTarget::PadSprint
+            | RebindTarget::PadDash
+            | RebindTarget::PadJump
+            | RebindTarget::PadBoost
+            | RebindTarget::PadPause => continue,
+        }
+        rebinding.pending = None;
+        return;
+    }
+
+    let Some(button) = buttons.get_just_pressed().next() else {
+        return;
+    };
+    match target {
+        RebindTarget::PadKick => input_map.pad_kick = vec![button.button_type],
+        RebindTarget::PadSprint => input_map.pad_sprint = button.button_type,
+        RebindTarget::PadDash => input_map.pad_dash = button.button_type,
+        RebindTarget::PadJump => input_map.pad_jump = button.button_type,
+        RebindTarget::PadBoost => input_map.pad_boost = button.button_type,
+        RebindTarget::PadPause => input_map.pad_pause = button.button_type,
+        RebindTarget::Key(..) | RebindTarget::Pause => return,
+    }
+    rebinding.pending = None;
+}
+
+fn rebind_button(ui: &mut egui::Ui, rebinding: &mut Rebinding, target: RebindTarget, text: String) {
+    let text = if rebinding.pending == Some(target) {
+        match target {
+            RebindTarget::PadKick
+            | RebindTarget::PadSprint
+            | RebindTarget::PadDash
+            | RebindTarget::PadJump
+            | RebindTarget::PadBoost
+            | RebindTarget::PadPause => "press a button...".to_string(),
+            RebindTarget::Key(..) | RebindTarget::Pause => "press a key...".to_string(),
+        }
+    } else {
+        text
+    };
+    if ui.button(text).clicked() {
+        rebinding.pending = Some(target);
+    }
+}
+
+fn pad_names(buttons: &[GamepadButtonType]) -> String {
+    let names: Vec<String> = buttons
+        .iter()
+        .map(|button| format!("{:?}", button))
+        .collect();
+    names.join(" / ")
+}
+
+pub fn bindings_ui(
+    ui: &mut egui::Ui,
+    input_map: &InputMap,
+    rebinding: &mut Rebinding,
+    names: &ScanNames,
+) {
+    egui::Grid::new("key_bindings").show(ui, |ui| {
+        ui.label("");
+        ui.label("Set A");
+        ui.label("Set B");
+        ui.label("Gamepad");
+        ui.end_row();
+        for action in Action::ALL {
+            ui.label(action.name());
+            for set in 0..input_map.sets.len() {
+                let text = names.display(&input_map.sets[set][action as usize]);
+                rebind_button(ui, rebinding, RebindTarget::Key(set, action), text);
+            }
+            match action {
+                Action::Kick => {
+                    let text = pad_names(&input_map.pad_kick);
+                    rebind_button(ui, rebinding, RebindTarget::PadKick, text);
+                }
+                Action::Sprint => {
+                    let text = pad_names(&[input_map.pad_sprint]);
+                    rebind_button(ui, rebinding, RebindTarget::PadSprint, text);
+                }
+                Action::Dash => {
+                    let text = pad_names(&[input_map.pad_dash]);
+                    rebind_button(ui, rebinding, RebindTarget::PadDash, text);
+                }
+                Action::Jump => {
+                    let text = pad_names(&[input_map.pad_jump]);
+                    rebind_button(ui, rebinding, RebindTarget::PadJump, text);
+                }
+                Action::Boost => {
+                    let text = pad_names(&[input_map.pad_boost]);
+                    rebind_button(ui, rebinding, RebindTarget::PadBoost, text);
+                }
+                _ => {}
+            }
+            ui.end_row();
+        }
+        ui.label("Pause");
+        rebind_button(
+            ui,
+            rebinding,
+            RebindTarget::Pause,
+            names.display(&input_map.pause),
+        );
+        ui.label("");
+        let text = pad_names(&[input_map.pad_pause]);
+        rebind_button(ui, rebinding, RebindTarget::PadPause, text);
+        ui.end_row();
+    });
+    ui.checkbox(&mut rebinding.by_symbol, "Bind by printed symbol");
+}
@@ -0,0 +1,215 @@
+use super::cli::StartupArgs;
+use super::determinism::GameRng;
+use super::game_state::GameState;
+use super::input::{LocalInputs, PlayerInput};
+use super::marks::{MatchEntity, Player, PlayerIndex};
+use super::net::networked;
+use super::player::{move_player, player_input, Facing};
+use super::BOARD_DIM;
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+// a full meter lasts two seconds of boosting
+const BOOST_BURN: f32 = 0.5;
+// extra speed on top of running, and how quickly it builds and fades
+const BOOST_MAX_SPEED: f32 = 10.0;
+const BOOST_ACCEL: f32 = 30.0;
+const BOOST_DECAY: f32 = 15.0;
+const PICKUP_FUEL: f32 = 0.25;
+const PICKUP_RADIUS: f32 = 0.3;
+const PLAYER_RADIUS: f32 = 0.5;
+// a kickoff's worth to start the match with
+const STARTING_FUEL: f32 = 0.33;
+// the player's centre is this far above a pickup lying on the floor
+const PICKUP_REACH: f32 = 1.2;
+pub const PICKUP_INTERVAL: f32 = 4.0;
+pub const MAX_PICKUPS: usize = 6;
+// kept clear of the walls and goal mouths
+const PICKUP_INSET: f32 = 1.0;
+
+pub struct BoostMeterPlugin;
+
+impl Plugin for BoostMeterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_pickups)
+            .init_resource::<PickupSpawner>();
+        // online sessions burn and collect on their own ticks so both peers agree
+        let networked = app
+            .world
+            .get_resource::<StartupArgs>()
+            .map_or(false, networked);
+        if !networked {
+            app.add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(boost_thrust_system.after(move_player))
+                    .with_system(pickup_spawner_system)
+                    .with_system(pickup_collect_system),
+            );
+        }
+    }
+}
+
+// boost left to burn from 0 to 1, and the extra speed it is currently giving
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct BoostMeter {
+    pub fuel: f32,
+    speed: f32,
+}
+
+impl Default for BoostMeter {
+    fn default() -> Self {
+        Self {
+            fuel: STARTING_FUEL,
+            speed: 0.0,
+        }
+    }
+}
+
+// a canister on the floor, whoever runs through it first gets the fuel
+#[derive(Component)]
+pub struct BoostPickup;
+
+#[derive(Resource)]
+pub struct PickupAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Resource)]
+struct PickupSpawner(Timer);
+
+impl Default for PickupSpawner {
+    fn default() -> Self {
+        Self(Timer::from_seconds(PICKUP_INTERVAL, TimerMode::Repeating))
+    }
+}
+
+fn setup_pickups(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PickupAssets {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: PICKUP_RADIUS,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::ORANGE,
+            emissive: Color::ORANGE,
+            ..default()
+        }),
+    });
+}
+
+// the burn builds speed the way the player is heading and it fades again once
+// the key is let go or the meter runs dry
+fn boost_thrust_system(
+    mut player_query: Query<
+        (
+            &PlayerIndex,
+            &Facing,
+            &mut BoostMeter,
+            &mut KinematicCharacterController,
+        ),
+        With<Player>,
+    >,
+    input: Res<PlayerInput>,
+    locals: Res<LocalInputs>,
+    time: Res<Time>,
+) {
+    for (index, facing, mut meter, mut player_ctrl) in player_query.iter_mut() {
+        let boosting = player_input(index, &input, &locals).map_or(false, |input| input.boost);
+        let thrust = boost_thrust(boosting, time.delta_seconds(), facing, &mut meter);
+        if thrust != Vec3::ZERO {
+            player_ctrl.translation = Some(player_ctrl.translation.unwrap_or_default() + thrust);
+        }
+    }
+}
+
+// one step of the burn, local play and online sessions both boost through here
+pub fn boost_thrust(boosting: bool, dt: f32, facing: &Facing, meter: &mut BoostMeter) -> Vec3 {
+    if boosting && meter.fuel > 0.0 {
+        meter.fuel = (meter.fuel - BOOST_BURN * dt).max(0.0);
+        meter.speed = (meter.speed + BOOST_ACCEL * dt).min(BOOST_MAX_SPEED);
+    } else {
+        meter.speed = (meter.speed - BOOST_DECAY * dt).max(0.0);
+    }
+    facing.0 * meter.speed * dt
+}
+
+// drops a pickup somewhere on the board every so often, up to MAX_PICKUPS at once
+fn pickup_spawner_system(
+    mut commands: Commands,
+    mut spawner: ResMut<PickupSpawner>,
+    mut rng: ResMut<GameRng>,
+    assets: Res<PickupAssets>,
+    pickup_query: Query<(), With<BoostPickup>>,
+    time: Res<Time>,
+) {
+    if !spawner.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    if pickup_query.iter().count() >= MAX_PICKUPS {
+        return;
+    }
+    spawn_pickup(&mut commands, &assets, &mut rng.rng);
+}
+
+pub fn spawn_pickup(commands: &mut Commands, assets: &PickupAssets, rng: &mut impl Rng) {
+    let (width, height, length) = BOARD_DIM;
+    let half = Vec2::new(width, length) / 2.0 - Vec2::splat(PICKUP_INSET);
+    let position = Vec3::new(
+        rng.gen_range(-half.x..half.x),
+        height / 2.0 + PICKUP_RADIUS,
+        rng.gen_range(-half.y..half.y),
+    );
+    commands.spawn((
+        PbrBundle {
+            mesh: assets.mesh.clone(),
+            material: assets.material.clone(),
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        NotShadowCaster,
+        BoostPickup,
+        // cleared with the ball and players on a rematch
+        MatchEntity,
+        Name::new("boost pickup"),
+    ));
+}
+
+fn pickup_collect_system(
+    mut commands: Commands,
+    mut player_query: Query<(&Transform, &mut BoostMeter), With<Player>>,
+    pickup_query: Query<(Entity, &Transform), With<BoostPickup>>,
+) {
+    for (pickup_entity, pickup_tf) in pickup_query.iter() {
+        let collected = player_query
+            .iter_mut()
+            .any(|(player_tf, mut meter)| collect_pickup(pickup_tf, player_tf, &mut meter));
+        if collected {
+            commands.entity(pickup_entity).despawn_recursive();
+        }
+    }
+}
+
+// tops up the meter if the player is running through the pickup
+pub fn collect_pickup(
+    pickup_tf: &Transform,
+    player_tf: &Transform,
+    meter: &mut BoostMeter,
+) -> bool {
+    let mut offset = pickup_tf.translation - player_tf.translation;
+    let reach = offset.y.abs() <= PICKUP_REACH;
+    offset.y = 0.0;
+    // a full meter leaves the pickup for someone who needs it
+    if !reach || offset.length() > PICKUP_RADIUS + PLAYER_RADIUS || meter.fuel >= 1.0 {
+        return false;
+    }
+    meter.fuel = (meter.fuel + PICKUP_FUEL).min(1.0);
+    true
+}
@@ -0,0 +1,176 @@
+use super::arena::PadEffect;
+use super::cli::StartupArgs;
+use super::game_state::GameState;
+use super::marks::{Ball, Player};
+use super::net;
+use std::time::Duration;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+// how long a pad stays dark after it has been used
+const PAD_COOLDOWN: f32 = 5.0;
+const SPEED_BOOST_SCALE: f32 = 1.5;
+const SPEED_BOOST_TIME: f32 = 2.0;
+// launch pads kick the ball on along the way the pad faces, and a little up
+const LAUNCH_POWER: f32 = 10.0;
+const LAUNCH_LIFT: f32 = 0.4;
+
+pub struct BoostPadPlugin;
+
+impl Plugin for BoostPadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(pad_glow_system.after(boost_pad_system)),
+        );
+
+        // rollback sessions use the pads and run out boosts in their own schedule
+        let rollback = app
+            .world
+            .get_resource::<StartupArgs>()
+            .map_or(false, net::rollback);
+        if !rollback {
+            app.add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(boost_pad_system)
+                    .with_system(speed_boost_system),
+            );
+        }
+    }
+}
+
+// a pad spawned from the arena, dark until its cooldown runs out
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct BoostPad {
+    effect: PadEffect,
+    cooldown: Timer,
+}
+
+impl BoostPad {
+    pub fn new(effect: PadEffect) -> Self {
+        let mut cooldown = Timer::from_seconds(PAD_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self { effect, cooldown }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+}
+
+// the glowing disc on top of a pad, dimmed while the pad recharges
+#[derive(Component)]
+pub struct PadGlow;
+
+// a player picked up a speed pad, move_player scales its speed until this runs out
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct SpeedBoost(Timer);
+
+impl SpeedBoost {
+    pub fn scale(&self) -> f32 {
+        if self.0.finished() {
+            1.0
+        } else {
+            SPEED_BOOST_SCALE
+        }
+    }
+
+    // true once the boost has run out
+    pub fn run_down(&mut self, delta: Duration) -> bool {
+        self.0.tick(delta).finished()
+    }
+}
+
+fn boost_pad_system(
+    mut commands: Commands,
+    mut pad_query: Query<(Entity, &GlobalTransform, &mut BoostPad)>,
+    player_query: Query<Entity, With<Player>>,
+    ball_query: Query<Entity, With<Ball>>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+) {
+    for (pad_entity, pad_tf, mut pad) in pad_query.iter_mut() {
+        step_pad(
+            &mut commands,
+            (pad_entity, pad_tf, &mut *pad),
+            &rapier_context,
+            |entity| player_query.contains(entity),
+            |entity| ball_query.contains(entity),
+            time.delta(),
+        );
+    }
+}
+
+// runs down one pad's cooldown and fires it at the first thing of the right kind
+// sitting on it; shared with rollback sessions, which step it by the tick
+pub fn step_pad(
+    commands: &mut Commands,
+    (pad_entity, pad_tf, pad): (Entity, &GlobalTransform, &mut BoostPad),
+    rapier_context: &RapierContext,
+    is_player: impl Fn(Entity) -> bool,
+    is_ball: impl Fn(Entity) -> bool,
+    delta: Duration,
+) {
+    pad.cooldown.tick(delta);
+    if !pad.ready() {
+        return;
+    }
+    let touching = rapier_context
+        .intersections_with(pad_entity)
+        .filter(|(_, _, intersecting)| *intersecting)
+        .map(|(a, b, _)| if a == pad_entity { b } else { a });
+    for other in touching {
+        let used = match pad.effect {
+            PadEffect::Speed if is_player(other) => {
+                commands.entity(other).insert(SpeedBoost(Timer::from_seconds(
+                    SPEED_BOOST_TIME,
+                    TimerMode::Once,
+                )));
+                true
+            }
+            PadEffect::Launch if is_ball(other) => {
+                let (_, rotation, _) = pad_tf.to_scale_rotation_translation();
+                let forward = rotation * Vec3::Z;
+                commands.entity(other).insert(ExternalImpulse {
+                    impulse: (forward + Vec3::Y * LAUNCH_LIFT).normalize() * LAUNCH_POWER,
+                    torque_impulse: Vec3::ZERO,
+                });
+                true
+            }
+            _ => false,
+        };
+        if used {
+            pad.cooldown.reset();
+            break;
+        }
+    }
+}
+
+fn pad_glow_system(
+    pad_query: Query<(&BoostPad, &Children), Changed<BoostPad>>,
+    mut glow_query: Query<&mut Visibility, With<PadGlow>>,
+) {
+    for (pad, children) in pad_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut visibility) = glow_query.get_mut(*child) {
+                if visibility.is_visible != pad.ready() {
+                    visibility.is_visible = pad.ready();
+                }
+            }
+        }
+    }
+}
+
+fn speed_boost_system(
+    mut commands: Commands,
+    mut boost_query: Query<(Entity, &mut SpeedBoost)>,
+    time: Res<Time>,
+) {
+    for (entity, mut boost) in boost_query.iter_mut() {
+        if boost.run_down(time.delta()) {
+            commands.entity(entity).remove::<SpeedBoost>();
+        }
+    }
+}
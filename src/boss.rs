@@ -0,0 +1,344 @@
+use super::difficulty::Difficulty;
+use super::game_state::GameState;
+use super::goalkeeper::{closest_threat, predicted_x};
+use super::marks::{Ball, Team};
+use super::mode::{GameMode, ModeContext};
+use super::nametags::NameTag;
+use super::rematch::RematchEvent;
+use super::{Score, BOARD_DIM, GOAL_GAP};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+
+const BOSS_SCALE: f32 = 2.2;
+const BOSS_SPEED: f32 = 3.0;
+const BOSS_DASH_SPEED: f32 = 14.0;
+const BOSS_DASH_TIME: f32 = 0.25;
+const BOSS_DASH_COOLDOWN: f32 = 3.0;
+const SHOCKWAVE_RADIUS: f32 = 3.0;
+const SHOCKWAVE_IMPULSE: f32 = 12.0;
+const SHOCKWAVE_COOLDOWN: f32 = 5.0;
+const SHOCKWAVE_LIFETIME: f32 = 0.5;
+const BREACHES_TO_WIN: u32 = 5;
+
+pub struct BossPlugin;
+
+pub struct BossMode;
+
+impl GameMode for BossMode {
+    fn name(&self) -> &'static str {
+        "boss"
+    }
+
+    fn title(&self) -> &'static str {
+        "Boss keeper"
+    }
+
+    fn description(&self) -> &'static str {
+        "Break through an oversized keeper five times."
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_plugin(BossPlugin);
+    }
+
+    fn winner(&self, ctx: &ModeContext) -> Option<Team> {
+        (ctx.score.home >= BREACHES_TO_WIN).then_some(Team::Home)
+    }
+}
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BreachCounter::default())
+            .add_startup_system(setup_boss)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(boss_track_system)
+                    .with_system(boss_dash_system.after(boss_track_system))
+                    .with_system(boss_shockwave_system)
+                    .with_system(shockwave_effect_system)
+                    .with_system(breach_system),
+            )
+            .add_system(breach_reset_system)
+            .add_system(boss_ui_system);
+    }
+}
+
+#[derive(Component)]
+pub struct BossKeeper {
+    target_x: f32,
+    reaction: Timer,
+    dash_timer: Timer,
+    dash_cooldown: Timer,
+    dash_direction: f32,
+    shockwave_cooldown: Timer,
+}
+
+#[derive(Component)]
+struct Shockwave {
+    timer: Timer,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Resource)]
+pub struct BreachCounter {
+    pub breaches: u32,
+    pub needed: u32,
+}
+
+impl Default for BreachCounter {
+    fn default() -> Self {
+        Self {
+            breaches: 0,
+            needed: BREACHES_TO_WIN,
+        }
+    }
+}
+
+impl BreachCounter {
+    pub fn defeated(&self) -> bool {
+        self.breaches >= self.needed
+    }
+}
+
+#[derive(Resource)]
+struct ShockwaveAssets {
+    mesh: Handle<Mesh>,
+}
+
+fn keeper_line() -> f32 {
+    BOARD_DIM.2 / 2.0 - 2.0
+}
+
+fn setup_boss(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Capsule::default())),
+            material: materials.add(StandardMaterial {
+                base_color: Color::ORANGE_RED,
+                emissive: Color::rgb(0.3, 0.05, 0.0),
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, 1.1 * BOSS_SCALE, keeper_line())
+                .with_scale(Vec3::splat(BOSS_SCALE)),
+            ..default()
+        },
+        Collider::capsule_y(0.5, 0.5),
+        RigidBody::KinematicPositionBased,
+        Restitution::coefficient(1.5),
+        BossKeeper {
+            target_x: 0.0,
+            reaction: Timer::from_seconds(0.2, TimerMode::Once),
+            dash_timer: Timer::from_seconds(BOSS_DASH_TIME, TimerMode::Once),
+            dash_cooldown: Timer::from_seconds(BOSS_DASH_COOLDOWN, TimerMode::Once),
+            dash_direction: 0.0,
+            shockwave_cooldown: Timer::from_seconds(SHOCKWAVE_COOLDOWN, TimerMode::Once),
+        },
+        Team::Away,
+        NameTag("CPU".to_string()),
+        Name::new("BossKeeper"),
+    ));
+
+    commands.insert_resource(ShockwaveAssets {
+        mesh: meshes.add(Mesh::from(shape::Torus {
+            radius: 1.0,
+            ring_radius: 0.08,
+            ..default()
+        })),
+    });
+}
+
+fn boss_track_system(
+    mut boss_query: Query<(&mut BossKeeper, &mut Transform), Without<Ball>>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    difficulty: Res<Difficulty>,
+    time: Res<Time>,
+) {
+    let Some((ball_tf, ball_vel)) = closest_threat(ball_query.iter()) else {
+        return;
+    };
+
+    for (mut boss, mut boss_tf) in boss_query.iter_mut() {
+        // the keeper only re-reads the shot once per reaction window
+        boss.reaction.tick(time.delta());
+        if boss.reaction.finished() {
+            boss.target_x =
+                predicted_x(ball_tf, ball_vel, keeper_line()).clamp(-GOAL_GAP, GOAL_GAP);
+            boss.reaction = Timer::from_seconds(difficulty.reaction_time(), TimerMode::Once);
+        }
+
+        if !boss.dash_timer.finished() {
+            continue;
+        }
+        let step = BOSS_SPEED * difficulty.speed_scale() * time.delta_seconds();
+        let delta = (boss.target_x - boss_tf.translation.x).clamp(-step, step);
+        boss_tf.translation.x += delta;
+    }
+}
+
+// a short burst of lateral speed when a shot is heading away from the keeper
+fn boss_dash_system(
+    mut boss_query: Query<(&mut BossKeeper, &mut Transform), Without<Ball>>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    time: Res<Time>,
+) {
+    let Some((ball_tf, ball_vel)) = closest_threat(ball_query.iter()) else {
+        return;
+    };
+
+    for (mut boss, mut boss_tf) in boss_query.iter_mut() {
+        boss.dash_timer.tick(time.delta());
+        boss.dash_cooldown.tick(time.delta());
+
+        if !boss.dash_timer.finished() {
+            let step = boss.dash_direction * BOSS_DASH_SPEED * time.delta_seconds();
+            boss_tf.translation.x = (boss_tf.translation.x + step).clamp(-GOAL_GAP, GOAL_GAP);
+            continue;
+        }
+
+        let incoming = ball_vel.linvel.z > 6.0;
+        let gap = predicted_x(ball_tf, ball_vel, keeper_line()).clamp(-GOAL_GAP, GOAL_GAP)
+            - boss_tf.translation.x;
+        if incoming && gap.abs() > 1.0 && boss.dash_cooldown.finished() {
+            boss.dash_direction = gap.signum();
+            boss.dash_timer.reset();
+            boss.dash_cooldown.reset();
+        }
+    }
+}
+
+fn boss_shockwave_system(
+    mut commands: Commands,
+    mut boss_query: Query<(&mut BossKeeper, &Transform), Without<Ball>>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    shockwave_assets: Res<ShockwaveAssets>,
+    time: Res<Time>,
+) {
+    for (mut boss, boss_tf) in boss_query.iter_mut() {
+        boss.shockwave_cooldown.tick(time.delta());
+        let Some((ball_entity, ball_tf)) = ball_query.iter().min_by(|(_, a), (_, b)| {
+            let a = a.translation.distance_squared(boss_tf.translation);
+            let b = b.translation.distance_squared(boss_tf.translation);
+            a.total_cmp(&b)
+        }) else {
+            continue;
+        };
+
+        let mut away = ball_tf.translation - boss_tf.translation;
+        away.y = 0.0;
+        if away.length() > SHOCKWAVE_RADIUS || !boss.shockwave_cooldown.finished() {
+            continue;
+        }
+        boss.shockwave_cooldown.reset();
+
+        // always clear back up the field, never into our own goal
+        let clear = Vec3::new(away.x, 0.4, -1.0).normalize();
+        commands.entity(ball_entity).insert(ExternalImpulse {
+            impulse: clear * SHOCKWAVE_IMPULSE,
+            torque_impulse: Vec3::ZERO,
+        });
+
+        let material = materials.add(StandardMaterial {
+            base_color: Color::rgba(1.0, 0.6, 0.1, 0.8),
+            emissive: Color::ORANGE,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        commands.spawn((
+            PbrBundle {
+                mesh: shockwave_assets.mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(boss_tf.translation.x, 0.2, boss_tf.translation.z),
+                ..default()
+            },
+            Shockwave {
+                timer: Timer::from_seconds(SHOCKWAVE_LIFETIME, TimerMode::Once),
+                material,
+            },
+            Name::new("Shockwave"),
+        ));
+    }
+}
+
+fn shockwave_effect_system(
+    mut commands: Commands,
+    mut shockwave_query: Query<(Entity, &mut Shockwave, &mut Transform)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, mut shockwave, mut tf) in shockwave_query.iter_mut() {
+        shockwave.timer.tick(time.delta());
+        let t = shockwave.timer.percent();
+        tf.scale = Vec3::new(1.0, 1.0, 1.0) * (0.5 + t * SHOCKWAVE_RADIUS);
+        if let Some(material) = materials.get_mut(&shockwave.material) {
+            material.base_color.set_a(0.8 * (1.0 - t));
+        }
+        if shockwave.timer.finished() {
+            materials.remove(&shockwave.material);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn breach_system(
+    score: Res<Score>,
+    mut breaches: ResMut<BreachCounter>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if !score.is_changed() {
+        return;
+    }
+    breaches.breaches = score.home;
+    if breaches.defeated() {
+        rapier_config.physics_pipeline_active = false;
+    }
+}
+
+fn breach_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut breaches: ResMut<BreachCounter>,
+) {
+    if rematch_events.iter().count() > 0 {
+        *breaches = BreachCounter::default();
+    }
+}
+
+fn boss_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    breaches: Res<BreachCounter>,
+    mut rematch_events: EventWriter<RematchEvent>,
+) {
+    let ctx = egui_context.ctx_mut();
+    egui::Area::new("breach_counter")
+        .anchor(egui::Align2::RIGHT_TOP, [-16.0, 16.0])
+        .show(ctx, |ui| {
+            let left = breaches.needed.saturating_sub(breaches.breaches);
+            ui.label(
+                egui::RichText::new(format!("Keeper shield: {}/{}", left, breaches.needed))
+                    .size(20.0)
+                    .color(egui::Color32::from_rgb(255, 110, 40)),
+            );
+        });
+
+    if breaches.defeated() {
+        egui::Window::new("Keeper defeated")
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "You breached the keeper {} times",
+                    breaches.breaches
+                ));
+                if ui.button("Try again").clicked() {
+                    rematch_events.send(RematchEvent);
+                }
+            });
+    }
+}
@@ -0,0 +1,119 @@
+use super::cli::StartupArgs;
+use super::marks::Ball;
+use super::{MatchEntities, Score};
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::window::{CreateWindow, WindowId};
+use bevy_egui::{egui, EguiContext};
+
+// sideline gantry position relative to the ball's spot along the pitch
+const GANTRY_OFFSET: Vec3 = Vec3::new(16.0, 9.0, 0.0);
+const FOLLOW_STIFFNESS: f32 = 2.0;
+
+pub struct BroadcastPlugin;
+
+impl Plugin for BroadcastPlugin {
+    fn build(&self, app: &mut App) {
+        let broadcast = app
+            .world
+            .get_resource::<StartupArgs>()
+            .map_or(false, |args| args.broadcast);
+        if !broadcast {
+            return;
+        }
+
+        app.insert_resource(BroadcastWindow(WindowId::new()))
+            .add_startup_system(setup_broadcast)
+            .add_system(broadcast_camera_system)
+            .add_system(broadcast_scorebug_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct BroadcastWindow(pub WindowId);
+
+#[derive(Component)]
+pub struct BroadcastCamera {
+    focus: Vec3,
+}
+
+fn setup_broadcast(
+    mut commands: Commands,
+    mut create_window_events: EventWriter<CreateWindow>,
+    broadcast_window: Res<BroadcastWindow>,
+) {
+    create_window_events.send(CreateWindow {
+        id: broadcast_window.0,
+        descriptor: WindowDescriptor {
+            width: 1280.0,
+            height: 720.0,
+            title: "Playground broadcast".to_string(),
+            ..default()
+        },
+    });
+
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(broadcast_window.0),
+                priority: 2,
+                ..default()
+            },
+            transform: Transform::from_translation(GANTRY_OFFSET).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        // the player HUD is bevy ui, keep it off the clean feed
+        UiCameraConfig { show_ui: false },
+        BroadcastCamera { focus: Vec3::ZERO },
+        Name::new("BroadcastCamera"),
+    ));
+}
+
+// track along the touchline like a rail camera, always looking at the ball
+fn broadcast_camera_system(
+    mut camera_query: Query<(&mut BroadcastCamera, &mut Transform), Without<Ball>>,
+    ball_query: Query<&Transform, With<Ball>>,
+    entities: Res<MatchEntities>,
+    time: Res<Time>,
+) {
+    let Some(Ok(ball_tf)) = entities.ball.map(|ball| ball_query.get(ball)) else {
+        return;
+    };
+
+    for (mut camera, mut tf) in camera_query.iter_mut() {
+        let t = (FOLLOW_STIFFNESS * time.delta_seconds()).min(1.0);
+        let target = Vec3::new(ball_tf.translation.x * 0.5, 0.0, ball_tf.translation.z);
+        camera.focus = camera.focus.lerp(target, t);
+        tf.translation = Vec3::new(0.0, 0.0, camera.focus.z) + GANTRY_OFFSET;
+        tf.look_at(camera.focus, Vec3::Y);
+    }
+}
+
+fn broadcast_scorebug_system(
+    mut egui_context: ResMut<EguiContext>,
+    broadcast_window: Res<BroadcastWindow>,
+    score: Res<Score>,
+) {
+    let Some(ctx) = egui_context.try_ctx_for_window_mut(broadcast_window.0) else {
+        return;
+    };
+
+    egui::Area::new("scorebug")
+        .anchor(egui::Align2::LEFT_TOP, [24.0, 24.0])
+        .show(ctx, |ui| scorebug_ui(ui, &score));
+}
+
+// shared by every clean feed: the broadcast window and the streamer overlay
+pub fn scorebug_ui(ui: &mut egui::Ui, score: &Score) {
+    egui::Frame::none()
+        .fill(egui::Color32::from_black_alpha(200))
+        .inner_margin(egui::style::Margin::symmetric(12.0, 6.0))
+        .show(ui, |ui| {
+            ui.label(
+                egui::RichText::new(format!("HOME  {}  –  {}  AWAY", score.home, score.away))
+                    .size(22.0)
+                    .strong()
+                    .color(egui::Color32::WHITE),
+            );
+        });
+}
@@ -0,0 +1,138 @@
+use super::game_state::GameState;
+use super::marks::{Ball, Team};
+use super::rematch::RematchEvent;
+use super::{GoalReset, GoalScoredEvent, MatchEntities};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+// develop-only shortcuts for tuning, all while holding the backquote key since every
+// ctrl, shift and alt is already a player action:
+// M slow motion, G goal for home, B freeze the ball, R reset the match
+pub struct CheatsPlugin;
+
+impl Plugin for CheatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SlowMotion>()
+            .add_system(slow_motion_system)
+            .add_system(freeze_ball_system)
+            .add_system(reset_match_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(instant_goal_system),
+            );
+    }
+}
+
+fn cheat_pressed(keyboard: &Input<KeyCode>, key: KeyCode) -> bool {
+    keyboard.pressed(KeyCode::Grave) && keyboard.just_pressed(key)
+}
+
+// the timestep mode from before slow motion, put back when it's turned off
+#[derive(Resource, Default)]
+struct SlowMotion {
+    restore: Option<TimestepMode>,
+}
+
+fn slow_motion_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut slow: ResMut<SlowMotion>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if !cheat_pressed(&keyboard, KeyCode::M) {
+        return;
+    }
+    if let Some(restore) = slow.restore.take() {
+        rapier_config.timestep_mode = restore;
+        info!("Slow motion off");
+        return;
+    }
+
+    slow.restore = Some(rapier_config.timestep_mode);
+    rapier_config.timestep_mode = match rapier_config.timestep_mode {
+        TimestepMode::Fixed { dt, substeps } => TimestepMode::Fixed {
+            dt: dt * 0.5,
+            substeps,
+        },
+        TimestepMode::Variable {
+            max_dt,
+            time_scale,
+            substeps,
+        } => TimestepMode::Variable {
+            max_dt,
+            time_scale: time_scale * 0.5,
+            substeps,
+        },
+        TimestepMode::Interpolated {
+            dt,
+            time_scale,
+            substeps,
+        } => TimestepMode::Interpolated {
+            dt,
+            time_scale: time_scale * 0.5,
+            substeps,
+        },
+    };
+    info!("Slow motion on");
+}
+
+// scored through the usual event, so the score, popups and reset all follow
+fn instant_goal_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut goal_events: EventWriter<GoalScoredEvent>,
+    entities: Res<MatchEntities>,
+    goal_reset: Res<GoalReset>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+) {
+    if !cheat_pressed(&keyboard, KeyCode::G) || goal_reset.pending() {
+        return;
+    }
+    let Some(ball) = entities.ball else {
+        return;
+    };
+    let Ok((ball_tf, ball_vel)) = ball_query.get(ball) else {
+        return;
+    };
+    goal_events.send(GoalScoredEvent {
+        team: Team::Home,
+        against: Team::Away,
+        points: 1,
+        ball,
+        position: ball_tf.translation,
+        ball_velocity: ball_vel.linvel,
+    });
+}
+
+// a frozen ball hangs where it is, kicks and all, until pressed again
+#[derive(Component)]
+struct FrozenBall;
+
+fn freeze_ball_system(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    mut ball_query: Query<(Entity, &mut Velocity, Option<&FrozenBall>), With<Ball>>,
+) {
+    if !cheat_pressed(&keyboard, KeyCode::B) {
+        return;
+    }
+    for (entity, mut vel, frozen) in ball_query.iter_mut() {
+        if frozen.is_some() {
+            commands
+                .entity(entity)
+                .remove::<FrozenBall>()
+                .insert(RigidBody::Dynamic);
+        } else {
+            *vel = Velocity::zero();
+            commands
+                .entity(entity)
+                .insert((FrozenBall, RigidBody::Fixed));
+        }
+    }
+}
+
+fn reset_match_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut rematch_events: EventWriter<RematchEvent>,
+) {
+    if cheat_pressed(&keyboard, KeyCode::R) {
+        rematch_events.send(RematchEvent);
+    }
+}
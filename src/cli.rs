@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use clap::Parser;
+use std::path::PathBuf;
+
+// every flag the game takes, parsed once before any plugin is built. plugins read
+// their own flags from this resource, so --help lists them all and a typo is an error
+// instead of being silently ignored
+#[derive(Parser, Resource, Clone, Default, Debug)]
+#[command(name = "playground")]
+pub struct StartupArgs {
+    #[arg(long, value_name = "FILE",
+        help = "play an arena descriptor instead of the default one")]
+    pub arena: Option<PathBuf>,
+    #[arg(long, value_name = "N", help = "end the match when a side reaches n goals")]
+    pub score_limit: Option<u32>,
+    #[arg(long, value_name = "N", help = "play with 2 to 4 balls at once")]
+    pub multiball: Option<usize>,
+    #[arg(long, value_name = "NAME", help = "play this game mode")]
+    pub mode: Option<String>,
+    #[arg(long, help = "same as --mode boss")]
+    pub boss: bool,
+    #[arg(long, help = "same as --mode survival")]
+    pub survival: bool,
+    #[arg(long, value_name = "NAME", help = "same as --mode drill, on the named drill")]
+    pub drill: Option<Option<String>>,
+    #[arg(long, value_name = "NAME", help = "play with a ball skin from a loaded mod")]
+    pub ball_skin: Option<String>,
+    #[arg(long, value_name = "FILE", help = "watch a saved replay from the replays folder")]
+    pub replay: Option<String>,
+    #[arg(long, value_name = "WxH", value_parser = parse_size,
+        help = "open the window at this size")]
+    pub window_size: Option<Vec2>,
+
+    #[arg(long, help = "simulate without a window, see --frames")]
+    pub headless: bool,
+    #[arg(long, value_name = "N", help = "frames a headless run simulates")]
+    pub frames: Option<u32>,
+    #[arg(long, help = "fixed physics steps and a seeded rng")]
+    pub deterministic: bool,
+    #[arg(long, value_name = "N", help = "seed the gameplay rng")]
+    pub seed: Option<u64>,
+    #[arg(long, value_name = "NAME", help = "save state hashes under this name (develop)")]
+    pub record_checksums: Option<String>,
+    #[arg(long, value_name = "NAME", conflicts_with = "record_checksums",
+        help = "compare state hashes against a recorded log (develop)")]
+    pub verify_checksums: Option<String>,
+    #[arg(long, value_name = "NAME", help = "record the local input under this name")]
+    pub record_tape: Option<String>,
+    #[arg(long, value_name = "NAME", conflicts_with = "record_tape",
+        help = "play back a recorded input tape")]
+    pub play_tape: Option<String>,
+    #[arg(long, value_name = "BALLS", help = "time a field of balls and exit")]
+    pub bench: Option<Option<usize>>,
+    #[arg(long, value_name = "N", help = "frames the bench measures")]
+    pub bench_frames: Option<u32>,
+
+    #[arg(long, value_name = "N", help = "build the random arena with this seed")]
+    pub arena_seed: Option<u64>,
+    #[arg(long, value_name = "FILE", help = "report problems in an arena and exit")]
+    pub validate_arena: Option<PathBuf>,
+    #[arg(long, value_name = "FILE", help = "write the match history as csv and exit")]
+    pub export_history: Option<PathBuf>,
+    #[arg(long, help = "record positions and inputs every tick for training")]
+    pub record_dataset: bool,
+    #[arg(long, help = "let the AI difficulty follow how the match goes")]
+    pub adaptive: bool,
+    #[arg(long, help = "always run at a reduced frame rate")]
+    pub low_power: bool,
+    #[arg(long, help = "open a second window with a broadcast camera")]
+    pub broadcast: bool,
+    #[arg(long, help = "transparent window for streaming overlays")]
+    pub overlay: bool,
+    #[arg(long, conflicts_with = "overlay", help = "overlay on chroma green")]
+    pub overlay_chroma: bool,
+
+    #[arg(long, num_args = 3, value_names = ["LOCAL", "PEER", "SIDE"],
+        help = "play online with rollback, SIDE is home or away")]
+    pub net: Option<Vec<String>>,
+    #[arg(long, num_args = 3, value_names = ["LOCAL", "PEER", "SIDE"], conflicts_with = "net",
+        help = "play online in lockstep, SIDE is home or away")]
+    pub lockstep: Option<Vec<String>>,
+    #[arg(long, value_name = "CODE", help = "only accept a peer giving the same code")]
+    pub room: Option<String>,
+}
+
+impl StartupArgs {
+    pub fn window(&self) -> WindowDescriptor {
+        let mut window = WindowDescriptor::default();
+        if let Some(size) = self.window_size {
+            window.width = size.x;
+            window.height = size.y;
+        }
+        window
+    }
+}
+
+// 1280x720
+fn parse_size(text: &str) -> Result<Vec2, String> {
+    let invalid = || format!("expected <width>x<height>, got {}", text);
+    let (w, h) = text.split_once('x').ok_or_else(invalid)?;
+    let size = Vec2::new(
+        w.parse().map_err(|_| invalid())?,
+        h.parse().map_err(|_| invalid())?,
+    );
+    if size.x > 0.0 && size.y > 0.0 {
+        Ok(size)
+    } else {
+        Err(invalid())
+    }
+}
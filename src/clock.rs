@@ -0,0 +1,201 @@
+use super::config::{ClockConfig, GameConfig};
+use super::game_state::GameState;
+use super::kickoff::Kickoff;
+use super::marks::Team;
+use super::mode::ActiveMode;
+use super::rematch::RematchEvent;
+use super::{MatchEntities, Score, PLAYER_SPAWN};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+pub const MATCH_DURATION: f32 = 180.0;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        let defaults = ClockConfig::default();
+        app.insert_resource(MatchClock::new(defaults.duration, defaults.halves))
+            .add_event::<PeriodEndEvent>()
+            // paused, half time and menus don't eat into the match, and neither does a goal
+            // replay, the time it plays back was already on the clock
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(match_clock_system),
+            )
+            .add_system(period_reset_system.after(match_clock_system))
+            .add_system_set(
+                SystemSet::on_update(GameState::HalfTime).with_system(half_time_ui_system),
+            )
+            .add_system(clock_reset_system)
+            .add_system(clock_config_system);
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct MatchClock {
+    pub elapsed: f32,
+    pub duration: f32,
+    // time lost to restarts, played on after regulation
+    pub stoppage: f32,
+    pub sudden_death: bool,
+    // false at half time too, see `full_time` for the end of the match
+    pub running: bool,
+    // the clock stopped for good
+    #[serde(default)]
+    finished: bool,
+    pub halves: u32,
+    // counts from 1
+    pub half: u32,
+}
+
+// the clock stopped at the end of a half, or for good
+pub struct PeriodEndEvent {
+    pub full_time: bool,
+}
+
+impl MatchClock {
+    pub fn new(duration: f32, halves: u32) -> Self {
+        Self {
+            elapsed: 0.0,
+            duration,
+            stoppage: 0.0,
+            sudden_death: false,
+            running: true,
+            finished: false,
+            halves,
+            half: 1,
+        }
+    }
+
+    pub fn half_length(&self) -> f32 {
+        self.duration / self.halves.max(1) as f32
+    }
+
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    // what the display counts down, the end of the current half
+    pub fn half_remaining(&self) -> f32 {
+        (self.half_length() * self.half as f32 - self.elapsed).max(0.0)
+    }
+
+    pub fn in_stoppage(&self) -> bool {
+        self.elapsed >= self.duration && !self.sudden_death
+    }
+
+    pub fn full_time(&self) -> bool {
+        self.finished
+    }
+}
+
+fn match_clock_system(
+    mut clock: ResMut<MatchClock>,
+    kickoff: Res<Kickoff>,
+    score: Res<Score>,
+    time: Res<Time>,
+    mut period_events: EventWriter<PeriodEndEvent>,
+    mode: Res<ActiveMode>,
+) {
+    if !clock.running || !mode.0.timed() {
+        return;
+    }
+
+    let dt = time.delta_seconds();
+    clock.elapsed += dt;
+    // waiting for the kickoff touch is dead time, owed back as stoppage
+    if kickoff.held && clock.elapsed < clock.duration {
+        clock.stoppage += dt;
+    }
+
+    // earlier halves end on the dot, stoppage is all played at the end
+    let half_end = clock.half_length() * clock.half as f32;
+    if clock.half < clock.halves && clock.elapsed >= half_end {
+        clock.elapsed = half_end;
+        clock.running = false;
+        period_events.send(PeriodEndEvent { full_time: false });
+        return;
+    }
+
+    if clock.elapsed >= clock.duration + clock.stoppage && !clock.sudden_death {
+        if score.home == score.away {
+            clock.sudden_death = true;
+        } else {
+            clock.running = false;
+        }
+    } else if clock.sudden_death && score.home != score.away {
+        clock.running = false;
+    }
+    if !clock.running {
+        clock.finished = true;
+        period_events.send(PeriodEndEvent { full_time: true });
+    }
+}
+
+// everyone back to their spots for the restart, the side that didn't kick off last
+// gets the ball
+fn period_reset_system(
+    mut period_events: EventReader<PeriodEndEvent>,
+    mut kickoff: ResMut<Kickoff>,
+    mut transforms: Query<&mut Transform>,
+    entities: Res<MatchEntities>,
+) {
+    if period_events.iter().count() == 0 {
+        return;
+    }
+    let next = match kickoff.possession {
+        Team::Home => Team::Away,
+        Team::Away => Team::Home,
+    };
+    kickoff.restart(next);
+    for (index, ball) in entities.balls.iter().enumerate() {
+        if let Ok(mut ball_tf) = transforms.get_mut(*ball) {
+            ball_tf.translation = kickoff.ball_spot_at(index);
+        }
+    }
+    if let Some(Ok(mut player_tf)) = entities.player.map(|player| transforms.get_mut(player)) {
+        player_tf.translation = PLAYER_SPAWN;
+    }
+}
+
+fn half_time_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut clock: ResMut<MatchClock>,
+    mut state: ResMut<State<GameState>>,
+    score: Res<Score>,
+) {
+    let mut resume = false;
+    egui::Window::new("Half time")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading(format!("{} – {}", score.home, score.away));
+            ui.label(format!("End of half {} of {}", clock.half, clock.halves));
+            resume = ui.button("Next half").clicked();
+        });
+
+    if resume {
+        clock.half += 1;
+        clock.running = true;
+        state.pop().ok();
+    }
+}
+
+fn clock_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut clock: ResMut<MatchClock>,
+) {
+    if rematch_events.iter().count() > 0 {
+        *clock = MatchClock::new(clock.duration, clock.halves);
+    }
+}
+
+fn clock_config_system(config: Res<GameConfig>, mut clock: ResMut<MatchClock>) {
+    if !config.is_changed() {
+        return;
+    }
+    clock.duration = config.clock.duration;
+    clock.halves = config.clock.halves;
+}
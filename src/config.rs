@@ -0,0 +1,511 @@
+use super::cli::StartupArgs;
+use super::clock::MATCH_DURATION;
+use super::input::StickSettings;
+use super::marks::Ball;
+use super::mode::ActiveMode;
+use super::mutators::Mutators;
+use super::rules::RuleSet;
+use super::storage::{self, Versioned};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+pub const CONFIG_PATH: &str = "config/playground.ron";
+const CONFIG_DIR: &str = "config";
+const WATCH_INTERVAL: f32 = 0.5;
+const ERROR_DISPLAY_TIME: f32 = 8.0;
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let mut config = GameConfig::load();
+        if let Some(args) = app.world.get_resource::<StartupArgs>() {
+            config.apply_args(args);
+        }
+        app.insert_resource(config)
+            .insert_resource(ConfigWatcher::default())
+            .init_resource::<PhysicsOverrides>()
+            .add_system(config_watch_system)
+            .add_system(apply_physics_config_system.after(config_watch_system))
+            .add_system(apply_ball_config_system.after(config_watch_system))
+            .add_system(config_error_ui_system);
+
+        #[cfg(feature = "develop")]
+        app.add_system(physics_panel_system);
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct GameConfig {
+    pub physics: PhysicsConfig,
+    // per-mode overrides, keyed by `GameMode::name`
+    pub mode_physics: HashMap<String, PhysicsConfig>,
+    pub replays: ReplayConfig,
+    pub controls: StickSettings,
+    pub kick: KickConfig,
+    pub ball: BallConfig,
+    pub hud: HudConfig,
+    pub clock: ClockConfig,
+    pub rules: RuleSet,
+    // per-mode rule sets, keyed like `mode_physics`
+    pub mode_rules: HashMap<String, RuleSet>,
+    pub leaderboard: LeaderboardConfig,
+    pub power: PowerConfig,
+}
+
+impl GameConfig {
+    pub fn load() -> Self {
+        let path = std::path::Path::new(CONFIG_PATH);
+        if !path.exists() {
+            return Self::default();
+        }
+        match storage::read::<Self>(path).and_then(|config| config.validate().map(|_| config)) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("Ignoring invalid {}: {}", CONFIG_PATH, err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Self = storage::parse(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // catches values that parse fine but would break the simulation
+    pub fn validate(&self) -> Result<(), String> {
+        let physics = std::iter::once(("default", &self.physics)).chain(
+            self.mode_physics
+                .iter()
+                .map(|(mode, physics)| (mode.as_str(), physics)),
+        );
+        for (mode, physics) in physics {
+            if !(-50.0..=0.0).contains(&physics.gravity) {
+                return Err(format!("{} gravity must be between -50 and 0", mode));
+            }
+            if physics.substeps == 0 || physics.velocity_iterations == 0 {
+                return Err(format!(
+                    "{} substeps and iterations must be at least 1",
+                    mode
+                ));
+            }
+        }
+        let sticks = [
+            self.controls.move_x,
+            self.controls.move_y,
+            self.controls.aim_x,
+            self.controls.aim_y,
+        ];
+        if sticks
+            .iter()
+            .any(|axis| !(0.0..0.9).contains(&axis.deadzone))
+        {
+            return Err("stick deadzones must be between 0 and 0.9".to_string());
+        }
+        if self.kick.strength <= 0.0 {
+            return Err("kick strength must be positive".to_string());
+        }
+        if !(0.0..=2.0).contains(&self.ball.restitution) {
+            return Err("ball restitution must be between 0 and 2".to_string());
+        }
+        if self.ball.magnus < 0.0 {
+            return Err("ball magnus must not be negative".to_string());
+        }
+        if self.clock.duration <= 0.0 || self.clock.halves == 0 {
+            return Err("clock duration and halves must be positive".to_string());
+        }
+        if self.replays.long_shot_distance <= 0.0 {
+            return Err("long_shot_distance must be positive".to_string());
+        }
+        if self.leaderboard.timeout <= 0.0 {
+            return Err("leaderboard timeout must be positive".to_string());
+        }
+        if self.power.max_dt <= 0.0 || self.power.reduced_rate <= 0.0 {
+            return Err("power max_dt and reduced_rate must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    // command line flags win over the file, on every reload too; the limit goes on
+    // every mode's rules, since rules_for prefers a mode's own set over the default
+    pub fn apply_args(&mut self, args: &StartupArgs) {
+        if args.score_limit.is_some() {
+            let mode_rules = self.mode_rules.values_mut();
+            for rules in std::iter::once(&mut self.rules).chain(mode_rules) {
+                rules.score_limit = args.score_limit;
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = storage::write(std::path::Path::new(CONFIG_PATH), self) {
+            warn!("Failed to write {}: {}", CONFIG_PATH, err);
+        }
+    }
+
+    pub fn physics_for(&self, mode: &str) -> &PhysicsConfig {
+        self.mode_physics.get(mode).unwrap_or(&self.physics)
+    }
+
+    pub fn rules_for(&self, mode: &str) -> &RuleSet {
+        self.mode_rules.get(mode).unwrap_or(&self.rules)
+    }
+}
+
+impl Versioned for GameConfig {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PhysicsConfig {
+    pub gravity: f32,
+    pub substeps: usize,
+    pub velocity_iterations: usize,
+    pub friction_iterations: usize,
+    pub stabilization_iterations: usize,
+    pub ccd_substeps: usize,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            gravity: -9.81,
+            substeps: 1,
+            velocity_iterations: 4,
+            friction_iterations: 8,
+            stabilization_iterations: 1,
+            ccd_substeps: 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ReplayConfig {
+    pub auto_save_wins: bool,
+    pub auto_save_long_shots: bool,
+    // writes every match to disk when it ends, whatever happened in it
+    pub record_matches: bool,
+    // metres from the kick to the goal line that make a goal a long shot
+    pub long_shot_distance: f32,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            auto_save_wins: false,
+            auto_save_long_shots: false,
+            record_matches: false,
+            long_shot_distance: 12.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct KickConfig {
+    // scales every shot's power, 1.0 plays the shots as tuned
+    pub strength: f32,
+}
+
+impl Default for KickConfig {
+    fn default() -> Self {
+        Self { strength: 1.0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BallConfig {
+    // bounce off walls and players, 1.0 keeps all the speed
+    pub restitution: f32,
+    // spinning balls swerve, see spin.rs
+    pub curve: bool,
+    // sideways force per unit of spin times speed
+    pub magnus: f32,
+}
+
+impl Default for BallConfig {
+    fn default() -> Self {
+        Self {
+            restitution: 1.0,
+            curve: true,
+            magnus: 0.02,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct HudConfig {
+    pub show_scoreboard: bool,
+    pub show_clock: bool,
+    pub show_minimap: bool,
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self {
+            show_scoreboard: true,
+            show_clock: true,
+            show_minimap: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ClockConfig {
+    // seconds of regulation time, split evenly between the halves
+    pub duration: f32,
+    pub halves: u32,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            duration: MATCH_DURATION,
+            halves: 2,
+        }
+    }
+}
+
+// physics mods set per mode, applied over the file's without ever being saved into it
+#[derive(Resource, Default)]
+pub struct PhysicsOverrides(pub HashMap<String, PhysicsConfig>);
+
+impl PhysicsOverrides {
+    pub fn physics_for<'a>(&'a self, config: &'a GameConfig, mode: &str) -> &'a PhysicsConfig {
+        self.0
+            .get(mode)
+            .unwrap_or_else(|| config.physics_for(mode))
+    }
+}
+
+// only read by builds with the leaderboard feature, no endpoint keeps it offline
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LeaderboardConfig {
+    pub endpoint: Option<String>,
+    // seconds before a request to the server is given up on
+    pub timeout: f32,
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            timeout: 5.0,
+        }
+    }
+}
+
+// how physics keeps pace while power saving drops the frame rate, see power.rs
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PowerConfig {
+    // longest step rapier takes at a full frame rate, in seconds
+    pub max_dt: f32,
+    // frames per second while saving power
+    pub reduced_rate: f32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            max_dt: 1.0 / 60.0,
+            reduced_rate: 20.0,
+        }
+    }
+}
+
+// polls the config directory, bevy's asset watcher only covers the assets folder
+#[derive(Resource)]
+struct ConfigWatcher {
+    timer: Timer,
+    modified: HashMap<String, SystemTime>,
+    error: Option<(String, Timer)>,
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(WATCH_INTERVAL, TimerMode::Repeating),
+            modified: scan_config_dir(),
+            error: None,
+        }
+    }
+}
+
+fn scan_config_dir() -> HashMap<String, SystemTime> {
+    let Ok(entries) = std::fs::read_dir(CONFIG_DIR) else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            name.ends_with(".ron").then_some((name, modified))
+        })
+        .collect()
+}
+
+fn config_watch_system(
+    mut watcher: ResMut<ConfigWatcher>,
+    mut config: ResMut<GameConfig>,
+    args: Option<Res<StartupArgs>>,
+    time: Res<Time>,
+) {
+    if let Some((_, timer)) = watcher.error.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            watcher.error = None;
+        }
+    }
+
+    watcher.timer.tick(time.delta());
+    if !watcher.timer.just_finished() {
+        return;
+    }
+    let modified = scan_config_dir();
+    if modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+
+    let result = std::fs::read_to_string(CONFIG_PATH)
+        .map_err(|err| err.to_string())
+        .and_then(|text| GameConfig::parse(&text));
+    match result {
+        Ok(mut reloaded) => {
+            if let Some(args) = &args {
+                reloaded.apply_args(args);
+            }
+            info!("Reloaded {}", CONFIG_PATH);
+            watcher.error = None;
+            if reloaded != *config {
+                *config = reloaded;
+            }
+        }
+        Err(err) => {
+            // keep playing on the last good config
+            warn!("Not applying {}: {}", CONFIG_PATH, err);
+            watcher.error = Some((
+                err,
+                Timer::from_seconds(ERROR_DISPLAY_TIME, TimerMode::Once),
+            ));
+        }
+    }
+}
+
+fn config_error_ui_system(mut egui_context: ResMut<EguiContext>, watcher: Res<ConfigWatcher>) {
+    let Some((error, _)) = &watcher.error else {
+        return;
+    };
+    egui::Area::new("config_error")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new(format!("{}: {}", CONFIG_PATH, error))
+                    .color(egui::Color32::LIGHT_RED),
+            );
+        });
+}
+
+fn apply_physics_config_system(
+    config: Res<GameConfig>,
+    overrides: Res<PhysicsOverrides>,
+    mode: Res<ActiveMode>,
+    mutators: Res<Mutators>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut rapier_context: ResMut<RapierContext>,
+) {
+    if !config.is_changed() && !overrides.is_changed() && !mutators.is_changed() {
+        return;
+    }
+    let physics = overrides.physics_for(&config, mode.name());
+
+    rapier_config.gravity = Vec3::Y * physics.gravity * mutators.gravity_scale();
+    match &mut rapier_config.timestep_mode {
+        TimestepMode::Fixed { substeps, .. }
+        | TimestepMode::Variable { substeps, .. }
+        | TimestepMode::Interpolated { substeps, .. } => *substeps = physics.substeps.max(1),
+    }
+
+    let params = &mut rapier_context.integration_parameters;
+    params.max_velocity_iterations = physics.velocity_iterations.max(1);
+    params.max_velocity_friction_iterations = physics.friction_iterations.max(1);
+    params.max_stabilization_iterations = physics.stabilization_iterations;
+    params.max_ccd_substeps = physics.ccd_substeps.max(1);
+}
+
+// balls are respawned on every rematch, so new ones are tuned as they appear
+fn apply_ball_config_system(
+    config: Res<GameConfig>,
+    mutators: Res<Mutators>,
+    mut ball_query: Query<&mut Restitution, With<Ball>>,
+    added: Query<(), Added<Ball>>,
+) {
+    if !config.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut restitution in ball_query.iter_mut() {
+        restitution.coefficient = mutators.restitution(config.ball.restitution);
+    }
+}
+
+#[cfg(feature = "develop")]
+fn physics_panel_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut config: ResMut<GameConfig>,
+    overrides: Res<PhysicsOverrides>,
+    mode: Res<ActiveMode>,
+) {
+    let mut edited = config.physics_for(mode.name()).clone();
+    let mut save = false;
+
+    egui::Window::new("Physics")
+        .default_open(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("Tuning for mode: {}", mode.name()));
+            if overrides.0.contains_key(mode.name()) {
+                ui.colored_label(egui::Color32::YELLOW, "A mod overrides these in play");
+            }
+            ui.add(egui::Slider::new(&mut edited.gravity, -30.0..=0.0).text("gravity"));
+            ui.add(egui::Slider::new(&mut edited.substeps, 1..=8).text("substeps"));
+            ui.add(
+                egui::Slider::new(&mut edited.velocity_iterations, 1..=16)
+                    .text("velocity iterations"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.friction_iterations, 1..=16)
+                    .text("friction iterations"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.stabilization_iterations, 0..=8)
+                    .text("stabilization iterations"),
+            );
+            ui.add(egui::Slider::new(&mut edited.ccd_substeps, 1..=8).text("ccd substeps"));
+            save = ui.button("Save to config").clicked();
+        });
+
+    if &edited != config.physics_for(mode.name()) {
+        if config.mode_physics.contains_key(mode.name()) {
+            config.mode_physics.insert(mode.name().to_string(), edited);
+        } else {
+            config.physics = edited;
+        }
+    }
+    if save {
+        config.save();
+    }
+}
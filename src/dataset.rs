@@ -0,0 +1,212 @@
+use super::cli::StartupArgs;
+use super::input::PlayerInput;
+use super::marks::{Ball, Player, Team};
+use super::rematch::RematchEvent;
+use super::storage;
+use super::{MatchEntities, Score};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const TICK_RATE: f32 = 30.0;
+const MAGIC: &[u8; 4] = b"PGDS";
+const FORMAT_VERSION: u16 = 1;
+
+// input bits recorded per player each tick
+const INPUT_FORWARD: u8 = 1 << 0;
+const INPUT_BACK: u8 = 1 << 1;
+const INPUT_LEFT: u8 = 1 << 2;
+const INPUT_RIGHT: u8 = 1 << 3;
+const INPUT_KICK: u8 = 1 << 4;
+
+pub struct DatasetPlugin;
+
+impl Plugin for DatasetPlugin {
+    fn build(&self, app: &mut App) {
+        let record = app
+            .world
+            .get_resource::<StartupArgs>()
+            .map_or(false, |args| args.record_dataset);
+        if !record {
+            return;
+        }
+
+        app.insert_resource(DatasetRecorder::default())
+            .add_startup_system(open_dataset_system)
+            .add_system(record_tick_system)
+            .add_system(rotate_dataset_system)
+            .add_system(close_dataset_system);
+    }
+}
+
+// one file per match:
+//   header: magic, version u16, tick rate f32
+//   tick:   index u32, reward i8, ball pos/vel 6 x f32, entity count u8,
+//           then per entity team u8, input bits u8, position 3 x f32
+// everything little endian
+#[derive(Resource)]
+pub struct DatasetRecorder {
+    writer: Option<BufWriter<File>>,
+    timer: Timer,
+    tick: u32,
+    last_goals: u32,
+    last_conceded: u32,
+}
+
+impl Default for DatasetRecorder {
+    fn default() -> Self {
+        Self {
+            writer: None,
+            timer: Timer::from_seconds(1.0 / TICK_RATE, TimerMode::Repeating),
+            tick: 0,
+            last_goals: 0,
+            last_conceded: 0,
+        }
+    }
+}
+
+impl DatasetRecorder {
+    fn open(&mut self) {
+        self.close();
+
+        let dir = storage::data_dir().join("datasets");
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let path = dir.join(format!("match-{}.pgds", stamp));
+
+        let result = std::fs::create_dir_all(&dir).and_then(|_| {
+            let mut writer = BufWriter::new(File::create(&path)?);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+            writer.write_all(&TICK_RATE.to_le_bytes())?;
+            Ok(writer)
+        });
+
+        match result {
+            Ok(writer) => {
+                info!("Recording dataset to {}", path.display());
+                self.writer = Some(writer);
+            }
+            Err(err) => warn!("Dataset recording disabled: {}", err),
+        }
+        self.tick = 0;
+        self.last_goals = 0;
+        self.last_conceded = 0;
+    }
+
+    fn close(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            if let Err(err) = writer.flush() {
+                warn!("Failed to flush dataset: {}", err);
+            }
+        }
+    }
+}
+
+fn open_dataset_system(mut recorder: ResMut<DatasetRecorder>) {
+    recorder.open();
+}
+
+// sticks are folded onto the same bits as the keys, past half deflection
+fn input_bits(input: &PlayerInput) -> u8 {
+    let mut bits = 0;
+    if input.movement.y > 0.5 {
+        bits |= INPUT_FORWARD;
+    }
+    if input.movement.y < -0.5 {
+        bits |= INPUT_BACK;
+    }
+    if input.movement.x < -0.5 {
+        bits |= INPUT_LEFT;
+    }
+    if input.movement.x > 0.5 {
+        bits |= INPUT_RIGHT;
+    }
+    if input.kick {
+        bits |= INPUT_KICK;
+    }
+    bits
+}
+
+fn write_vec3(buf: &mut Vec<u8>, v: Vec3) {
+    for c in v.to_array() {
+        buf.extend_from_slice(&c.to_le_bytes());
+    }
+}
+
+fn record_tick_system(
+    mut recorder: ResMut<DatasetRecorder>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    team_query: Query<(&Transform, &Team, Option<&Player>), Without<Ball>>,
+    input: Res<PlayerInput>,
+    score: Res<Score>,
+    entities: Res<MatchEntities>,
+    time: Res<Time>,
+) {
+    recorder.timer.tick(time.delta());
+    if !recorder.timer.just_finished() || recorder.writer.is_none() {
+        return;
+    }
+    // a sample only has room for one ball, multiball records the first
+    let Some(Ok((ball_tf, ball_vel))) = entities.ball.map(|ball| ball_query.get(ball)) else {
+        return;
+    };
+
+    // +1 for every goal scored since the last tick, -1 for every goal conceded
+    let reward = score.home.saturating_sub(recorder.last_goals) as i8
+        - score.away.saturating_sub(recorder.last_conceded) as i8;
+    recorder.last_goals = score.home;
+    recorder.last_conceded = score.away;
+
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(&recorder.tick.to_le_bytes());
+    buf.push(reward as u8);
+    write_vec3(&mut buf, ball_tf.translation);
+    write_vec3(&mut buf, ball_vel.linvel);
+
+    let entities: Vec<_> = team_query.iter().take(u8::MAX as usize).collect();
+    buf.push(entities.len() as u8);
+    for (tf, team, player) in entities {
+        buf.push(*team as u8);
+        buf.push(if player.is_some() {
+            input_bits(&input)
+        } else {
+            0
+        });
+        write_vec3(&mut buf, tf.translation);
+    }
+
+    recorder.tick += 1;
+    let failed = recorder
+        .writer
+        .as_mut()
+        .map(|writer| writer.write_all(&buf).is_err())
+        .unwrap_or_default();
+    if failed {
+        warn!("Dataset write failed, stopping recording");
+        recorder.writer = None;
+    }
+}
+
+fn rotate_dataset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut recorder: ResMut<DatasetRecorder>,
+) {
+    if rematch_events.iter().count() > 0 {
+        recorder.open();
+    }
+}
+
+fn close_dataset_system(
+    mut exit_events: EventReader<AppExit>,
+    mut recorder: ResMut<DatasetRecorder>,
+) {
+    if exit_events.iter().count() > 0 {
+        recorder.close();
+    }
+}
@@ -0,0 +1,132 @@
+use super::BOARD_DIM;
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::window::{CreateWindow, WindowClosed, WindowId};
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+
+pub struct DebugWindowPlugin;
+
+impl Plugin for DebugWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DebugWindow(None))
+            .add_system(toggle_debug_window_system)
+            .add_system(debug_window_closed_system)
+            .add_system(debug_labels_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct DebugWindow(pub Option<WindowId>);
+
+#[derive(Component)]
+pub struct DebugCamera;
+
+// F4 opens a top-down view in its own OS window; the rapier debug lines
+// are regular world geometry so the second camera picks them up too
+fn toggle_debug_window_system(
+    mut commands: Commands,
+    mut debug_window: ResMut<DebugWindow>,
+    mut create_window_events: EventWriter<CreateWindow>,
+    mut windows: ResMut<Windows>,
+    keyboard: Res<Input<KeyCode>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F4) {
+        return;
+    }
+
+    if let Some(id) = debug_window.0 {
+        if let Some(window) = windows.get_mut(id) {
+            window.close();
+        }
+        return;
+    }
+
+    let id = WindowId::new();
+    create_window_events.send(CreateWindow {
+        id,
+        descriptor: WindowDescriptor {
+            width: 480.0,
+            height: 800.0,
+            title: "Playground debug view".to_string(),
+            ..default()
+        },
+    });
+
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(id),
+                priority: 1,
+                ..default()
+            },
+            projection: OrthographicProjection {
+                scale: (BOARD_DIM.2 + 4.0) / 2.0,
+                scaling_mode: ScalingMode::FixedVertical(2.0),
+                ..default()
+            }
+            .into(),
+            transform: Transform::from_xyz(0.0, 30.0, 0.0).looking_at(Vec3::ZERO, Vec3::Z),
+            ..default()
+        },
+        DebugCamera,
+        Name::new("DebugCamera"),
+    ));
+    debug_window.0 = Some(id);
+}
+
+fn debug_window_closed_system(
+    mut commands: Commands,
+    mut debug_window: ResMut<DebugWindow>,
+    mut closed_events: EventReader<WindowClosed>,
+    camera_query: Query<Entity, With<DebugCamera>>,
+) {
+    for ev in closed_events.iter() {
+        if Some(ev.id) != debug_window.0 {
+            continue;
+        }
+        for entity in camera_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        debug_window.0 = None;
+    }
+}
+
+fn debug_labels_system(
+    mut egui_context: ResMut<EguiContext>,
+    debug_window: Res<DebugWindow>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<DebugCamera>>,
+    labelled_query: Query<(&Name, &GlobalTransform), With<Collider>>,
+) {
+    let Some(id) = debug_window.0 else {
+        return;
+    };
+    let (Some(window), Ok((camera, camera_tf))) = (windows.get(id), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(ctx) = egui_context.try_ctx_for_window_mut(id) else {
+        return;
+    };
+
+    egui::Area::new("debug_labels")
+        .fixed_pos(egui::pos2(0.0, 0.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            let painter = ui.painter();
+            for (name, tf) in labelled_query.iter() {
+                let Some(pos) = camera.world_to_viewport(camera_tf, tf.translation()) else {
+                    continue;
+                };
+                // viewport coordinates start at the bottom left
+                painter.text(
+                    egui::pos2(pos.x, window.height() - pos.y),
+                    egui::Align2::CENTER_CENTER,
+                    name.as_str(),
+                    egui::FontId::monospace(11.0),
+                    egui::Color32::LIGHT_GREEN,
+                );
+            }
+        });
+}
@@ -0,0 +1,275 @@
+use super::cli::StartupArgs;
+#[cfg(feature = "develop")]
+use super::marks::Ball;
+use super::marks::Team;
+use super::rematch::RematchEvent;
+use super::spin;
+#[cfg(feature = "develop")]
+use super::storage;
+#[cfg(feature = "develop")]
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::time::FixedTimestep;
+use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+#[cfg(feature = "develop")]
+use serde::{Deserialize, Serialize};
+use std::hash::Hasher;
+
+// rapier steps exactly this much, TICK_DT of real time apart
+pub const TICK_DT: f32 = 1.0 / 60.0;
+const FIXED_PHYSICS_STAGE: &str = "fixed_physics";
+#[cfg(feature = "develop")]
+const CHECKSUM_STAGE: &str = "checksum";
+#[cfg(feature = "develop")]
+const CHECKSUM_INTERVAL: u32 = 60;
+#[cfg(feature = "develop")]
+const CHECKSUM_DIR: &str = "checksums";
+
+// usage: [--deterministic] [--seed <n>] and, in develop builds,
+// [--record-checksums <name> | --verify-checksums <name>]
+pub struct DeterminismPlugin {
+    // steps rapier from a schedule of its own at a fixed rate, off for headless runs
+    // and online sessions, which step it once per tick themselves
+    pub fixed_schedule: bool,
+}
+
+// the feature also builds rapier with enhanced-determinism
+pub fn deterministic(args: &StartupArgs) -> bool {
+    cfg!(feature = "deterministic") || args.deterministic
+}
+
+impl Plugin for DeterminismPlugin {
+    fn build(&self, app: &mut App) {
+        let args = app
+            .world
+            .get_resource::<StartupArgs>()
+            .cloned()
+            .unwrap_or_default();
+
+        let deterministic = deterministic(&args);
+        let fixed_schedule = deterministic && self.fixed_schedule;
+        let seed = match args.seed {
+            Some(seed) => seed,
+            None if deterministic => 0,
+            None => rand::random(),
+        };
+        info!("Random seed {}", seed);
+        app.insert_resource(GameRng::new(seed))
+            .add_system(reseed_system);
+
+        if deterministic {
+            info!("Deterministic physics, fixed {:.4}s steps", TICK_DT);
+            app.insert_resource(RapierConfiguration {
+                timestep_mode: TimestepMode::Fixed {
+                    dt: TICK_DT,
+                    substeps: 1,
+                },
+                ..default()
+            });
+        }
+        // a slow frame steps more than once and a fast one not at all, so the match
+        // runs at the same speed whatever the frame rate
+        if fixed_schedule {
+            app.add_stage_after(CoreStage::Update, FIXED_PHYSICS_STAGE, fixed_physics_schedule())
+                .add_system_set_to_stage(
+                    CoreStage::Last,
+                    RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsStages::DetectDespawn),
+                );
+        }
+
+        #[cfg(feature = "develop")]
+        {
+            let mut checksums = Checksums::default();
+            if let Some(name) = &args.verify_checksums {
+                match storage::load::<ChecksumLog>(&checksum_file(name)) {
+                    Some(log) => checksums.expected = Some(log),
+                    None => warn!("No checksum log named {}", name),
+                }
+            } else {
+                checksums.record = args.record_checksums.clone();
+            }
+            app.insert_resource(checksums)
+                .add_system(checksum_save_system);
+            // the fixed schedule checksums after each of its steps
+            if !fixed_schedule {
+                app.add_system_to_stage(CoreStage::PostUpdate, checksum_system);
+            }
+        }
+    }
+}
+
+fn physics_stage(stage: PhysicsStages) -> SystemStage {
+    SystemStage::parallel().with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(stage))
+}
+
+// the stages rapier would add itself, for schedules that decide when to step it
+pub fn physics_schedule() -> Schedule {
+    Schedule::default()
+        .with_stage(
+            PhysicsStages::SyncBackend,
+            physics_stage(PhysicsStages::SyncBackend),
+        )
+        .with_stage_before(
+            PhysicsStages::SyncBackend,
+            spin::MAGNUS_STAGE,
+            spin::magnus_stage(),
+        )
+        .with_stage_after(
+            PhysicsStages::SyncBackend,
+            PhysicsStages::StepSimulation,
+            physics_stage(PhysicsStages::StepSimulation),
+        )
+        .with_stage_after(
+            PhysicsStages::StepSimulation,
+            PhysicsStages::Writeback,
+            physics_stage(PhysicsStages::Writeback),
+        )
+}
+
+// looped as many times as TICK_DT fits in the time since the last frame
+fn fixed_physics_schedule() -> Schedule {
+    let schedule = physics_schedule().with_run_criteria(FixedTimestep::step(TICK_DT as f64));
+    #[cfg(feature = "develop")]
+    let schedule = schedule.with_stage_after(
+        PhysicsStages::Writeback,
+        CHECKSUM_STAGE,
+        SystemStage::single(checksum_system),
+    );
+    schedule
+}
+
+// every gameplay roll goes through this, so a seed replays the same match
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    pub rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+// each match starts the sequence over
+fn reseed_system(mut rematch_events: EventReader<RematchEvent>, mut rng: ResMut<GameRng>) {
+    if rematch_events.iter().count() > 0 {
+        let seed = rng.seed;
+        *rng = GameRng::new(seed);
+    }
+}
+
+fn hash_vec3(hasher: &mut impl Hasher, v: Vec3) {
+    for c in v.to_array() {
+        hasher.write_u32(c.to_bits());
+    }
+}
+
+// the ball and every team member, bit for bit; team members go by side then name,
+// so two on the same side hash in the same order whichever spawned first
+pub fn state_hash<'a>(
+    balls: impl Iterator<Item = (&'a Transform, &'a Velocity)>,
+    players: impl Iterator<Item = (&'a Team, Option<&'a Name>, &'a Transform)>,
+) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for (ball_tf, ball_vel) in balls {
+        hash_vec3(&mut hasher, ball_tf.translation);
+        hash_vec3(&mut hasher, ball_vel.linvel);
+    }
+    let mut players: Vec<_> = players.collect();
+    let key = |(team, name, _): &(&Team, Option<&'a Name>, &Transform)| {
+        (**team as u8, name.map(Name::as_str))
+    };
+    players.sort_by(|a, b| key(a).cmp(&key(b)));
+    for (.., tf) in players {
+        hash_vec3(&mut hasher, tf.translation);
+    }
+    hasher.finish()
+}
+
+#[cfg(feature = "develop")]
+fn checksum_file(name: &str) -> String {
+    format!("{}/{}.ron", CHECKSUM_DIR, name)
+}
+
+// one hash every CHECKSUM_INTERVAL physics steps
+#[cfg(feature = "develop")]
+#[derive(Default, Serialize, Deserialize)]
+struct ChecksumLog {
+    hashes: Vec<u64>,
+}
+
+#[cfg(feature = "develop")]
+impl storage::Versioned for ChecksumLog {
+    const VERSION: u32 = 1;
+}
+
+#[cfg(feature = "develop")]
+#[derive(Resource, Default)]
+struct Checksums {
+    tick: u32,
+    log: ChecksumLog,
+    record: Option<String>,
+    expected: Option<ChecksumLog>,
+    diverged: bool,
+}
+
+// counts only the frames physics actually stepped, pauses don't shift the ticks
+#[cfg(feature = "develop")]
+fn checksum_system(
+    mut checksums: ResMut<Checksums>,
+    rapier_config: Res<RapierConfiguration>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    team_query: Query<(&Team, Option<&Name>, &Transform), Without<Ball>>,
+) {
+    if !rapier_config.physics_pipeline_active {
+        return;
+    }
+    checksums.tick += 1;
+    if checksums.tick % CHECKSUM_INTERVAL != 0 {
+        return;
+    }
+
+    let hash = state_hash(ball_query.iter(), team_query.iter());
+    let index = checksums.log.hashes.len();
+    checksums.log.hashes.push(hash);
+    debug!("Checksum at tick {}: {:016x}", checksums.tick, hash);
+
+    let expected = checksums
+        .expected
+        .as_ref()
+        .and_then(|log| log.hashes.get(index))
+        .copied();
+    if let Some(expected) = expected {
+        if expected != hash && !checksums.diverged {
+            error!(
+                "Simulation diverged at tick {}: expected {:016x}, got {:016x}",
+                checksums.tick, expected, hash
+            );
+            checksums.diverged = true;
+        }
+    }
+}
+
+#[cfg(feature = "develop")]
+fn checksum_save_system(mut exit_events: EventReader<AppExit>, checksums: Res<Checksums>) {
+    if exit_events.iter().count() == 0 {
+        return;
+    }
+    if let Some(name) = &checksums.record {
+        storage::save(&checksum_file(name), &checksums.log);
+        info!(
+            "Saved checksum log {} ({} hashes)",
+            name,
+            checksums.log.hashes.len()
+        );
+    }
+    if checksums.expected.is_some() && !checksums.diverged {
+        info!("No divergence in {} checksums", checksums.log.hashes.len());
+    }
+}
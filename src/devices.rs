@@ -0,0 +1,275 @@
+use super::game_state::GameState;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+pub struct DevicesPlugin;
+
+impl Plugin for DevicesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DeviceAssignment::default())
+            .add_system(gamepad_hotplug_system)
+            .add_system(active_device_system.after(gamepad_hotplug_system))
+            .add_system(disconnected_prompt_system.after(gamepad_hotplug_system))
+            .add_system(device_assignment_ui_system);
+    }
+}
+
+pub const MAX_LOCAL_PLAYERS: usize = 4;
+
+// two players can share one keyboard, each with their own cluster of keys,
+// see bindings.rs for which keys
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyboardSet {
+    A,
+    B,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputDevice {
+    Keyboard(KeyboardSet),
+    Gamepad(Gamepad),
+}
+
+impl InputDevice {
+    pub fn label(&self) -> String {
+        match self {
+            InputDevice::Keyboard(KeyboardSet::A) => "Keyboard set A".to_string(),
+            InputDevice::Keyboard(KeyboardSet::B) => "Keyboard set B".to_string(),
+            InputDevice::Gamepad(gamepad) => format!("Gamepad {}", gamepad.id),
+        }
+    }
+
+    pub fn is_keyboard(&self) -> bool {
+        matches!(self, InputDevice::Keyboard(_))
+    }
+}
+
+// which device drives each local player slot
+#[derive(Resource)]
+pub struct DeviceAssignment {
+    pub slots: Vec<InputDevice>,
+    // hand newly connected pads to the first keyboard slot until the player picks manually
+    pub auto: bool,
+    // slot whose pad went away mid-match, play is paused until it is sorted out
+    pub disconnected: Option<(usize, Gamepad)>,
+    screen_open: bool,
+    // the disconnect paused the match, so sorting it out resumes it
+    paused: bool,
+}
+
+impl Default for DeviceAssignment {
+    fn default() -> Self {
+        Self {
+            slots: vec![InputDevice::Keyboard(KeyboardSet::A)],
+            auto: true,
+            disconnected: None,
+            screen_open: false,
+            paused: false,
+        }
+    }
+}
+
+impl DeviceAssignment {
+    pub fn device(&self, slot: usize) -> InputDevice {
+        self.slots
+            .get(slot)
+            .copied()
+            .unwrap_or(InputDevice::Keyboard(KeyboardSet::A))
+    }
+
+    // a new slot takes the first device nobody else is using
+    pub fn add_slot(&mut self, available: &[InputDevice]) {
+        if self.slots.len() >= MAX_LOCAL_PLAYERS {
+            return;
+        }
+        let device = available
+            .iter()
+            .find(|device| !self.slots.contains(device))
+            .copied()
+            .unwrap_or(InputDevice::Keyboard(KeyboardSet::B));
+        self.slots.push(device);
+    }
+
+    fn resolve_disconnect(&mut self, state: &mut State<GameState>) {
+        if self.disconnected.take().is_none() || !std::mem::take(&mut self.paused) {
+            return;
+        }
+        if *state.current() == GameState::Paused {
+            state.pop().ok();
+        }
+    }
+}
+
+// polls the connected pads rather than reading events, so a pad that drops and
+// comes back within a frame is still noticed
+fn gamepad_hotplug_system(
+    gamepads: Res<Gamepads>,
+    mut assignment: ResMut<DeviceAssignment>,
+    mut state: ResMut<State<GameState>>,
+    mut known: Local<Vec<Gamepad>>,
+) {
+    let connected: Vec<Gamepad> = gamepads.iter().collect();
+
+    for gamepad in connected.iter().filter(|g| !known.contains(g)) {
+        info!("Gamepad {} connected", gamepad.id);
+        if assignment.disconnected.map(|(_, g)| g) == Some(*gamepad) {
+            assignment.resolve_disconnect(&mut state);
+        } else if assignment.auto {
+            let used = assignment.slots.contains(&InputDevice::Gamepad(*gamepad));
+            let free = assignment
+                .slots
+                .iter()
+                .position(|device| device.is_keyboard());
+            if let (false, Some(slot)) = (used, free) {
+                assignment.slots[slot] = InputDevice::Gamepad(*gamepad);
+            }
+        }
+    }
+
+    for gamepad in known.iter().filter(|g| !connected.contains(g)) {
+        info!("Gamepad {} disconnected", gamepad.id);
+        let slot = assignment
+            .slots
+            .iter()
+            .position(|device| *device == InputDevice::Gamepad(*gamepad));
+        if let (Some(slot), None) = (slot, assignment.disconnected) {
+            assignment.disconnected = Some((slot, *gamepad));
+            // the same pause as the pause key, the clock and everything else stop too
+            if *state.current() == GameState::Playing {
+                assignment.paused = state.push(GameState::Paused).is_ok();
+            }
+        }
+    }
+
+    *known = connected;
+}
+
+// with a single player and no manual assignment, whichever device was touched last drives
+fn active_device_system(
+    mut assignment: ResMut<DeviceAssignment>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    scans: Res<Input<ScanCode>>,
+) {
+    if !assignment.auto || assignment.slots.len() != 1 || assignment.disconnected.is_some() {
+        return;
+    }
+
+    let touched = gamepads.iter().find(|gamepad| {
+        let stick = |kind| {
+            axes.get(GamepadAxis::new(*gamepad, kind))
+                .unwrap_or_default()
+                .abs()
+                > 0.5
+        };
+        buttons
+            .get_just_pressed()
+            .any(|button| button.gamepad == *gamepad)
+            || stick(GamepadAxisType::LeftStickX)
+            || stick(GamepadAxisType::LeftStickY)
+    });
+    let device = match touched {
+        Some(gamepad) => InputDevice::Gamepad(gamepad),
+        None if scans.get_just_pressed().next().is_some() => InputDevice::Keyboard(KeyboardSet::A),
+        None => return,
+    };
+    if assignment.slots[0] != device {
+        info!("Switching to {}", device.label());
+        assignment.slots[0] = device;
+    }
+}
+
+fn disconnected_prompt_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut assignment: ResMut<DeviceAssignment>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let Some((slot, gamepad)) = assignment.disconnected else {
+        return;
+    };
+
+    egui::Window::new("Controller disconnected")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!(
+                "Player {} lost gamepad {}. Reconnect it to carry on.",
+                slot + 1,
+                gamepad.id
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Use keyboard").clicked() {
+                    assignment.slots[slot] = InputDevice::Keyboard(KeyboardSet::A);
+                    assignment.resolve_disconnect(&mut state);
+                }
+                if ui.button("Assign devices").clicked() {
+                    assignment.screen_open = true;
+                }
+            });
+        });
+}
+
+// F5 opens the lobby, where each local player slot is handed an input device
+fn device_assignment_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut assignment: ResMut<DeviceAssignment>,
+    mut state: ResMut<State<GameState>>,
+    gamepads: Res<Gamepads>,
+    keyboard: Res<Input<KeyCode>>,
+) {
+    if keyboard.just_pressed(KeyCode::F5) {
+        assignment.screen_open = !assignment.screen_open;
+    }
+    if !assignment.screen_open {
+        return;
+    }
+
+    let mut devices = vec![
+        InputDevice::Keyboard(KeyboardSet::A),
+        InputDevice::Keyboard(KeyboardSet::B),
+    ];
+    devices.extend(gamepads.iter().map(InputDevice::Gamepad));
+
+    let mut open = true;
+    let mut edited = DeviceAssignment {
+        slots: assignment.slots.clone(),
+        ..default()
+    };
+    egui::Window::new("Lobby")
+        .open(&mut open)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            for (slot, device) in edited.slots.iter_mut().enumerate() {
+                egui::ComboBox::from_label(format!("Player {}", slot + 1))
+                    .selected_text(device.label())
+                    .show_ui(ui, |ui| {
+                        for candidate in devices.iter() {
+                            ui.selectable_value(device, *candidate, candidate.label());
+                        }
+                    });
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Add player").clicked() {
+                    edited.add_slot(&devices);
+                }
+                // the first slot always drives the on-field player
+                if edited.slots.len() > 1 && ui.button("Remove player").clicked() {
+                    edited.slots.pop();
+                }
+            });
+        });
+
+    if edited.slots != assignment.slots {
+        assignment.slots = edited.slots;
+        assignment.auto = false;
+        // picking a connected device for the stranded slot settles the disconnect
+        if let Some((slot, gamepad)) = assignment.disconnected {
+            if assignment.slots.get(slot) != Some(&InputDevice::Gamepad(gamepad)) {
+                assignment.resolve_disconnect(&mut state);
+            }
+        }
+    }
+    assignment.screen_open = open;
+}
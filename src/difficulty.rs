@@ -0,0 +1,134 @@
+use super::cli::StartupArgs;
+use super::marks::Team;
+use super::player::KickEvent;
+use super::Score;
+use bevy::prelude::*;
+
+// how often the adaptive controller re-evaluates the match
+const SAMPLE_WINDOW: f32 = 15.0;
+const ADJUST_STEP: f32 = 0.1;
+// the adaptive controller never strays further than this from the chosen base
+const ADAPTIVE_RANGE: f32 = 0.3;
+// kicks per minute a comfortable player tends to land
+const BASELINE_SHOT_RATE: f32 = 8.0;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        let args = app
+            .world
+            .get_resource::<StartupArgs>()
+            .cloned()
+            .unwrap_or_default();
+        app.insert_resource(Difficulty::from_args(&args))
+            .insert_resource(DifficultySampler::default())
+            .add_event::<KickEvent>()
+            .add_system(adaptive_difficulty_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct Difficulty {
+    // 0.0 is the gentlest AI, 1.0 the sharpest
+    pub base: f32,
+    pub level: f32,
+    pub adaptive: bool,
+}
+
+impl Difficulty {
+    pub fn from_args(args: &StartupArgs) -> Self {
+        Self {
+            base: 0.5,
+            level: 0.5,
+            adaptive: args.adaptive,
+        }
+    }
+
+    pub fn speed_scale(&self) -> f32 {
+        0.75 + self.level * 0.5
+    }
+
+    // seconds an AI waits before committing to a reaction
+    pub fn reaction_time(&self) -> f32 {
+        0.4 - self.level * 0.35
+    }
+}
+
+#[derive(Resource)]
+struct DifficultySampler {
+    timer: Timer,
+    goals: u32,
+    conceded: u32,
+    kicks: u32,
+}
+
+impl Default for DifficultySampler {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(SAMPLE_WINDOW, TimerMode::Repeating),
+            goals: 0,
+            conceded: 0,
+            kicks: 0,
+        }
+    }
+}
+
+fn adaptive_difficulty_system(
+    mut difficulty: ResMut<Difficulty>,
+    mut sampler: ResMut<DifficultySampler>,
+    mut kick_events: EventReader<KickEvent>,
+    score: Res<Score>,
+    time: Res<Time>,
+) {
+    // the player's shot rate, the AI's own kicks say nothing about how they're doing
+    sampler.kicks += kick_events
+        .iter()
+        .filter(|ev| ev.team == Team::Home)
+        .count() as u32;
+    if !difficulty.adaptive {
+        return;
+    }
+
+    sampler.timer.tick(time.delta());
+    if !sampler.timer.just_finished() {
+        return;
+    }
+
+    let goals = counted_since(score.home, sampler.goals) as i32;
+    let conceded = counted_since(score.away, sampler.conceded) as i32;
+    let shot_rate = sampler.kicks as f32 * 60.0 / SAMPLE_WINDOW;
+
+    let mut pressure = (goals - conceded) as f32;
+    if shot_rate > BASELINE_SHOT_RATE * 1.5 {
+        pressure += 0.5;
+    } else if shot_rate < BASELINE_SHOT_RATE * 0.5 {
+        pressure -= 0.5;
+    }
+
+    if pressure.abs() >= 0.5 {
+        let step = ADJUST_STEP * pressure.signum();
+        let low = (difficulty.base - ADAPTIVE_RANGE).max(0.0);
+        let high = (difficulty.base + ADAPTIVE_RANGE).min(1.0);
+        difficulty.level = (difficulty.level + step).clamp(low, high);
+        debug!(
+            "Adaptive difficulty now {:.2} (goal diff {}, {:.0} shots/min)",
+            difficulty.level,
+            goals - conceded,
+            shot_rate
+        );
+    }
+
+    sampler.goals = score.home;
+    sampler.conceded = score.away;
+    sampler.kicks = 0;
+}
+
+// rematches reset the score, in which case everything on the board is new
+fn counted_since(now: u32, before: u32) -> u32 {
+    if now >= before {
+        now - before
+    } else {
+        now
+    }
+}
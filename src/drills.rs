@@ -0,0 +1,278 @@
+use super::cli::StartupArgs;
+use super::game_state::GameState;
+use super::marks::{Ball, MatchEntity, Player};
+use super::mode::GameMode;
+use super::rematch::RematchEvent;
+use super::Score;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+const DRILL_DIR: &str = "assets/drills";
+// pause between attempts so the result can be read
+const ATTEMPT_GAP: f32 = 1.5;
+
+pub struct DrillPlugin;
+
+pub struct DrillMode;
+
+impl GameMode for DrillMode {
+    fn name(&self) -> &'static str {
+        "drill"
+    }
+
+    fn title(&self) -> &'static str {
+        "Training drill"
+    }
+
+    fn description(&self) -> &'static str {
+        "Practice scenarios scored against par."
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_plugin(DrillPlugin);
+    }
+
+    // scored over a set number of attempts, however long they take
+    fn timed(&self) -> bool {
+        false
+    }
+}
+
+impl Plugin for DrillPlugin {
+    fn build(&self, app: &mut App) {
+        let name = app
+            .world
+            .get_resource::<StartupArgs>()
+            .and_then(|args| args.drill.clone().flatten())
+            .unwrap_or_else(|| "top_corner_volley".to_string());
+        let Some(drill) = Drill::load(&name) else {
+            return;
+        };
+
+        app.insert_resource(DrillRun::new(drill))
+            .add_startup_system(setup_drill_targets)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(drill_attempt_system),
+            )
+            .add_system(drill_reset_system)
+            .add_system(drill_ui_system);
+    }
+}
+
+// a practice scenario authored in assets/drills/<name>.ron
+#[derive(Deserialize, Clone)]
+pub struct Drill {
+    pub name: String,
+    pub description: String,
+    pub ball_position: [f32; 3],
+    pub ball_velocity: [f32; 3],
+    pub player_position: [f32; 3],
+    pub attempts: u32,
+    // seconds each attempt lasts before it counts as a miss
+    pub time_limit: f32,
+    pub par: u32,
+    // checked in order, the first zone the ball enters scores
+    pub targets: Vec<DrillTarget>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DrillTarget {
+    pub name: String,
+    pub center: [f32; 3],
+    pub half_extents: [f32; 3],
+    pub points: u32,
+}
+
+impl Drill {
+    pub fn load(name: &str) -> Option<Self> {
+        let path = format!("{}/{}.ron", DRILL_DIR, name);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("Cannot read drill {}: {}", path, err);
+                return None;
+            }
+        };
+        match ron::from_str(&text) {
+            Ok(drill) => Some(drill),
+            Err(err) => {
+                warn!("Ignoring invalid drill {}: {}", path, err);
+                None
+            }
+        }
+    }
+}
+
+impl DrillTarget {
+    fn contains(&self, point: Vec3) -> bool {
+        let offset = (point - Vec3::from_array(self.center)).abs();
+        offset.cmple(Vec3::from_array(self.half_extents)).all()
+    }
+}
+
+#[derive(Resource)]
+pub struct DrillRun {
+    pub drill: Drill,
+    pub attempt: u32,
+    pub points: u32,
+    pub last_result: Option<String>,
+    attempt_timer: Timer,
+    // counts down before the next attempt is served
+    gap_timer: Option<Timer>,
+    last_goals: u32,
+}
+
+impl DrillRun {
+    fn new(drill: Drill) -> Self {
+        Self {
+            attempt_timer: Timer::from_seconds(drill.time_limit, TimerMode::Once),
+            drill,
+            attempt: 0,
+            points: 0,
+            last_result: None,
+            gap_timer: Some(Timer::from_seconds(0.0, TimerMode::Once)),
+            last_goals: 0,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.attempt >= self.drill.attempts && self.gap_timer.is_some()
+    }
+
+    fn end_attempt(&mut self, result: String) {
+        self.last_result = Some(result);
+        self.gap_timer = Some(Timer::from_seconds(ATTEMPT_GAP, TimerMode::Once));
+    }
+}
+
+#[derive(Component)]
+struct DrillZone;
+
+fn setup_drill_targets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    run: Res<DrillRun>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgba(0.2, 1.0, 0.4, 0.25),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    for target in run.drill.targets.iter() {
+        let [x, y, z] = target.half_extents;
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Box::new(x * 2.0, y * 2.0, z * 2.0))),
+                material: material.clone(),
+                transform: Transform::from_translation(Vec3::from_array(target.center)),
+                ..default()
+            },
+            DrillZone,
+            Name::new(format!("drill target {}", target.name)),
+        ));
+    }
+}
+
+fn drill_attempt_system(
+    mut run: ResMut<DrillRun>,
+    time: Res<Time>,
+    score: Res<Score>,
+    mut ball_query: Query<(&mut Transform, &mut Velocity), (With<Ball>, With<MatchEntity>)>,
+    mut player_query: Query<&mut Transform, (With<Player>, Without<Ball>)>,
+) {
+    let Ok((mut ball_tf, mut ball_vel)) = ball_query.get_single_mut() else {
+        return;
+    };
+
+    // serve the next ball once the pause after the previous attempt is over
+    if let Some(gap) = run.gap_timer.as_mut() {
+        gap.tick(time.delta());
+        if !gap.finished() || run.attempt >= run.drill.attempts {
+            return;
+        }
+        run.gap_timer = None;
+        run.attempt += 1;
+        run.attempt_timer.reset();
+        run.last_goals = score.home;
+
+        ball_tf.translation = Vec3::from_array(run.drill.ball_position);
+        *ball_vel = Velocity::linear(Vec3::from_array(run.drill.ball_velocity));
+        if let Ok(mut player_tf) = player_query.get_single_mut() {
+            player_tf.translation = Vec3::from_array(run.drill.player_position);
+        }
+        return;
+    }
+
+    let hit = run
+        .drill
+        .targets
+        .iter()
+        .find(|target| target.contains(ball_tf.translation))
+        .map(|target| (target.name.clone(), target.points));
+    if let Some((name, points)) = hit {
+        run.points += points;
+        run.end_attempt(format!("{} +{}", name, points));
+        return;
+    }
+
+    // a goal outside every zone moves the ball back to kickoff, so it ends the attempt too
+    run.attempt_timer.tick(time.delta());
+    if score.home > run.last_goals {
+        run.end_attempt("goal, no target".to_string());
+    } else if run.attempt_timer.finished() {
+        run.end_attempt("missed".to_string());
+    }
+}
+
+fn drill_reset_system(mut rematch_events: EventReader<RematchEvent>, mut run: ResMut<DrillRun>) {
+    if rematch_events.iter().count() > 0 {
+        *run = DrillRun::new(run.drill.clone());
+    }
+}
+
+fn drill_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    run: Res<DrillRun>,
+    mut rematch_events: EventWriter<RematchEvent>,
+) {
+    egui::Window::new(&run.drill.name)
+        .anchor(egui::Align2::RIGHT_TOP, [-16.0, 16.0])
+        .resizable(false)
+        .collapsible(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(&run.drill.description);
+            ui.separator();
+            ui.label(format!(
+                "Attempt {} / {}",
+                run.attempt.max(1),
+                run.drill.attempts
+            ));
+            if run.gap_timer.is_none() {
+                ui.label(format!(
+                    "Time left: {:.1}s",
+                    run.attempt_timer.remaining_secs()
+                ));
+            }
+            ui.label(format!("Points: {} (par {})", run.points, run.drill.par));
+            if let Some(result) = &run.last_result {
+                ui.label(result);
+            }
+
+            if run.finished() {
+                ui.separator();
+                let verdict = if run.points >= run.drill.par {
+                    egui::RichText::new("Par beaten!").color(egui::Color32::GREEN)
+                } else {
+                    egui::RichText::new("Below par").color(egui::Color32::LIGHT_RED)
+                };
+                ui.heading(verdict);
+                if ui.button("Try again").clicked() {
+                    rematch_events.send(RematchEvent);
+                }
+            }
+        });
+}
@@ -0,0 +1,494 @@
+use super::arena::{Arena, ArenaPiece, ArenaPieceId, PieceKind, PropShape};
+use super::arena_validation::{self, ArenaIssue, Severity};
+use super::marks::GameCamera;
+use super::undo::{EditCommand, EditHistory};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use simula_viz::grid::Grid;
+
+const PICK_DISTANCE: f32 = 200.0;
+const HANDLE_LENGTH: f32 = 1.5;
+const HANDLE_SIZE: f32 = 16.0;
+// how far the mouse travels across a handle for one radian of rotation
+const PIXELS_PER_RADIAN: f32 = 120.0;
+const ROTATION_SNAP: f32 = 15.0;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EditorSelection(None))
+            .insert_resource(Gizmo::default())
+            .add_system(pick_system)
+            .add_system(arena_editor_ui_system)
+            .add_system(selection_inspector_system.after(pick_system))
+            .add_system(selection_gizmo_system.after(selection_inspector_system));
+    }
+}
+
+#[derive(Resource)]
+pub struct EditorSelection(pub Option<Entity>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+struct GizmoDrag {
+    axis: usize,
+    start: Transform,
+    before: Option<(usize, ArenaPiece)>,
+    // accumulated mouse travel, world units or radians depending on the mode
+    amount: f32,
+}
+
+#[derive(Resource)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub snap: bool,
+    drag: Option<GizmoDrag>,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            snap: true,
+            drag: None,
+        }
+    }
+}
+
+impl Gizmo {
+    // translate runs along world axes, rotate and scale along the object's own
+    fn axis(&self, tf: &Transform, axis: usize) -> Vec3 {
+        let world = [Vec3::X, Vec3::Y, Vec3::Z][axis];
+        match self.mode {
+            GizmoMode::Translate => world,
+            GizmoMode::Rotate | GizmoMode::Scale => tf.rotation * world,
+        }
+    }
+
+    // translation snaps by the grid spacing rather than to the grid itself, so pieces
+    // that sit between lines keep their offset; scale snaps the size change the same way
+    fn apply(&self, drag: &GizmoDrag, spacing: f32, size: Vec3) -> Transform {
+        let mut tf = drag.start;
+        let snap = |value: f32, step: f32| {
+            if self.snap {
+                (value / step).round() * step
+            } else {
+                value
+            }
+        };
+        match self.mode {
+            GizmoMode::Translate => {
+                tf.translation += self.axis(&drag.start, drag.axis) * snap(drag.amount, spacing);
+            }
+            GizmoMode::Rotate => {
+                let angle = snap(drag.amount.to_degrees(), ROTATION_SNAP).to_radians();
+                let axis = [Vec3::X, Vec3::Y, Vec3::Z][drag.axis];
+                tf.rotation = drag.start.rotation * Quat::from_axis_angle(axis, angle);
+            }
+            GizmoMode::Scale => {
+                let full = size[drag.axis].max(0.01);
+                let grown = (full + snap(drag.amount, spacing)).max(spacing.min(full));
+                tf.scale[drag.axis] = drag.start.scale[drag.axis] * grown / full;
+            }
+        }
+        tf
+    }
+}
+
+// ray from the camera through a point on the window, window coordinates start at
+// the bottom left like the cursor
+fn cursor_ray(
+    camera: &Camera,
+    camera_tf: &GlobalTransform,
+    window: &Window,
+    cursor: Vec2,
+) -> (Vec3, Vec3) {
+    let size = Vec2::new(window.width(), window.height());
+    let ndc = cursor / size * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_tf.compute_matrix() * camera.projection_matrix().inverse();
+    // reverse z, 1 is the near plane
+    let near = ndc_to_world.project_point3(ndc.extend(1.0));
+    let far = ndc_to_world.project_point3(ndc.extend(0.5));
+    (near, (far - near).normalize_or_zero())
+}
+
+// left click picks whichever collider is under the cursor, clicking empty space clears
+fn pick_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut selection: ResMut<EditorSelection>,
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    rapier_context: Res<RapierContext>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || egui_context.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    let Ok((camera, camera_tf)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+
+    let (origin, direction) = cursor_ray(camera, camera_tf, window, cursor);
+    selection.0 = rapier_context
+        .cast_ray(
+            origin,
+            direction,
+            PICK_DISTANCE,
+            true,
+            QueryFilter::default(),
+        )
+        .map(|(entity, _)| entity);
+}
+
+// arena-wide actions, always on screen in develop builds
+fn arena_editor_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut history: ResMut<EditHistory>,
+    mut arena: ResMut<Arena>,
+    mut issues: Local<Option<Vec<ArenaIssue>>>,
+    time: Res<Time>,
+) {
+    egui::Window::new("Arena")
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!(
+                "{} ({})",
+                arena.descriptor.name,
+                arena.path.display()
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Add prop").clicked() {
+                    let piece = ArenaPiece::new(
+                        "Prop",
+                        PieceKind::Prop,
+                        Vec3::new(0.0, 0.5, 0.0),
+                        Vec3::splat(0.5),
+                    );
+                    let index = arena.descriptor.pieces.len();
+                    let command = EditCommand::Place { index, piece };
+                    history.record(command, &mut arena, time.elapsed_seconds_f64());
+                }
+                if ui
+                    .add_enabled(history.can_undo(), egui::Button::new("Undo"))
+                    .clicked()
+                {
+                    history.undo(&mut arena);
+                }
+                if ui
+                    .add_enabled(history.can_redo(), egui::Button::new("Redo"))
+                    .clicked()
+                {
+                    history.redo(&mut arena);
+                }
+                if ui.button("Validate").clicked() {
+                    *issues = Some(arena_validation::validate(&arena.descriptor));
+                }
+                if ui.button("Save").clicked() {
+                    match arena.descriptor.save(&arena.path) {
+                        Ok(()) => info!("Saved arena to {}", arena.path.display()),
+                        Err(err) => warn!("Failed to save {}: {}", arena.path.display(), err),
+                    }
+                }
+            });
+
+            let Some(issues) = issues.as_ref() else {
+                return;
+            };
+            ui.separator();
+            if issues.is_empty() {
+                ui.label("No problems found");
+            }
+            for issue in issues.iter() {
+                let color = match issue.severity {
+                    Severity::Warning => egui::Color32::YELLOW,
+                    Severity::Error => egui::Color32::LIGHT_RED,
+                };
+                ui.colored_label(color, issue.to_string());
+            }
+        });
+}
+
+fn selection_inspector_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut selection: ResMut<EditorSelection>,
+    mut gizmo: ResMut<Gizmo>,
+    mut arena: ResMut<Arena>,
+    mut history: ResMut<EditHistory>,
+    mut transforms: Query<(Option<&Name>, &mut Transform, Option<&ArenaPieceId>)>,
+    keyboard: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let Some(entity) = selection.0 else {
+        return;
+    };
+    let Ok((name, mut tf, piece)) = transforms.get_mut(entity) else {
+        selection.0 = None;
+        return;
+    };
+
+    let mut open = true;
+    let typing = egui_context.ctx_mut().wants_keyboard_input();
+    let mut delete = keyboard.just_pressed(KeyCode::Delete) && !typing;
+    let mut edited = *tf;
+    let before = piece.map(|ArenaPieceId(id)| arena.descriptor.pieces[*id].clone());
+    let mut properties = before.clone();
+    egui::Window::new("Selection")
+        .open(&mut open)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(match name {
+                Some(name) => format!("{} ({:?})", name, entity),
+                None => format!("{:?}", entity),
+            });
+            ui.horizontal(|ui| {
+                ui.label("Position");
+                ui.add(egui::DragValue::new(&mut edited.translation.x).speed(0.05));
+                ui.add(egui::DragValue::new(&mut edited.translation.y).speed(0.05));
+                ui.add(egui::DragValue::new(&mut edited.translation.z).speed(0.05));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Scale");
+                ui.add(egui::DragValue::new(&mut edited.scale.x).speed(0.01));
+                ui.add(egui::DragValue::new(&mut edited.scale.y).speed(0.01));
+                ui.add(egui::DragValue::new(&mut edited.scale.z).speed(0.01));
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut gizmo.mode, GizmoMode::Translate, "Move");
+                ui.selectable_value(&mut gizmo.mode, GizmoMode::Rotate, "Rotate");
+                ui.selectable_value(&mut gizmo.mode, GizmoMode::Scale, "Scale");
+                ui.checkbox(&mut gizmo.snap, "Snap to grid");
+            });
+            if let Some(properties) = &mut properties {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut properties.name);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Color");
+                    ui.color_edit_button_rgb(&mut properties.color);
+                    ui.checkbox(&mut properties.visible, "Visible");
+                });
+                if properties.kind == PieceKind::Prop {
+                    ui.horizontal(|ui| {
+                        ui.label("Shape");
+                        ui.selectable_value(&mut properties.shape, PropShape::Box, "Box");
+                        ui.selectable_value(&mut properties.shape, PropShape::Sphere, "Sphere");
+                    });
+                }
+                delete |= ui.button("Delete").clicked();
+            }
+        });
+
+    let now = time.elapsed_seconds_f64();
+    if let (Some(ArenaPieceId(index)), Some(before), Some(mut after)) = (piece, before, properties)
+    {
+        if delete {
+            selection.0 = None;
+            let command = EditCommand::Delete {
+                index: *index,
+                piece: before,
+            };
+            history.record(command, &mut arena, now);
+            return;
+        }
+        // the gizmo moves the entity ahead of the descriptor, so only what was
+        // touched in this window goes into the history
+        let restyled = after != before;
+        let moved = edited != *tf;
+        if moved {
+            after.set_transform(&edited);
+        }
+        if restyled || moved {
+            // anything beyond the transform needs a fresh mesh or material
+            history.dirty |= restyled;
+            let command = EditCommand::Change {
+                index: *index,
+                before,
+                after,
+            };
+            history.record(command, &mut arena, now);
+        }
+    }
+    if edited != *tf {
+        *tf = edited;
+    }
+    if !open {
+        selection.0 = None;
+    }
+}
+
+enum HandleEvent {
+    Started(usize),
+    Dragged(f32),
+    Released,
+}
+
+// collider bounds in yellow plus one draggable handle per axis, drawn over the game
+// camera view. Dropping a handle on an arena piece writes it back to the descriptor
+fn selection_gizmo_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut gizmo: ResMut<Gizmo>,
+    mut arena: ResMut<Arena>,
+    mut history: ResMut<EditHistory>,
+    selection: Res<EditorSelection>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    mut target_query: Query<(&mut Transform, Option<&Collider>, Option<&ArenaPieceId>)>,
+    grid_query: Query<&Grid>,
+    time: Res<Time>,
+) {
+    let Some(entity) = selection.0 else {
+        gizmo.drag = None;
+        return;
+    };
+    let Ok((mut tf, collider, piece)) = target_query.get_mut(entity) else {
+        return;
+    };
+    let Ok((camera, camera_tf)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let spacing = grid_query
+        .get_single()
+        .map(|grid| grid.size as f32 / grid.divisions as f32)
+        .unwrap_or(1.0);
+
+    let to_screen = |point: Vec3| {
+        camera
+            .world_to_viewport(camera_tf, point)
+            .map(|pos| egui::pos2(pos.x, window.height() - pos.y))
+    };
+    let matrix = tf.compute_matrix();
+    let mut segments = Vec::new();
+
+    if let Some(collider) = collider {
+        let aabb = collider.raw.compute_local_aabb();
+        let (min, max) = (Vec3::from(aabb.mins), Vec3::from(aabb.maxs));
+        let corner = |i: usize| {
+            matrix.transform_point3(Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            ))
+        };
+        // every pair of corners that differs in exactly one axis is an edge
+        for a in 0..8 {
+            for bit in [1, 2, 4] {
+                if a & bit == 0 {
+                    segments.push((corner(a), corner(a | bit)));
+                }
+            }
+        }
+    }
+
+    let origin = tf.translation;
+    let handles: Vec<_> = (0..3)
+        .map(|axis| (origin, origin + gizmo.axis(&tf, axis) * HANDLE_LENGTH))
+        .collect();
+    let colors = [
+        egui::Color32::RED,
+        egui::Color32::GREEN,
+        egui::Color32::BLUE,
+    ];
+
+    let mut event = None;
+    egui::Area::new("selection_gizmo")
+        .fixed_pos(egui::pos2(0.0, 0.0))
+        .show(egui_context.ctx_mut(), |ui| {
+            let painter = ui.painter();
+            for (from, to) in segments {
+                if let (Some(from), Some(to)) = (to_screen(from), to_screen(to)) {
+                    painter.line_segment([from, to], egui::Stroke::new(1.5, egui::Color32::YELLOW));
+                }
+            }
+
+            for (axis, (from, to)) in handles.iter().enumerate() {
+                let (Some(from), Some(to)) = (to_screen(*from), to_screen(*to)) else {
+                    continue;
+                };
+                painter.line_segment([from, to], egui::Stroke::new(2.0, colors[axis]));
+                painter.circle_filled(to, HANDLE_SIZE / 2.0, colors[axis]);
+
+                let rect = egui::Rect::from_center_size(to, egui::Vec2::splat(HANDLE_SIZE));
+                let response = ui.interact(rect, ui.id().with(axis), egui::Sense::drag());
+                if response.drag_started() {
+                    event = Some(HandleEvent::Started(axis));
+                } else if response.drag_released() {
+                    event = Some(HandleEvent::Released);
+                } else if response.dragged() {
+                    let along = to - from;
+                    let delta = response.drag_delta();
+                    let amount = match gizmo.mode {
+                        // sweeping across the handle turns around its axis
+                        GizmoMode::Rotate => {
+                            (delta.y * along.x - delta.x * along.y)
+                                / along.length().max(1.0)
+                                / PIXELS_PER_RADIAN
+                        }
+                        GizmoMode::Translate | GizmoMode::Scale => {
+                            delta.dot(along) / along.length_sq().max(1.0) * HANDLE_LENGTH
+                        }
+                    };
+                    event = Some(HandleEvent::Dragged(amount));
+                }
+            }
+        });
+
+    let size = piece
+        .map(|ArenaPieceId(id)| arena.descriptor.pieces[*id].extents() * 2.0)
+        .unwrap_or(tf.scale);
+    match event {
+        Some(HandleEvent::Started(axis)) => {
+            gizmo.drag = Some(GizmoDrag {
+                axis,
+                start: *tf,
+                before: piece.map(|ArenaPieceId(id)| (*id, arena.descriptor.pieces[*id].clone())),
+                amount: 0.0,
+            });
+        }
+        Some(HandleEvent::Dragged(amount)) => {
+            if let Some(mut drag) = gizmo.drag.take() {
+                drag.amount += amount;
+                *tf = gizmo.apply(&drag, spacing, size);
+                gizmo.drag = Some(drag);
+            }
+        }
+        Some(HandleEvent::Released) => {
+            let Some(GizmoDrag {
+                before: Some((index, before)),
+                ..
+            }) = gizmo.drag.take()
+            else {
+                return;
+            };
+            let mut after = before.clone();
+            after.set_transform(&tf);
+            if after != before {
+                let command = EditCommand::Change {
+                    index,
+                    before,
+                    after,
+                };
+                history.record(command, &mut arena, time.elapsed_seconds_f64());
+            }
+        }
+        None => {}
+    }
+}
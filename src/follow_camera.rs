@@ -0,0 +1,101 @@
+use super::marks::{Ball, GameCamera, Player, PlayerIndex};
+use super::settings::GameSettings;
+use super::splitscreen::SplitScreen;
+use super::MatchEntities;
+use bevy::prelude::*;
+
+// how much the framed point leans from the player toward the ball
+const BALL_WEIGHT: f32 = 0.4;
+// behind the action on the home side, the height comes from the settings
+const FOLLOW_BACK: f32 = -14.0;
+const FOLLOW_SIDE: f32 = -1.5;
+// pulled back this much further per meter between player and ball
+const ZOOM_PER_METER: f32 = 0.06;
+const MIN_ZOOM: f32 = 0.8;
+const MAX_ZOOM: f32 = 2.2;
+const ZOOM_STIFFNESS: f32 = 1.5;
+
+pub struct FollowCameraPlugin;
+
+impl Plugin for FollowCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(zoom_system)
+            .add_system(follow_camera_system.after(zoom_system));
+    }
+}
+
+// the shared camera tracking the player and ball outside develop builds
+#[derive(Component)]
+pub struct FollowCamera {
+    // smoothed point the camera looks at
+    pub focus: Vec3,
+    // scales the offset so a spread out play stays in frame
+    pub zoom: f32,
+}
+
+impl Default for FollowCamera {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl FollowCamera {
+    pub fn offset(height: f32) -> Vec3 {
+        Vec3::new(FOLLOW_SIDE, height, FOLLOW_BACK)
+    }
+}
+
+fn follow_camera_system(
+    settings: Res<GameSettings>,
+    split: Res<SplitScreen>,
+    mut camera_query: Query<(&mut FollowCamera, &mut Transform), With<GameCamera>>,
+    player_query: Query<(&PlayerIndex, &Transform), (With<Player>, Without<GameCamera>)>,
+    ball_query: Query<&Transform, (With<Ball>, Without<GameCamera>)>,
+    entities: Res<MatchEntities>,
+    time: Res<Time>,
+) {
+    // each half of a split window follows its own player
+    if split.active {
+        return;
+    }
+    let Some((_, player_tf)) = player_query.iter().find(|(index, _)| index.0 == 0) else {
+        return;
+    };
+    let target = match entities.ball.map(|ball| ball_query.get(ball)) {
+        Some(Ok(ball_tf)) => player_tf.translation.lerp(ball_tf.translation, BALL_WEIGHT),
+        _ => player_tf.translation,
+    };
+
+    let t = (settings.follow_stiffness * time.delta_seconds()).min(1.0);
+    for (mut follow, mut tf) in camera_query.iter_mut() {
+        follow.focus = follow.focus.lerp(target, t);
+        let wanted = follow.focus + FollowCamera::offset(settings.follow_height) * follow.zoom;
+        tf.translation = tf.translation.lerp(wanted, t);
+        tf.look_at(follow.focus, Vec3::Y);
+    }
+}
+
+fn zoom_system(
+    mut camera_query: Query<&mut FollowCamera>,
+    player_query: Query<(&PlayerIndex, &Transform), With<Player>>,
+    ball_query: Query<&Transform, With<Ball>>,
+    entities: Res<MatchEntities>,
+    time: Res<Time>,
+) {
+    let Some((_, player_tf)) = player_query.iter().find(|(index, _)| index.0 == 0) else {
+        return;
+    };
+    let Some(Ok(ball_tf)) = entities.ball.map(|ball| ball_query.get(ball)) else {
+        return;
+    };
+
+    let spread = player_tf.translation.distance(ball_tf.translation);
+    let wanted = (MIN_ZOOM + spread * ZOOM_PER_METER).clamp(MIN_ZOOM, MAX_ZOOM);
+    let t = (ZOOM_STIFFNESS * time.delta_seconds()).min(1.0);
+    for mut follow in camera_query.iter_mut() {
+        follow.zoom += (wanted - follow.zoom) * t;
+    }
+}
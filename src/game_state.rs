@@ -0,0 +1,269 @@
+use super::bindings::{InputMap, KeyState, Rebinding};
+use super::clock::PeriodEndEvent;
+use super::marks::Team;
+use super::mode::{ActiveMode, MatchFinishedEvent};
+use super::rematch::RematchEvent;
+use super::Score;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        // launching opens the title screen, the arena and players wait for Play
+        app.add_state(GameState::Menu)
+            .init_resource::<PhysicsHold>()
+            .init_resource::<MatchResult>()
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(freeze_physics))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(hold_physics))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(release_physics))
+            .add_system_set(SystemSet::on_enter(GameState::HalfTime).with_system(hold_physics))
+            .add_system_set(SystemSet::on_exit(GameState::HalfTime).with_system(release_physics))
+            .add_system_set(SystemSet::on_enter(GameState::Replay).with_system(hold_physics))
+            .add_system_set(SystemSet::on_exit(GameState::Replay).with_system(release_physics))
+            .add_system_set(SystemSet::on_enter(GameState::Playback).with_system(hold_physics))
+            .add_system_set(SystemSet::on_exit(GameState::Playback).with_system(release_physics))
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(freeze_physics))
+            .add_system(pause_toggle_system)
+            .add_system(match_finished_system)
+            .add_system(period_end_system)
+            .add_system_to_stage(CoreStage::PostUpdate, rematch_state_system)
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(pause_menu_system))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver).with_system(results_ui_system),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameState {
+    Menu,
+    Playing,
+    // pushed on top of Playing, popping it carries on where the match left off
+    Paused,
+    // pushed at the end of every half but the last
+    HalfTime,
+    // pushed a moment after a goal while the build-up plays back, the clock waits
+    Replay,
+    // pushed while scrubbing through a whole recording, this match's or one loaded
+    // from disk; nothing in the match runs and its keys go to the viewer
+    Playback,
+    // the mode's own results screen is up until the next rematch
+    GameOver,
+}
+
+// physics is stopped while paused, at half time or during a replay; remembers
+// whether it was running so pauses from elsewhere (warm-up, disconnects) aren't undone
+#[derive(Resource, Default)]
+struct PhysicsHold {
+    resume: Option<bool>,
+}
+
+fn hold_physics(mut hold: ResMut<PhysicsHold>, mut rapier_config: ResMut<RapierConfiguration>) {
+    if hold.resume.is_none() {
+        hold.resume = Some(rapier_config.physics_pipeline_active);
+    }
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn release_physics(mut hold: ResMut<PhysicsHold>, mut rapier_config: ResMut<RapierConfiguration>) {
+    if let Some(resume) = hold.resume.take() {
+        rapier_config.physics_pipeline_active = resume;
+    }
+}
+
+// P, Escape or a pad's start button unless rebound
+fn pause_toggle_system(
+    mut state: ResMut<State<GameState>>,
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    keyboard: Res<Input<KeyCode>>,
+    scans: Res<Input<ScanCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+) {
+    let keys = KeyState {
+        keys: &keyboard,
+        scans: &scans,
+    };
+    if rebinding.capturing() || !input_map.pause_pressed(&keys, &gamepads, &buttons) {
+        return;
+    }
+    let result = match state.current() {
+        GameState::Playing => state.push(GameState::Paused),
+        GameState::Paused => state.pop(),
+        GameState::Menu
+        | GameState::HalfTime
+        | GameState::Replay
+        | GameState::Playback
+        | GameState::GameOver => return,
+    };
+    if let Err(err) = result {
+        debug!("Pause toggle ignored: {:?}", err);
+    }
+}
+
+// a Quit button sends AppExit from Update, closing the window only asks to close it
+// and the app exits from PostUpdate, so anything saved on the way out reads both from
+// CoreStage::Last
+pub fn app_exiting(
+    exit_events: &mut EventReader<AppExit>,
+    close_events: &mut EventReader<WindowCloseRequested>,
+) -> bool {
+    let exit = exit_events.iter().count() > 0;
+    let close = close_events.iter().count() > 0;
+    exit || close
+}
+
+// who took the last match, None when the clock ran out without a winner
+#[derive(Resource, Default)]
+struct MatchResult {
+    winner: Option<Team>,
+}
+
+// nothing moves behind the results or the menu, the rematch turns physics back on
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn match_finished_system(
+    mut state: ResMut<State<GameState>>,
+    mut finished_events: EventReader<MatchFinishedEvent>,
+    mut result: ResMut<MatchResult>,
+) {
+    let Some(winner) = finished_events.iter().last().map(|ev| ev.winner) else {
+        return;
+    };
+    if state.current() == &GameState::Playing {
+        result.winner = Some(winner);
+        state.set(GameState::GameOver).ok();
+    }
+}
+
+// the clock running out ends the match even in modes that never pick a winner. a
+// period that ends just as something else takes over waits until play resumes
+fn period_end_system(
+    mut state: ResMut<State<GameState>>,
+    mut period_events: EventReader<PeriodEndEvent>,
+    mut result: ResMut<MatchResult>,
+    mut pending: Local<Option<bool>>,
+    mut rematch_events: EventReader<RematchEvent>,
+) {
+    // a fresh match forgets whatever the last one left behind
+    if rematch_events.iter().count() > 0 {
+        *pending = None;
+    }
+    if let Some(full_time) = period_events.iter().last().map(|ev| ev.full_time) {
+        *pending = Some(full_time || pending.unwrap_or(false));
+    }
+    if state.current() != &GameState::Playing {
+        return;
+    }
+    let Some(full_time) = pending.take() else {
+        return;
+    };
+    if full_time {
+        result.winner = None;
+        state.set(GameState::GameOver).ok();
+    } else {
+        state.push(GameState::HalfTime).ok();
+    }
+}
+
+// every mode restarts through a rematch, whichever screen asked for it
+fn rematch_state_system(
+    mut state: ResMut<State<GameState>>,
+    mut rematch_events: EventReader<RematchEvent>,
+) {
+    if rematch_events.iter().count() > 0 && state.current() != &GameState::Playing {
+        state.replace(GameState::Playing).ok();
+    }
+}
+
+fn pause_menu_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut state: ResMut<State<GameState>>,
+    mut rematch_events: EventWriter<RematchEvent>,
+    mut exit_events: EventWriter<bevy::app::AppExit>,
+) {
+    let ctx = egui_context.ctx_mut();
+    // dim the frozen match behind the menu
+    ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("pause_dim"),
+    ))
+    .rect_filled(ctx.screen_rect(), 0.0, egui::Color32::from_black_alpha(140));
+
+    let mut resume = false;
+    let mut restart = false;
+    let mut menu = false;
+    let mut quit = false;
+    egui::Window::new("Paused")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            resume = ui.button("Resume").clicked();
+            restart = ui.button("Restart").clicked();
+            menu = ui.button("Main menu").clicked();
+            quit = ui.button("Quit").clicked();
+        });
+
+    if resume {
+        state.pop().ok();
+    } else if restart {
+        // the rematch puts the state back to Playing
+        rematch_events.send(RematchEvent);
+    } else if menu {
+        state.replace(GameState::Menu).ok();
+    } else if quit {
+        exit_events.send(bevy::app::AppExit);
+    }
+}
+
+fn results_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    result: Res<MatchResult>,
+    score: Res<Score>,
+    mut state: ResMut<State<GameState>>,
+    mut rematch_events: EventWriter<RematchEvent>,
+    mut exit_events: EventWriter<bevy::app::AppExit>,
+    mode: Res<ActiveMode>,
+) {
+    if mode.0.own_results() {
+        return;
+    }
+    let title = match result.winner {
+        Some(team) => format!("{} wins", team.name()),
+        None => "Full time".to_string(),
+    };
+    egui::Window::new("Results")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading(title);
+            ui.label(format!(
+                "{} {} – {} {}",
+                Team::Home.name(),
+                score.home,
+                score.away,
+                Team::Away.name()
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Rematch").clicked() {
+                    rematch_events.send(RematchEvent);
+                }
+                if ui.button("Main menu").clicked() {
+                    state.replace(GameState::Menu).ok();
+                }
+                if ui.button("Quit").clicked() {
+                    exit_events.send(bevy::app::AppExit);
+                }
+            });
+        });
+}
@@ -0,0 +1,129 @@
+use super::difficulty::Difficulty;
+use super::game_state::GameState;
+use super::marks::{Ball, MatchEntity, Team};
+use super::nametags::NameTag;
+use super::{MatchAssets, MatchEntities, GOAL_GAP};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const KEEPER_SPEED: f32 = 4.0;
+// how far in front of the goal sensor the keeper patrols
+const KEEPER_DEPTH: f32 = 0.7;
+// the posts are a unit wide and the capsule half a unit round, so the keeper
+// stops short of GOAL_GAP rather than climbing into a post
+const KEEPER_REACH: f32 = GOAL_GAP - 1.0;
+
+pub struct GoalkeeperPlugin;
+
+impl Plugin for GoalkeeperPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_goalkeeper)
+            .add_system(goalkeeper_spawn_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(goalkeeper_track_system),
+            );
+    }
+}
+
+// slides along its line between the posts, never forward or back
+#[derive(Component)]
+pub struct Goalkeeper {
+    target_x: f32,
+    reaction: Timer,
+}
+
+#[derive(Resource)]
+struct GoalkeeperMaterial(Handle<StandardMaterial>);
+
+fn setup_goalkeeper(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(GoalkeeperMaterial(materials.add(StandardMaterial {
+        base_color: Color::GOLD,
+        ..default()
+    })));
+}
+
+// where the ball will cross a line of constant z, assuming it keeps its heading
+pub fn predicted_x(ball_tf: &Transform, ball_vel: &Velocity, line: f32) -> f32 {
+    let dz = line - ball_tf.translation.z;
+    if ball_vel.linvel.z > 0.1 && dz > 0.0 {
+        ball_tf.translation.x + ball_vel.linvel.x * (dz / ball_vel.linvel.z)
+    } else {
+        ball_tf.translation.x
+    }
+}
+
+// with multiball the away side minds whichever ball is deepest toward its goal at +z
+pub fn closest_threat<'a>(
+    balls: impl Iterator<Item = (&'a Transform, &'a Velocity)>,
+) -> Option<(&'a Transform, &'a Velocity)> {
+    balls.max_by(|(a, _), (b, _)| a.translation.z.total_cmp(&b.translation.z))
+}
+
+// like the opponent, a keeper lost to a rematch is put back the next frame
+fn goalkeeper_spawn_system(
+    mut commands: Commands,
+    keeper_query: Query<(), With<Goalkeeper>>,
+    // the sensor sits at the top level, and its global transform lags a frame behind spawning
+    goal_query: Query<&Transform, Without<Goalkeeper>>,
+    entities: Res<MatchEntities>,
+    match_assets: Option<Res<MatchAssets>>,
+    material: Res<GoalkeeperMaterial>,
+) {
+    let Some(match_assets) = match_assets else {
+        return;
+    };
+    let Some(Ok(goal_tf)) = entities.goal.map(|goal| goal_query.get(goal)) else {
+        return;
+    };
+    if !keeper_query.is_empty() {
+        return;
+    }
+
+    let goal = goal_tf.translation;
+    commands.spawn((
+        PbrBundle {
+            mesh: match_assets.player_mesh.clone(),
+            material: material.0.clone(),
+            transform: Transform::from_xyz(0.0, 1.1, goal.z - KEEPER_DEPTH),
+            ..default()
+        },
+        Collider::capsule_y(0.5, 0.5),
+        RigidBody::KinematicPositionBased,
+        Restitution::coefficient(1.5),
+        Goalkeeper {
+            target_x: 0.0,
+            reaction: Timer::from_seconds(0.0, TimerMode::Once),
+        },
+        Team::Away,
+        MatchEntity,
+        NameTag("Keeper".to_string()),
+        Name::new("goalkeeper"),
+    ));
+}
+
+fn goalkeeper_track_system(
+    mut keeper_query: Query<(&mut Goalkeeper, &mut Transform), Without<Ball>>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    difficulty: Res<Difficulty>,
+    time: Res<Time>,
+) {
+    let Some((ball_tf, ball_vel)) = closest_threat(ball_query.iter()) else {
+        return;
+    };
+
+    for (mut keeper, mut keeper_tf) in keeper_query.iter_mut() {
+        // sharper keepers re-read the shot more often
+        keeper.reaction.tick(time.delta());
+        if keeper.reaction.finished() {
+            let line = keeper_tf.translation.z;
+            keeper.target_x =
+                predicted_x(ball_tf, ball_vel, line).clamp(-KEEPER_REACH, KEEPER_REACH);
+            keeper.reaction = Timer::from_seconds(difficulty.reaction_time(), TimerMode::Once);
+        }
+
+        let step = KEEPER_SPEED * difficulty.speed_scale() * time.delta_seconds();
+        let delta = (keeper.target_x - keeper_tf.translation.x).clamp(-step, step);
+        keeper_tf.translation.x =
+            (keeper_tf.translation.x + delta).clamp(-KEEPER_REACH, KEEPER_REACH);
+    }
+}
@@ -0,0 +1,257 @@
+use super::arena::{piece_collider, Arena, PieceKind};
+use super::cli::StartupArgs;
+use super::clock::MatchClock;
+use super::config::GameConfig;
+use super::determinism::{DeterminismPlugin, GameRng, TICK_DT};
+use super::marks::{Ball, EnemyGoal, PlayerGoal};
+use super::mode::{ActiveMode, GameMode};
+use super::rematch::RematchEvent;
+use super::rules::LastTouch;
+use super::{goal_score_system, goal_system, GoalReset, GoalScoredEvent, MatchEntities, Score};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::scene::ScenePlugin;
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use std::sync::Arc;
+
+// ten simulated minutes unless `--frames <n>` says otherwise
+const DEFAULT_FRAMES: u32 = 36_000;
+
+// `--headless [--frames <n>]` steps the arena, the ball and the goal logic with no
+// window, renderer or egui, as fast as the cpu allows, then prints the score
+pub fn run(mode: Arc<dyn GameMode>, args: StartupArgs) {
+    let mut app = App::new();
+    // logging first, so loading the arena and config below can warn
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(bevy::log::LogPlugin::default());
+    let frames = args.frames.unwrap_or(DEFAULT_FRAMES);
+    build_headless(&mut app, mode, args, frames);
+    app.run();
+}
+
+// everything but the runner and logging, so tests can step the same app by hand
+fn build_headless(app: &mut App, mode: Arc<dyn GameMode>, args: StartupArgs, frames: u32) {
+    build_headless_match(app, mode, args);
+    app.insert_resource(HeadlessRun { frames, frame: 0 })
+        .add_system(relaunch_system.after(goal_system))
+        .add_system(headless_exit_system);
+}
+
+// the arena, ball and goal logic without the relaunches or the frame limit, for
+// tests that put their own players on the pitch
+pub fn build_headless_match(app: &mut App, mode: Arc<dyn GameMode>, args: StartupArgs) {
+    let mut config = GameConfig::load();
+    config.apply_args(&args);
+    let clock = MatchClock::new(config.clock.duration, config.clock.halves);
+
+    // the determinism plugin reads its flags from the args
+    app.insert_resource(args.clone())
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        // rapier's async colliders look for meshes and scenes even if none are used
+        .add_plugin(AssetPlugin::default())
+        .add_asset::<Mesh>()
+        .add_plugin(ScenePlugin)
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugin(DeterminismPlugin {
+            fixed_schedule: false,
+        })
+        // every frame is one fixed step, however quickly it ran
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: TICK_DT,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .insert_resource(Arena::open(args.arena))
+        .insert_resource(config)
+        .insert_resource(clock)
+        .insert_resource(ActiveMode(mode))
+        .init_resource::<Score>()
+        .init_resource::<GoalReset>()
+        .init_resource::<MatchEntities>()
+        .init_resource::<LastTouch>()
+        .add_event::<GoalScoredEvent>()
+        .add_event::<RematchEvent>()
+        .add_startup_system(spawn_headless_match)
+        .add_system(goal_system)
+        .add_system(goal_score_system.after(goal_system));
+}
+
+#[derive(Resource)]
+struct HeadlessRun {
+    frames: u32,
+    frame: u32,
+}
+
+// colliders only, the arena pieces keep their names so logs read the same
+fn spawn_headless_match(
+    mut commands: Commands,
+    mut entities: ResMut<MatchEntities>,
+    mut rng: ResMut<GameRng>,
+    arena: Res<Arena>,
+) {
+    for piece in arena.descriptor.pieces.iter() {
+        let mut entity = commands.spawn((
+            piece_collider(piece),
+            TransformBundle::from(piece.transform()),
+            Name::new(piece.name.clone()),
+        ));
+        match piece.kind {
+            PieceKind::GoalSensor | PieceKind::HomeGoalSensor => {
+                entity.insert((
+                    Sensor,
+                    ActiveCollisionTypes::default() | ActiveCollisionTypes::DYNAMIC_STATIC,
+                    ActiveEvents::COLLISION_EVENTS,
+                ));
+                if piece.kind == PieceKind::GoalSensor {
+                    entity.insert(EnemyGoal);
+                    entities.goal = Some(entity.id());
+                } else {
+                    entity.insert(PlayerGoal);
+                    entities.home_goal = Some(entity.id());
+                }
+            }
+            // pads do nothing here, but the ball mustn't bounce off them
+            PieceKind::BoostPad => {
+                entity.insert(Sensor);
+            }
+            _ => {
+                entity.insert(RigidBody::Fixed);
+            }
+        }
+    }
+
+    let ball = commands
+        .spawn((
+            TransformBundle::from(Transform::from_xyz(0.0, 1.0, 0.0)),
+            Collider::ball(0.5),
+            RigidBody::Dynamic,
+            random_launch(&mut rng.rng),
+            Ccd::enabled(),
+            Damping {
+                angular_damping: 1.0,
+                linear_damping: 0.5,
+            },
+            ActiveEvents::COLLISION_EVENTS,
+            Ball,
+            Name::new("ball"),
+        ))
+        .id();
+    entities.ball = Some(ball);
+    entities.balls = vec![ball];
+}
+
+// a shot from the center spot at either goal
+fn random_launch(rng: &mut impl Rng) -> Velocity {
+    let z = rng.gen_range(10.0..16.0) * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    Velocity {
+        linvel: Vec3::new(rng.gen_range(-3.0..3.0), rng.gen_range(1.0..4.0), z),
+        angvel: Vec3::ZERO,
+    }
+}
+
+// after a goal, or once the ball has died down, shoot again from the center
+fn relaunch_system(
+    mut goal_events: EventReader<GoalScoredEvent>,
+    mut ball_query: Query<(&mut Transform, &mut Velocity), With<Ball>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let scored = goal_events.iter().count() > 0;
+    for (mut tf, mut vel) in ball_query.iter_mut() {
+        let settled = vel.linvel.length_squared() < 0.01;
+        if scored || settled || tf.translation.y < -5.0 {
+            tf.translation = Vec3::new(0.0, 1.0, 0.0);
+            *vel = random_launch(&mut rng.rng);
+        }
+    }
+}
+
+fn headless_exit_system(
+    mut run: ResMut<HeadlessRun>,
+    mut exit: EventWriter<AppExit>,
+    score: Res<Score>,
+) {
+    run.frame += 1;
+    if run.frame < run.frames {
+        return;
+    }
+    info!(
+        "Simulated {} frames ({:.0} s): home {} - {} away",
+        run.frame,
+        run.frame as f32 * TICK_DT,
+        score.home,
+        score.away
+    );
+    exit.send(AppExit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::determinism::state_hash;
+    use crate::marks::Team;
+    use crate::mode::ModeRegistry;
+
+    #[derive(Resource, Default)]
+    struct Goals(Vec<(Team, Entity)>);
+
+    fn goal_log_system(mut goal_events: EventReader<GoalScoredEvent>, mut goals: ResMut<Goals>) {
+        for ev in goal_events.iter() {
+            goals.0.push((ev.against, ev.ball));
+        }
+    }
+
+    #[test]
+    fn shot_into_the_far_goal_scores_for_home() {
+        let mode = ModeRegistry::default().get("classic").unwrap();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        build_headless(&mut app, mode, StartupArgs::default(), DEFAULT_FRAMES);
+        app.init_resource::<Goals>()
+            .add_system(goal_log_system.after(goal_system));
+        // spawns the arena and ball and places the goals
+        app.update();
+
+        let entities = app.world.resource::<MatchEntities>();
+        let (ball, goal) = (entities.ball.unwrap(), entities.goal.unwrap());
+        let goal_at = app.world.get::<GlobalTransform>(goal).unwrap().translation();
+        app.world.get_mut::<Transform>(ball).unwrap().translation = goal_at - Vec3::Z * 2.0;
+        *app.world.get_mut::<Velocity>(ball).unwrap() = Velocity::linear(Vec3::Z * 10.0);
+
+        // stop at the first goal, the relaunch afterwards could score another
+        for _ in 0..60 {
+            app.update();
+            if !app.world.resource::<Goals>().0.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(app.world.resource::<Goals>().0, vec![(Team::Away, ball)]);
+        let score = app.world.resource::<Score>();
+        assert_eq!((score.home, score.away), (1, 0));
+    }
+
+    fn simulate(seed: u64, frames: u32) -> u64 {
+        let mode = ModeRegistry::default().get("classic").unwrap();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        build_headless(&mut app, mode, StartupArgs::default(), DEFAULT_FRAMES);
+        // the plugin seeds from the command line, which the test runner doesn't pass
+        app.insert_resource(GameRng::new(seed));
+        for _ in 0..frames {
+            app.update();
+        }
+        let mut ball_query = app
+            .world
+            .query_filtered::<(&Transform, &Velocity), With<Ball>>();
+        state_hash(ball_query.iter(&app.world), std::iter::empty())
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_match() {
+        assert_eq!(simulate(7, 600), simulate(7, 600));
+        assert_ne!(simulate(7, 600), simulate(8, 600));
+    }
+}
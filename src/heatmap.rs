@@ -0,0 +1,355 @@
+use super::marks::{Ball, GoalPost, MatchEntity, Team};
+use super::player::KickEvent;
+use super::rematch::RematchEvent;
+use super::{Score, BOARD_DIM};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_egui::egui;
+use bevy_rapier3d::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+// cells per metre along both floor axes
+const RESOLUTION: f32 = 2.0;
+const REFRESH_TIME: f32 = 1.0;
+// a shot nobody touched within this long went nowhere useful
+const SHOT_TIMEOUT: f32 = 3.0;
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Heatmap::new())
+            .insert_resource(ShotLog::default())
+            .add_startup_system(setup_heatmap_overlay)
+            .add_system(heatmap_sample_system)
+            .add_system(shot_tracking_system)
+            .add_system(heatmap_reset_system)
+            .add_system(heatmap_overlay_system.after(heatmap_sample_system));
+    }
+}
+
+// seconds the ball spent over each floor cell, row 0 at the home end
+#[derive(Resource)]
+pub struct Heatmap {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<f32>,
+}
+
+impl Heatmap {
+    fn new() -> Self {
+        let width = (BOARD_DIM.0 * RESOLUTION) as usize;
+        let height = (BOARD_DIM.2 * RESOLUTION) as usize;
+        Self {
+            width,
+            height,
+            cells: vec![0.0; width * height],
+        }
+    }
+
+    pub fn cell(&self, position: Vec3) -> Option<usize> {
+        let x = (position.x + BOARD_DIM.0 / 2.0) * RESOLUTION;
+        let z = (position.z + BOARD_DIM.2 / 2.0) * RESOLUTION;
+        if x < 0.0 || z < 0.0 || x >= self.width as f32 || z >= self.height as f32 {
+            return None;
+        }
+        Some(z as usize * self.width + x as usize)
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.cells.iter().copied().fold(0.0, f32::max)
+    }
+
+    // rgba8 pixels in cell order, cold cells fully transparent
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let peak = self.peak().max(f32::EPSILON);
+        self.cells
+            .iter()
+            .flat_map(|value| {
+                let t = (value / peak).sqrt();
+                let color = if t < 0.5 {
+                    Color::BLUE * (1.0 - t * 2.0) + Color::YELLOW * (t * 2.0)
+                } else {
+                    Color::YELLOW * (2.0 - t * 2.0) + Color::RED * (t * 2.0 - 1.0)
+                };
+                let [r, g, b, _] = color.as_rgba_f32();
+                [
+                    (r * 255.0) as u8,
+                    (g * 255.0) as u8,
+                    (b * 255.0) as u8,
+                    (t.min(1.0) * 200.0) as u8,
+                ]
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShotOutcome {
+    Goal,
+    Saved,
+    Missed,
+    Post,
+}
+
+impl ShotOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShotOutcome::Goal => "Goal",
+            ShotOutcome::Saved => "Saved",
+            ShotOutcome::Missed => "Missed",
+            ShotOutcome::Post => "Post",
+        }
+    }
+
+    fn color(&self) -> egui::Color32 {
+        match self {
+            ShotOutcome::Goal => egui::Color32::GREEN,
+            ShotOutcome::Saved => egui::Color32::LIGHT_BLUE,
+            ShotOutcome::Missed => egui::Color32::LIGHT_RED,
+            ShotOutcome::Post => egui::Color32::YELLOW,
+        }
+    }
+}
+
+pub struct ShotRecord {
+    pub origin: Vec3,
+    // flat direction of travel off the boot
+    pub direction: Vec3,
+    pub outcome: ShotOutcome,
+}
+
+// shots of the current match, the newest one waits for an outcome
+#[derive(Resource, Default)]
+pub struct ShotLog {
+    pub shots: Vec<ShotRecord>,
+    pending: Option<(Vec3, Vec3, f32)>,
+    last_goals: u32,
+}
+
+impl ShotLog {
+    pub fn count(&self, outcome: ShotOutcome) -> usize {
+        self.shots.iter().filter(|s| s.outcome == outcome).count()
+    }
+
+    fn resolve(&mut self, outcome: ShotOutcome) {
+        if let Some((origin, direction, _)) = self.pending.take() {
+            self.shots.push(ShotRecord {
+                origin,
+                direction,
+                outcome,
+            });
+        }
+    }
+}
+
+#[derive(Component)]
+struct HeatmapOverlay {
+    image: Handle<Image>,
+    refresh: Timer,
+}
+
+fn setup_heatmap_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    heatmap: Res<Heatmap>,
+) {
+    let image = images.add(Image::new(
+        Extent3d {
+            width: heatmap.width as u32,
+            height: heatmap.height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        heatmap.to_rgba(),
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    // quad lies flat just above the floor, its top edge at the home end
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Quad::new(Vec2::new(
+                BOARD_DIM.0,
+                BOARD_DIM.2,
+            )))),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(image.clone()),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, BOARD_DIM.1 / 2.0 + 0.01, 0.0)
+                .with_rotation(Quat::from_rotation_x(-FRAC_PI_2)),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        HeatmapOverlay {
+            image,
+            refresh: Timer::from_seconds(REFRESH_TIME, TimerMode::Repeating),
+        },
+        Name::new("heatmap"),
+    ));
+}
+
+fn heatmap_sample_system(
+    mut heatmap: ResMut<Heatmap>,
+    time: Res<Time>,
+    ball_query: Query<&Transform, (With<Ball>, With<MatchEntity>)>,
+) {
+    for ball_tf in ball_query.iter() {
+        if let Some(cell) = heatmap.cell(ball_tf.translation) {
+            heatmap.cells[cell] += time.delta_seconds();
+        }
+    }
+}
+
+fn shot_tracking_system(
+    mut shot_log: ResMut<ShotLog>,
+    mut kick_events: EventReader<KickEvent>,
+    mut collision_events: EventReader<CollisionEvent>,
+    score: Res<Score>,
+    time: Res<Time>,
+    ball_query: Query<&Transform, (With<Ball>, With<MatchEntity>)>,
+    team_query: Query<&Team>,
+    post_query: Query<&GoalPost>,
+    name_query: Query<&Name>,
+) {
+    let now = time.elapsed_seconds();
+
+    if score.home > shot_log.last_goals {
+        shot_log.resolve(ShotOutcome::Goal);
+    }
+    shot_log.last_goals = score.home;
+
+    for ev in collision_events.iter() {
+        let CollisionEvent::Started(a, b, _) = ev else {
+            continue;
+        };
+        let other = if ball_query.contains(*a) {
+            *b
+        } else if ball_query.contains(*b) {
+            *a
+        } else {
+            continue;
+        };
+        if team_query.get(other) == Ok(&Team::Away) {
+            shot_log.resolve(ShotOutcome::Saved);
+        }
+        if matches!(post_query.get(other), Ok(GoalPost(Team::Away))) {
+            shot_log.resolve(ShotOutcome::Post);
+        }
+        if matches!(
+            name_query.get(other).map(|n| n.as_str()),
+            Ok("FrontWall" | "FrontWallLeft" | "FrontWallRight")
+        ) {
+            shot_log.resolve(ShotOutcome::Missed);
+        }
+    }
+
+    if matches!(shot_log.pending, Some((_, _, at)) if now - at > SHOT_TIMEOUT) {
+        shot_log.resolve(ShotOutcome::Missed);
+    }
+
+    // the chart follows the home side's shots
+    for ev in kick_events.iter().filter(|ev| ev.team == Team::Home) {
+        let Ok(ball_tf) = ball_query.get(ev.ball) else {
+            continue;
+        };
+        // a second touch before the first shot resolved means it never got anywhere
+        shot_log.resolve(ShotOutcome::Missed);
+        let direction = Vec3::new(ev.impulse.x, 0.0, ev.impulse.z).normalize_or_zero();
+        shot_log.pending = Some((ball_tf.translation, direction, now));
+    }
+}
+
+fn heatmap_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut heatmap: ResMut<Heatmap>,
+    mut shot_log: ResMut<ShotLog>,
+) {
+    if rematch_events.iter().count() > 0 {
+        *heatmap = Heatmap::new();
+        *shot_log = ShotLog::default();
+    }
+}
+
+// top-down chart of the match's shots over a faint heatmap, home end at the bottom
+pub fn shot_chart_ui(ui: &mut egui::Ui, shot_log: &ShotLog, heatmap: &Heatmap) {
+    let scale = 12.0;
+    let size = egui::vec2(BOARD_DIM.0 * scale, BOARD_DIM.2 * scale);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let to_screen =
+        |p: Vec3| egui::pos2(rect.center().x - p.x * scale, rect.center().y - p.z * scale);
+
+    painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(20, 60, 20));
+    let peak = heatmap.peak().max(f32::EPSILON);
+    let cell = scale / RESOLUTION;
+    for (i, value) in heatmap.cells.iter().enumerate() {
+        if *value <= 0.0 {
+            continue;
+        }
+        let x = (i % heatmap.width) as f32 / RESOLUTION - BOARD_DIM.0 / 2.0;
+        let z = (i / heatmap.width) as f32 / RESOLUTION - BOARD_DIM.2 / 2.0;
+        let corner = to_screen(Vec3::new(x, 0.0, z));
+        let alpha = ((value / peak).sqrt() * 120.0) as u8;
+        painter.rect_filled(
+            egui::Rect::from_two_pos(corner, corner - egui::vec2(cell, cell)),
+            0.0,
+            egui::Color32::from_rgba_unmultiplied(255, 140, 0, alpha),
+        );
+    }
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+
+    for shot in shot_log.shots.iter() {
+        let start = to_screen(shot.origin);
+        let end = to_screen(shot.origin + shot.direction * 3.0);
+        let color = shot.outcome.color();
+        painter.line_segment([start, end], egui::Stroke::new(2.0, color));
+        painter.circle_filled(start, 3.0, color);
+    }
+
+    ui.horizontal(|ui| {
+        for outcome in [
+            ShotOutcome::Goal,
+            ShotOutcome::Saved,
+            ShotOutcome::Post,
+            ShotOutcome::Missed,
+        ] {
+            ui.label(
+                egui::RichText::new(format!("{} {}", outcome.name(), shot_log.count(outcome)))
+                    .color(outcome.color()),
+            );
+        }
+    });
+}
+
+// H toggles the overlay, the texture is only rebuilt while it is showing
+fn heatmap_overlay_system(
+    keyboard: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    heatmap: Res<Heatmap>,
+    mut images: ResMut<Assets<Image>>,
+    mut overlay_query: Query<(&mut HeatmapOverlay, &mut Visibility)>,
+) {
+    let Ok((mut overlay, mut visibility)) = overlay_query.get_single_mut() else {
+        return;
+    };
+    let toggled = keyboard.just_pressed(KeyCode::H);
+    if toggled {
+        visibility.is_visible = !visibility.is_visible;
+    }
+    if !visibility.is_visible {
+        return;
+    }
+
+    overlay.refresh.tick(time.delta());
+    if !toggled && !overlay.refresh.just_finished() {
+        return;
+    }
+    if let Some(image) = images.get_mut(&overlay.image) {
+        image.data = heatmap.to_rgba();
+    }
+}
@@ -0,0 +1,337 @@
+use super::clock::MatchClock;
+use super::heatmap::{ShotLog, ShotOutcome};
+use super::mode::{ActiveMode, MatchFinishedEvent};
+use super::rematch::RematchEvent;
+use super::storage;
+use super::Score;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HISTORY_FILE: &str = "history.sqlite";
+// kept in the database's user_version, bump it alongside a step in `migrate`
+const HISTORY_VERSION: u32 = 1;
+const TREND_LENGTH: usize = 20;
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(history) = History::open() else {
+            return;
+        };
+
+        app.insert_resource(history)
+            .insert_resource(CareerScreen::default())
+            .add_system(record_match_system)
+            .add_system(career_ui_system);
+    }
+}
+
+// `--export-history <file.csv>` dumps the table instead of starting the game. run from
+// main before the app exists, so it prints rather than logs
+pub fn export(path: &Path) -> ExitCode {
+    let Some(history) = History::open() else {
+        eprintln!("No match history to export");
+        return ExitCode::FAILURE;
+    };
+    match history.export_csv(path) {
+        Ok(rows) => {
+            println!("Exported {} matches to {}", rows, path.display());
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("History export failed: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub struct MatchRecord {
+    pub played_at: u64,
+    pub mode: String,
+    pub home_goals: u32,
+    pub away_goals: u32,
+    pub duration: f32,
+    pub shots: u32,
+    pub shots_on_target: u32,
+}
+
+// brings an existing database up to HISTORY_VERSION, refusing ones written by a newer build
+fn migrate(connection: &Connection) -> Result<(), String> {
+    let version: u32 = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|err| err.to_string())?;
+    if version > HISTORY_VERSION {
+        return Err(format!(
+            "written by a newer version (format {}, this build reads up to {})",
+            version, HISTORY_VERSION
+        ));
+    }
+    // version 0 databases predate versioning and already match version 1
+    if version < HISTORY_VERSION {
+        connection
+            .execute_batch(&format!("PRAGMA user_version = {}", HISTORY_VERSION))
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+// one row per finished match in saves/history.sqlite
+#[derive(Resource)]
+pub struct History {
+    connection: Mutex<Connection>,
+}
+
+impl History {
+    fn open() -> Option<Self> {
+        let path = storage::data_dir().join(HISTORY_FILE);
+        let result = std::fs::create_dir_all(storage::data_dir())
+            .map_err(|err| err.to_string())
+            .and_then(|_| Connection::open(&path).map_err(|err| err.to_string()))
+            .and_then(|connection| {
+                connection
+                    .execute(
+                        "CREATE TABLE IF NOT EXISTS matches (
+                            id INTEGER PRIMARY KEY,
+                            played_at INTEGER NOT NULL,
+                            mode TEXT NOT NULL,
+                            home_goals INTEGER NOT NULL,
+                            away_goals INTEGER NOT NULL,
+                            duration REAL NOT NULL,
+                            shots INTEGER NOT NULL,
+                            shots_on_target INTEGER NOT NULL
+                        )",
+                        [],
+                    )
+                    .map_err(|err| err.to_string())
+                    .and_then(|_| migrate(&connection))
+                    .map(|_| connection)
+            });
+
+        match result {
+            Ok(connection) => Some(Self {
+                connection: Mutex::new(connection),
+            }),
+            Err(err) => {
+                warn!("Match history disabled, {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, record: &MatchRecord) {
+        let connection = self.connection.lock().unwrap();
+        let result = connection.execute(
+            "INSERT INTO matches (played_at, mode, home_goals, away_goals, duration, shots, shots_on_target)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.played_at,
+                record.mode,
+                record.home_goals,
+                record.away_goals,
+                record.duration,
+                record.shots,
+                record.shots_on_target
+            ],
+        );
+        if let Err(err) = result {
+            warn!("Failed to record match: {}", err);
+        }
+    }
+
+    // wins, draws and losses across every recorded match
+    pub fn totals(&self) -> rusqlite::Result<(u32, u32, u32, f32)> {
+        let connection = self.connection.lock().unwrap();
+        connection.query_row(
+            "SELECT
+                COALESCE(SUM(home_goals > away_goals), 0),
+                COALESCE(SUM(home_goals = away_goals), 0),
+                COALESCE(SUM(home_goals < away_goals), 0),
+                COALESCE(AVG(home_goals), 0.0)
+             FROM matches",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+    }
+
+    // goal difference of the most recent matches, oldest first
+    pub fn recent_goal_difference(&self, limit: usize) -> rusqlite::Result<Vec<i32>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT home_goals - away_goals FROM matches ORDER BY played_at DESC, id DESC LIMIT ?1",
+        )?;
+        let mut rows = statement
+            .query_map([limit], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<i32>>>()?;
+        rows.reverse();
+        Ok(rows)
+    }
+
+    pub fn export_csv(&self, path: &Path) -> Result<usize, String> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT played_at, mode, home_goals, away_goals, duration, shots, shots_on_target
+                 FROM matches ORDER BY played_at, id",
+            )
+            .map_err(|err| err.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(format!(
+                    "{},{},{},{},{:.1},{},{}",
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, f64>(4)?,
+                    row.get::<_, u32>(5)?,
+                    row.get::<_, u32>(6)?
+                ))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|err| err.to_string())?;
+
+        let mut text =
+            "played_at,mode,home_goals,away_goals,duration,shots,shots_on_target\n".to_string();
+        for row in rows.iter() {
+            text.push_str(row);
+            text.push('\n');
+        }
+        std::fs::write(path, text).map_err(|err| err.to_string())?;
+        Ok(rows.len())
+    }
+}
+
+#[derive(Resource, Default)]
+struct CareerScreen {
+    open: bool,
+    recorded: bool,
+}
+
+fn record_match_system(
+    history: Res<History>,
+    mut career: ResMut<CareerScreen>,
+    mut finished_events: EventReader<MatchFinishedEvent>,
+    mut rematch_events: EventReader<RematchEvent>,
+    mut was_over: Local<bool>,
+    clock: Res<MatchClock>,
+    score: Res<Score>,
+    shot_log: Res<ShotLog>,
+    mode: Res<ActiveMode>,
+) {
+    let series_finished = finished_events.iter().count() > 0;
+    let full_time = !*was_over && clock.full_time();
+    *was_over = clock.full_time();
+
+    if (series_finished || full_time) && !career.recorded {
+        career.recorded = true;
+        let shots = shot_log.shots.len() as u32;
+        let off_target = shot_log.count(ShotOutcome::Missed) as u32;
+        history.insert(&MatchRecord {
+            played_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            mode: mode.name().to_string(),
+            home_goals: score.home,
+            away_goals: score.away,
+            duration: clock.elapsed,
+            shots,
+            shots_on_target: shots - off_target,
+        });
+    }
+
+    if rematch_events.iter().count() > 0 {
+        career.recorded = false;
+    }
+}
+
+fn career_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut career: ResMut<CareerScreen>,
+    keyboard: Res<Input<KeyCode>>,
+    history: Res<History>,
+) {
+    if keyboard.just_pressed(KeyCode::F8) {
+        career.open = !career.open;
+    }
+    if !career.open {
+        return;
+    }
+
+    let mut open = career.open;
+    egui::Window::new("Career")
+        .open(&mut open)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            match history.totals() {
+                Ok((wins, draws, losses, avg_goals)) => {
+                    ui.label(format!(
+                        "{} matches: {} W / {} D / {} L",
+                        wins + draws + losses,
+                        wins,
+                        draws,
+                        losses
+                    ));
+                    ui.label(format!("Average goals scored: {:.2}", avg_goals));
+                }
+                Err(err) => {
+                    ui.label(format!("History unavailable: {}", err));
+                }
+            }
+
+            ui.separator();
+            ui.label(format!("Goal difference, last {} matches", TREND_LENGTH));
+            let trend = history
+                .recent_goal_difference(TREND_LENGTH)
+                .unwrap_or_default();
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(240.0, 60.0), egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            let peak = trend.iter().map(|d| d.abs()).max().unwrap_or(1).max(1) as f32;
+            let bar = rect.width() / TREND_LENGTH as f32;
+            painter.line_segment(
+                [
+                    egui::pos2(rect.left(), rect.center().y),
+                    egui::pos2(rect.right(), rect.center().y),
+                ],
+                egui::Stroke::new(1.0, egui::Color32::GRAY),
+            );
+            for (i, diff) in trend.iter().enumerate() {
+                // draws were level, give them a sliver so the match still shows
+                let height = if *diff == 0 {
+                    1.0
+                } else {
+                    *diff as f32 / peak * rect.height() / 2.0
+                };
+                let left = rect.left() + i as f32 * bar;
+                let color = match diff.signum() {
+                    1 => egui::Color32::GREEN,
+                    -1 => egui::Color32::LIGHT_RED,
+                    _ => egui::Color32::GRAY,
+                };
+                painter.rect_filled(
+                    egui::Rect::from_two_pos(
+                        egui::pos2(left + 1.0, rect.center().y),
+                        egui::pos2(left + bar - 1.0, rect.center().y - height),
+                    ),
+                    0.0,
+                    color,
+                );
+            }
+
+            ui.separator();
+            if ui.button("Export CSV").clicked() {
+                let path = storage::data_dir().join("history.csv");
+                match history.export_csv(&path) {
+                    Ok(rows) => info!("Exported {} matches to {}", rows, path.display()),
+                    Err(err) => warn!("History export failed: {}", err),
+                }
+            }
+        });
+    career.open = open;
+}
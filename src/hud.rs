@@ -0,0 +1,477 @@
+use super::clock::MatchClock;
+use super::config::GameConfig;
+use super::marks::{ClockText, Player, PlayerIndex, Team};
+use super::mode::ActiveMode;
+use super::boost_meter::BoostMeter;
+use super::player::{KickCharge, Stamina};
+use super::splitscreen::SplitScreen;
+use super::Score;
+#[cfg(feature = "develop")]
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+const FONT: &str = "fonts/RubikSprayPaint-Regular.ttf";
+const SCORE_FONT_SIZE: f32 = 40.0;
+const POP_TIME: f32 = 0.4;
+// the clock pulses red over the last seconds of a half
+const WARNING_TIME: f32 = 10.0;
+const POWER_BAR_SIZE: (f32, f32) = (160.0, 12.0);
+const STAMINA_BAR_SIZE: (f32, f32) = (160.0, 6.0);
+const BOOST_BAR_SIZE: (f32, f32) = (160.0, 8.0);
+// one bar of each kind per local player, the second only while it is in the match
+const POWER_BARS: usize = 2;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_scoreboard)
+            .add_startup_system(setup_hud)
+            .add_system(scoreboard_system)
+            .add_system(hud_config_system)
+            .add_system(score_pop_system.after(scoreboard_system))
+            .add_system(clock_text_system)
+            .add_system(power_bar_system)
+            .add_system(stamina_bar_system)
+            .add_system(boost_bar_system);
+
+        // the frame time diagnostics come from the perf overlay
+        #[cfg(feature = "develop")]
+        app.add_system(fps_text_system);
+    }
+}
+
+#[derive(Component)]
+struct Scoreboard;
+
+// the copy over the right half, only shown while the screen is split
+#[derive(Component)]
+struct SplitScoreboard;
+
+#[derive(Component)]
+struct ScoreDigit {
+    team: Team,
+    shown: u32,
+    pop: Timer,
+}
+
+fn setup_scoreboard(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(FONT);
+    spawn_scoreboard(&mut commands, &font, Val::Px(0.0));
+    let split = spawn_scoreboard(&mut commands, &font, Val::Percent(50.0));
+    commands.entity(split).insert(SplitScoreboard);
+}
+
+fn spawn_scoreboard(commands: &mut Commands, font: &Handle<Font>, left: Val) -> Entity {
+    let style = |color: Color| TextStyle {
+        font: font.clone(),
+        font_size: SCORE_FONT_SIZE,
+        color,
+    };
+    let mut done = Timer::from_seconds(POP_TIME, TimerMode::Once);
+    done.tick(done.duration());
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(0.0),
+                        left,
+                        ..default()
+                    },
+                    align_items: AlignItems::Center,
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                ..default()
+            },
+            Scoreboard,
+            Name::new("Scoreboard"),
+        ))
+        .with_children(|parent| {
+            let margin = Style {
+                margin: UiRect::horizontal(Val::Px(8.0)),
+                ..default()
+            };
+            parent.spawn(
+                TextBundle::from_section(
+                    Team::Home.name().to_uppercase(),
+                    style(Team::Home.color()),
+                )
+                .with_style(margin.clone()),
+            );
+            parent.spawn((
+                TextBundle::from_section("0", style(Color::WHITE)).with_style(margin.clone()),
+                ScoreDigit {
+                    team: Team::Home,
+                    shown: 0,
+                    pop: done.clone(),
+                },
+            ));
+            parent.spawn(TextBundle::from_section("-", style(Color::WHITE)));
+            parent.spawn((
+                TextBundle::from_section("0", style(Color::WHITE)).with_style(margin.clone()),
+                ScoreDigit {
+                    team: Team::Away,
+                    shown: 0,
+                    pop: done.clone(),
+                },
+            ));
+            parent.spawn(
+                TextBundle::from_section(
+                    Team::Away.name().to_uppercase(),
+                    style(Team::Away.color()),
+                )
+                .with_style(margin),
+            );
+        })
+        .id()
+}
+
+#[derive(Component)]
+struct PowerBar(usize);
+
+#[derive(Component)]
+struct PowerFill;
+
+#[derive(Component)]
+struct StaminaBar(usize);
+
+#[derive(Component)]
+struct StaminaFill;
+
+#[derive(Component)]
+struct BoostBar(usize);
+
+#[derive(Component)]
+struct BoostFill;
+
+#[cfg(feature = "develop")]
+#[derive(Component)]
+struct FpsText;
+
+// the clock centred along the top, power, stamina and boost bars along the bottom, fps
+// in the corner
+fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font: Handle<Font> = asset_server.load(FONT);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: full_width(UiRect {
+                    top: Val::Px(0.0),
+                    ..default()
+                }),
+                ..default()
+            },
+            Name::new("ClockBar"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: SCORE_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                ),
+                ClockText,
+                Name::new("ClockText"),
+            ));
+        });
+
+    spawn_bar_row(&mut commands, "PowerBars", 24.0, POWER_BAR_SIZE, PowerBar, || {
+        PowerFill
+    });
+    spawn_bar_row(
+        &mut commands,
+        "StaminaBars",
+        44.0,
+        STAMINA_BAR_SIZE,
+        StaminaBar,
+        || StaminaFill,
+    );
+    spawn_bar_row(&mut commands, "BoostBars", 56.0, BOOST_BAR_SIZE, BoostBar, || {
+        BoostFill
+    });
+
+    #[cfg(feature = "develop")]
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font,
+                font_size: 18.0,
+                color: Color::GRAY,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(4.0),
+                right: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        }),
+        FpsText,
+        Name::new("FpsText"),
+    ));
+}
+
+fn full_width(position: UiRect) -> Style {
+    Style {
+        position_type: PositionType::Absolute,
+        position,
+        size: Size::new(Val::Percent(100.0), Val::Auto),
+        justify_content: JustifyContent::SpaceAround,
+        ..default()
+    }
+}
+
+// one hidden bar per local player across the bottom, the systems below size, colour
+// and show each fill
+fn spawn_bar_row<B: Component, F: Component>(
+    commands: &mut Commands,
+    name: &str,
+    bottom: f32,
+    size: (f32, f32),
+    bar: impl Fn(usize) -> B,
+    fill: impl Fn() -> F,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: full_width(UiRect {
+                    bottom: Val::Px(bottom),
+                    ..default()
+                }),
+                ..default()
+            },
+            Name::new(name.to_string()),
+        ))
+        .with_children(|parent| {
+            for index in 0..POWER_BARS {
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(size.0), Val::Px(size.1)),
+                                display: Display::None,
+                                ..default()
+                            },
+                            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                            ..default()
+                        },
+                        bar(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            fill(),
+                        ));
+                    });
+            }
+        });
+}
+
+fn hud_config_system(
+    config: Res<GameConfig>,
+    split: Res<SplitScreen>,
+    mut scoreboard_query: Query<(&mut Style, Option<&SplitScoreboard>), With<Scoreboard>>,
+    mut clock_query: Query<&mut Style, (With<ClockText>, Without<Scoreboard>)>,
+) {
+    if !config.is_changed() && !split.is_changed() {
+        return;
+    }
+    let display = |shown: bool| if shown { Display::Flex } else { Display::None };
+    for (mut style, split_copy) in scoreboard_query.iter_mut() {
+        let shown = split_copy.is_none() || split.active;
+        style.display = display(config.hud.show_scoreboard && shown);
+    }
+    for mut style in clock_query.iter_mut() {
+        style.display = display(config.hud.show_clock);
+    }
+}
+
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds.ceil() as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn clock_text_system(
+    clock: Res<MatchClock>,
+    time: Res<Time>,
+    mut text_query: Query<&mut Text, With<ClockText>>,
+    mode: Res<ActiveMode>,
+) {
+    let (value, color) = if !mode.0.timed() {
+        (String::new(), Color::WHITE)
+    } else if clock.sudden_death {
+        ("SUDDEN DEATH".to_string(), Color::ORANGE)
+    } else if clock.in_stoppage() {
+        let added = clock.elapsed - clock.duration;
+        (
+            format!("0:00 +{}", format_time(clock.stoppage - added)),
+            Color::YELLOW,
+        )
+    } else if clock.half_remaining() <= WARNING_TIME {
+        // pulse between red and white in the last seconds
+        let pulse = (time.elapsed_seconds() * 8.0).sin() * 0.5 + 0.5;
+        (
+            format_time(clock.half_remaining()),
+            Color::rgb(1.0, pulse, pulse),
+        )
+    } else {
+        (format_time(clock.half_remaining()), Color::WHITE)
+    };
+    let value = if clock.halves > 1 && !clock.sudden_death && mode.0.timed() {
+        format!("H{} {}", clock.half, value)
+    } else {
+        value
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = value.clone();
+        text.sections[0].style.color = color;
+    }
+}
+
+// each bar follows its player's charge and hides while kick is up
+fn power_bar_system(
+    player_query: Query<(&PlayerIndex, &KickCharge), With<Player>>,
+    mut bar_query: Query<(&PowerBar, &mut Style, &Children), Without<PowerFill>>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<PowerFill>>,
+) {
+    for (bar, mut style, children) in bar_query.iter_mut() {
+        let fraction = player_query
+            .iter()
+            .find(|(index, _)| index.0 == bar.0)
+            .and_then(|(_, charge)| charge.fraction());
+        style.display = if fraction.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        let fraction = fraction.unwrap_or_default().clamp(0.0, 1.0);
+        for child in children.iter() {
+            let Ok((mut fill_style, mut color)) = fill_query.get_mut(*child) else {
+                continue;
+            };
+            fill_style.size.width = Val::Percent(fraction * 100.0);
+            // yellow running to red at full charge
+            *color = Color::rgb(1.0, 1.0 - fraction, 0.0).into();
+        }
+    }
+}
+
+// a full bar is hidden, it only shows up once sprinting has cost something
+fn stamina_bar_system(
+    player_query: Query<(&PlayerIndex, &Stamina), With<Player>>,
+    mut bar_query: Query<(&StaminaBar, &mut Style, &Children), Without<StaminaFill>>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<StaminaFill>>,
+) {
+    for (bar, mut style, children) in bar_query.iter_mut() {
+        let stamina = player_query
+            .iter()
+            .find(|(index, _)| index.0 == bar.0)
+            .map(|(_, stamina)| stamina.0)
+            .filter(|stamina| *stamina < 1.0);
+        style.display = if stamina.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        let stamina = stamina.unwrap_or(1.0).clamp(0.0, 1.0);
+        for child in children.iter() {
+            let Ok((mut fill_style, mut color)) = fill_query.get_mut(*child) else {
+                continue;
+            };
+            fill_style.size.width = Val::Percent(stamina * 100.0);
+            // greys out as the legs run empty
+            *color = (Color::GRAY * (1.0 - stamina) + Color::CYAN * stamina).into();
+        }
+    }
+}
+
+// always up while the player is out, an empty meter is worth knowing about
+fn boost_bar_system(
+    player_query: Query<(&PlayerIndex, &BoostMeter), With<Player>>,
+    mut bar_query: Query<(&BoostBar, &mut Style, &Children), Without<BoostFill>>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<BoostFill>>,
+) {
+    for (bar, mut style, children) in bar_query.iter_mut() {
+        let meter = player_query
+            .iter()
+            .find(|(index, _)| index.0 == bar.0)
+            .map(|(_, meter)| meter.fuel);
+        style.display = if meter.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        let fuel = meter.unwrap_or_default().clamp(0.0, 1.0);
+        for child in children.iter() {
+            let Ok((mut fill_style, mut color)) = fill_query.get_mut(*child) else {
+                continue;
+            };
+            fill_style.size.width = Val::Percent(fuel * 100.0);
+            *color = Color::ORANGE.into();
+        }
+    }
+}
+
+#[cfg(feature = "develop")]
+fn fps_text_system(diagnostics: Res<Diagnostics>, mut text_query: Query<&mut Text, With<FpsText>>) {
+    let Some(fps) = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+    else {
+        return;
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("{:.0} fps", fps);
+    }
+}
+
+fn scoreboard_system(score: Res<Score>, mut digit_query: Query<(&mut Text, &mut ScoreDigit)>) {
+    if !score.is_changed() {
+        return;
+    }
+
+    for (mut text, mut digit) in digit_query.iter_mut() {
+        let value = score.of(digit.team);
+        if value == digit.shown {
+            continue;
+        }
+        // only goals pop, a rematch resetting to zero just updates quietly
+        if value > digit.shown {
+            digit.pop.reset();
+        }
+        digit.shown = value;
+        text.sections[0].value = value.to_string();
+    }
+}
+
+fn score_pop_system(time: Res<Time>, mut digit_query: Query<(&mut Text, &mut ScoreDigit)>) {
+    for (mut text, mut digit) in digit_query.iter_mut() {
+        if digit.pop.finished() {
+            continue;
+        }
+        digit.pop.tick(time.delta());
+        let t = digit.pop.percent_left();
+        let section = &mut text.sections[0];
+        section.style.font_size = SCORE_FONT_SIZE * (1.0 + 0.6 * t);
+        section.style.color = Color::WHITE * (1.0 - t) + digit.team.color() * t;
+    }
+}
@@ -0,0 +1,335 @@
+use super::bindings::{self, Action, InputMap, KeyState, Rebinding, ScanNames};
+use super::config::GameConfig;
+use super::devices::{DeviceAssignment, InputDevice};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StickSettings::default())
+            .insert_resource(PlayerInput::default())
+            .insert_resource(LocalInputs::default())
+            .insert_resource(ControllerSettingsOpen(false))
+            .add_system_to_stage(CoreStage::PreUpdate, gather_input_system.after(InputSystem))
+            .add_system(controls_config_system)
+            .add_system(controller_settings_ui_system);
+    }
+}
+
+// the per-frame view of what the local player wants, whatever device it came from
+#[derive(Resource, Default, Clone)]
+pub struct PlayerInput {
+    // x is screen right, y is up field
+    pub movement: Vec2,
+    pub aim: Vec2,
+    pub kick: bool,
+    pub kick_pressed: bool,
+    pub kick_released: bool,
+    pub sprint: bool,
+    pub dash_pressed: bool,
+    pub jump_pressed: bool,
+    pub boost: bool,
+    raw_movement: Vec2,
+    raw_aim: Vec2,
+}
+
+// one entry per local player slot, PlayerInput mirrors the first
+#[derive(Resource, Default)]
+pub struct LocalInputs(pub Vec<PlayerInput>);
+
+impl PlayerInput {
+    // the camera looks down +z with +x on screen left
+    pub fn world_movement(&self) -> Vec3 {
+        Vec3::new(-self.movement.x, 0.0, self.movement.y)
+    }
+
+    pub fn world_aim(&self) -> Vec3 {
+        Vec3::new(-self.aim.x, 0.0, self.aim.y)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    Linear,
+    Expo(f32),
+}
+
+impl ResponseCurve {
+    fn apply(&self, value: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Expo(exponent) => value.signum() * value.abs().powf(*exponent),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisSettings {
+    pub deadzone: f32,
+    pub curve: ResponseCurve,
+}
+
+impl AxisSettings {
+    // rescale so output starts at zero right at the deadzone edge, then shape
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        let scaled = ((magnitude - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        self.curve.apply(scaled) * value.signum()
+    }
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickSettings {
+    pub move_x: AxisSettings,
+    pub move_y: AxisSettings,
+    pub aim_x: AxisSettings,
+    pub aim_y: AxisSettings,
+}
+
+#[derive(Resource)]
+pub struct ControllerSettingsOpen(pub bool);
+
+pub fn gather_input_system(
+    mut input: ResMut<PlayerInput>,
+    mut locals: ResMut<LocalInputs>,
+    settings: Res<StickSettings>,
+    keyboard: Res<Input<KeyCode>>,
+    scans: Res<Input<ScanCode>>,
+    input_map: Res<InputMap>,
+    assignment: Res<DeviceAssignment>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+) {
+    let keys = KeyState {
+        keys: &keyboard,
+        scans: &scans,
+    };
+    locals.0 = assignment
+        .slots
+        .iter()
+        .map(|device| read_device(*device, &settings, &keys, &input_map, &axes, &buttons))
+        .collect();
+    *input = locals.0.first().cloned().unwrap_or_default();
+}
+
+fn read_device(
+    device: InputDevice,
+    settings: &StickSettings,
+    keys: &KeyState,
+    input_map: &InputMap,
+    axes: &Axis<GamepadAxis>,
+    buttons: &Input<GamepadButton>,
+) -> PlayerInput {
+    let mut raw_movement = Vec2::ZERO;
+    let mut raw_aim = Vec2::ZERO;
+    // held, just pressed, just released
+    let mut kick = (false, false, false);
+    let mut sprint = false;
+    let mut dash = false;
+    let mut jump = false;
+    let mut boost = false;
+    if let InputDevice::Gamepad(gamepad) = device {
+        let axis = |kind| {
+            axes.get(GamepadAxis::new(gamepad, kind))
+                .unwrap_or_default()
+        };
+        raw_movement = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        raw_aim = Vec2::new(
+            axis(GamepadAxisType::RightStickX),
+            axis(GamepadAxisType::RightStickY),
+        );
+        let kick_buttons: Vec<GamepadButton> = input_map
+            .pad_kick
+            .iter()
+            .map(|kind| GamepadButton::new(gamepad, *kind))
+            .collect();
+        let held = kick_buttons.iter().any(|button| buttons.pressed(*button));
+        kick = (
+            held,
+            kick_buttons
+                .iter()
+                .any(|button| buttons.just_pressed(*button)),
+            !held
+                && kick_buttons
+                    .iter()
+                    .any(|button| buttons.just_released(*button)),
+        );
+        sprint = buttons.pressed(GamepadButton::new(gamepad, input_map.pad_sprint));
+        dash = buttons.just_pressed(GamepadButton::new(gamepad, input_map.pad_dash));
+        jump = buttons.just_pressed(GamepadButton::new(gamepad, input_map.pad_jump));
+        boost = buttons.pressed(GamepadButton::new(gamepad, input_map.pad_boost));
+    }
+
+    let mut movement = Vec2::new(
+        settings.move_x.apply(raw_movement.x),
+        settings.move_y.apply(raw_movement.y),
+    );
+    let aim = Vec2::new(
+        settings.aim_x.apply(raw_aim.x),
+        settings.aim_y.apply(raw_aim.y),
+    );
+
+    if let InputDevice::Keyboard(set) = device {
+        let [up, down, left, right, kick_key, sprint_key, dash_key, jump_key, boost_key] =
+            Action::ALL.map(|action| input_map.key(set, action));
+        if up.pressed(keys) {
+            movement.y += 1.0;
+        }
+        if down.pressed(keys) {
+            movement.y -= 1.0;
+        }
+        if left.pressed(keys) {
+            movement.x -= 1.0;
+        }
+        if right.pressed(keys) {
+            movement.x += 1.0;
+        }
+        kick = (
+            kick_key.pressed(keys),
+            kick_key.just_pressed(keys),
+            kick_key.just_released(keys),
+        );
+        sprint = sprint_key.pressed(keys);
+        dash = dash_key.just_pressed(keys);
+        jump = jump_key.just_pressed(keys);
+        boost = boost_key.pressed(keys);
+    }
+
+    PlayerInput {
+        movement: movement.clamp(Vec2::splat(-1.0), Vec2::splat(1.0)),
+        aim,
+        kick: kick.0,
+        kick_pressed: kick.1,
+        kick_released: kick.2,
+        sprint,
+        dash_pressed: dash,
+        jump_pressed: jump,
+        boost,
+        raw_movement,
+        raw_aim,
+    }
+}
+
+fn axis_settings_ui(ui: &mut egui::Ui, label: &str, axis: &mut AxisSettings) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::Slider::new(&mut axis.deadzone, 0.0..=0.5).text("deadzone"));
+        let mut expo = matches!(axis.curve, ResponseCurve::Expo(_));
+        if ui.checkbox(&mut expo, "expo").changed() {
+            axis.curve = if expo {
+                ResponseCurve::Expo(2.0)
+            } else {
+                ResponseCurve::Linear
+            };
+        }
+        if let ResponseCurve::Expo(exponent) = &mut axis.curve {
+            ui.add(egui::Slider::new(exponent, 1.0..=4.0).text("exponent"));
+        }
+    });
+}
+
+// stick box with the raw position in grey and the processed one in yellow,
+// next to the response curve of the x axis
+fn stick_preview_ui(ui: &mut egui::Ui, raw: Vec2, shaped: Vec2, x_axis: &AxisSettings) {
+    let size = egui::vec2(120.0, 120.0);
+    ui.horizontal(|ui| {
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+        let dead = x_axis.deadzone * rect.width() / 2.0;
+        painter.rect_filled(
+            egui::Rect::from_center_size(rect.center(), egui::vec2(dead * 2.0, dead * 2.0)),
+            0.0,
+            egui::Color32::from_gray(40),
+        );
+        let to_screen = |v: Vec2| {
+            rect.center() + egui::vec2(v.x * rect.width() / 2.0, -v.y * rect.height() / 2.0)
+        };
+        painter.circle_filled(to_screen(raw), 4.0, egui::Color32::GRAY);
+        painter.circle_filled(to_screen(shaped), 4.0, egui::Color32::YELLOW);
+
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+        let points: Vec<egui::Pos2> = (0..=32)
+            .map(|i| {
+                let input = i as f32 / 32.0;
+                let output = x_axis.apply(input);
+                egui::pos2(
+                    rect.left() + input * rect.width(),
+                    rect.bottom() - output * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(2.0, egui::Color32::YELLOW),
+        ));
+    });
+}
+
+// the config file seeds the stick tuning, and reseeds it whenever it is reloaded
+fn controls_config_system(config: Res<GameConfig>, mut settings: ResMut<StickSettings>) {
+    if config.is_changed() {
+        *settings = config.controls;
+    }
+}
+
+fn controller_settings_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut settings: ResMut<StickSettings>,
+    mut open: ResMut<ControllerSettingsOpen>,
+    input: Res<PlayerInput>,
+    keyboard: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut rebinding: ResMut<Rebinding>,
+    scan_names: Res<ScanNames>,
+) {
+    if keyboard.just_pressed(KeyCode::F2) {
+        open.0 = !open.0;
+    }
+    if !open.0 {
+        return;
+    }
+
+    let mut edited = *settings;
+    egui::Window::new("Controller").show(egui_context.ctx_mut(), |ui| {
+        ui.heading("Movement");
+        axis_settings_ui(ui, "X", &mut edited.move_x);
+        axis_settings_ui(ui, "Y", &mut edited.move_y);
+        stick_preview_ui(ui, input.raw_movement, input.movement, &edited.move_x);
+        ui.separator();
+        ui.heading("Aim");
+        axis_settings_ui(ui, "X", &mut edited.aim_x);
+        axis_settings_ui(ui, "Y", &mut edited.aim_y);
+        stick_preview_ui(ui, input.raw_aim, input.aim, &edited.aim_x);
+        ui.separator();
+        ui.heading("Keys");
+        bindings::bindings_ui(ui, &input_map, &mut rebinding, &scan_names);
+    });
+
+    if edited != *settings {
+        *settings = edited;
+    }
+}
@@ -0,0 +1,437 @@
+use super::arena::{Arena, ArenaPiece, ArenaPieceId, LodSettings, PieceKind, PropShape};
+use super::marks::GameCamera;
+use bevy::core_pipeline::core_3d::Opaque3d;
+use bevy::ecs::system::{lifetimeless::*, SystemParamItem};
+use bevy::pbr::{
+    MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup, SetMeshViewBindGroup,
+};
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::mesh::{GpuBufferInfo, MeshVertexBufferLayout};
+use bevy::render::primitives::{Aabb, Frustum};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_phase::{
+    AddRenderCommand, DrawFunctions, EntityRenderCommand, RenderCommandResult, RenderPhase,
+    SetItemPipeline, TrackedRenderPass,
+};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+use bevy::render::view::{ExtractedView, NoFrustumCulling, VisibilitySystems};
+use bevy::render::{RenderApp, RenderStage};
+use bevy::transform::TransformSystem;
+use std::collections::HashMap;
+
+// floats per instance: a 4x4 world matrix then an rgba color
+const INSTANCE_FLOATS: usize = 20;
+
+pub struct InstancingPlugin;
+
+impl Plugin for InstancingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ExtractComponentPlugin::<PropInstances>::default())
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                prop_batch_system
+                    .after(TransformSystem::TransformPropagate)
+                    .after(VisibilitySystems::UpdatePerspectiveFrusta),
+            );
+        app.sub_app_mut(RenderApp)
+            .add_render_command::<Opaque3d, DrawInstanced>()
+            .init_resource::<InstancedPipeline>()
+            .init_resource::<SpecializedMeshPipelines<InstancedPipeline>>()
+            .init_resource::<InstanceBuffers>()
+            .add_system_to_stage(RenderStage::Queue, queue_instanced)
+            .add_system_to_stage(RenderStage::Prepare, prepare_instance_buffers);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PropLod {
+    Full,
+    Low,
+    // a flat card turned towards the camera
+    Impostor,
+}
+
+// every arena prop of one shape and detail level drawn in a single call; the props
+// themselves keep their colliders and transforms but carry no mesh of their own
+#[derive(Component, Clone)]
+pub struct PropInstances {
+    shape: PropShape,
+    lod: PropLod,
+    data: Vec<f32>,
+}
+
+impl ExtractComponent for PropInstances {
+    type Query = &'static PropInstances;
+    type Filter = ();
+
+    fn extract_component(item: bevy::ecs::query::QueryItem<'_, Self::Query>) -> Self {
+        item.clone()
+    }
+}
+
+pub fn prop_mesh(shape: PropShape, lod: PropLod) -> Mesh {
+    match (shape, lod) {
+        (_, PropLod::Impostor) => Mesh::from(shape::Quad::new(Vec2::splat(2.0))),
+        (PropShape::Box, _) => Mesh::from(shape::Box::new(2.0, 2.0, 2.0)),
+        (PropShape::Sphere, PropLod::Full) => Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            sectors: 32,
+            stacks: 16,
+        }),
+        (PropShape::Sphere, PropLod::Low) => Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            sectors: 8,
+            stacks: 4,
+        }),
+    }
+}
+
+fn prop_lod(distance: f32, settings: &LodSettings) -> Option<PropLod> {
+    if distance < settings.low_distance {
+        Some(PropLod::Full)
+    } else if distance < settings.impostor_distance {
+        Some(PropLod::Low)
+    } else if settings.impostors {
+        Some(PropLod::Impostor)
+    } else {
+        None
+    }
+}
+
+// rebuilds the instance lists whenever a prop or the camera moves, or the arena changes
+// axis aligned world box around a prop, rotation included
+fn world_bounds(tf: &GlobalTransform, piece: &ArenaPiece) -> (Vec3, Vec3) {
+    let matrix = tf.compute_matrix();
+    let half = Vec3::from(piece.half_extents);
+    let center = matrix.transform_point3(Vec3::ZERO);
+    let reach = matrix.x_axis.truncate().abs() * half.x
+        + matrix.y_axis.truncate().abs() * half.y
+        + matrix.z_axis.truncate().abs() * half.z;
+    (center - reach, center + reach)
+}
+
+fn prop_batch_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut batches: Query<&mut PropInstances>,
+    arena: Res<Arena>,
+    props: Query<(&GlobalTransform, &ArenaPieceId)>,
+    moved: Query<(), (Changed<GlobalTransform>, With<ArenaPieceId>)>,
+    camera_query: Query<(&GlobalTransform, &Frustum), With<GameCamera>>,
+    camera_moved: Query<(), (Changed<GlobalTransform>, With<GameCamera>)>,
+    mut count: Local<usize>,
+) {
+    // with the screen split a prop stays in if any view can see it
+    let frustums: Vec<&Frustum> = camera_query.iter().map(|(_, frustum)| frustum).collect();
+    let Some((camera_tf, _)) = camera_query.iter().next() else {
+        return;
+    };
+    let total = props.iter().count();
+    if !arena.is_changed() && camera_moved.is_empty() && moved.is_empty() && total == *count {
+        return;
+    }
+    *count = total;
+
+    let camera = camera_tf.translation();
+    let facing = camera_tf.to_scale_rotation_translation().1;
+    let culling = &arena.descriptor.culling;
+    let visible_groups = culling.visible_groups(camera);
+    let candidates: Vec<(&GlobalTransform, &ArenaPiece)> = props
+        .iter()
+        .filter_map(|(tf, ArenaPieceId(id))| Some((tf, arena.descriptor.pieces.get(*id)?)))
+        .filter(|(_, piece)| piece.kind == PieceKind::Prop && piece.visible)
+        .filter(|(_, piece)| match (&visible_groups, &piece.group) {
+            (Some(groups), Some(group)) => groups.contains(&group.as_str()),
+            _ => true,
+        })
+        .collect();
+
+    // grouped props share one world space box, so a whole cluster is tested at once
+    let mut group_bounds: HashMap<&str, (Vec3, Vec3)> = HashMap::new();
+    for (tf, piece) in candidates.iter() {
+        let Some(group) = &piece.group else {
+            continue;
+        };
+        let (min, max) = world_bounds(tf, piece);
+        let bounds = group_bounds.entry(group.as_str()).or_insert((min, max));
+        *bounds = (bounds.0.min(min), bounds.1.max(max));
+    }
+    let group_visible: HashMap<&str, bool> = group_bounds
+        .into_iter()
+        .map(|(group, (min, max))| {
+            let margin = Vec3::splat(culling.margin);
+            let aabb = Aabb::from_min_max(min - margin, max + margin);
+            let visible = frustums
+                .iter()
+                .any(|frustum| frustum.intersects_obb(&aabb, &Mat4::IDENTITY, true));
+            (group, visible)
+        })
+        .collect();
+
+    let mut lists: Vec<((PropShape, PropLod), Vec<f32>)> = Vec::new();
+    for (tf, piece) in candidates {
+        if culling.frustum {
+            let visible = match &piece.group {
+                Some(group) => group_visible.get(group.as_str()).copied().unwrap_or(true),
+                None => {
+                    let half = (Vec3::from(piece.half_extents) + culling.margin).max(Vec3::ZERO);
+                    let aabb = Aabb {
+                        center: Vec3A::ZERO,
+                        half_extents: half.into(),
+                    };
+                    let model = tf.compute_matrix();
+                    frustums
+                        .iter()
+                        .any(|frustum| frustum.intersects_obb(&aabb, &model, true))
+                }
+            };
+            if !visible {
+                continue;
+            }
+        }
+        let distance = camera_query
+            .iter()
+            .map(|(camera_tf, _)| tf.translation().distance(camera_tf.translation()))
+            .fold(f32::MAX, f32::min);
+        let Some(lod) = prop_lod(distance, &arena.descriptor.lod) else {
+            continue;
+        };
+        // the unit meshes are two units across, scale them out to the half extents
+        let model = match lod {
+            PropLod::Impostor => {
+                let (scale, _, translation) = tf.to_scale_rotation_translation();
+                let size = (Vec3::from(piece.half_extents) * scale).max_element();
+                Mat4::from_scale_rotation_translation(Vec3::splat(size), facing, translation)
+            }
+            PropLod::Full | PropLod::Low => {
+                tf.compute_matrix() * Mat4::from_scale(Vec3::from(piece.half_extents))
+            }
+        };
+
+        let key = (piece.shape, lod);
+        let index = match lists.iter().position(|(k, _)| *k == key) {
+            Some(index) => index,
+            None => {
+                lists.push((key, Vec::new()));
+                lists.len() - 1
+            }
+        };
+        let data = &mut lists[index].1;
+        data.extend_from_slice(&model.to_cols_array());
+        data.extend_from_slice(&[piece.color[0], piece.color[1], piece.color[2], 1.0]);
+    }
+
+    // batches that lost all their props are emptied rather than despawned
+    for mut batch in batches.iter_mut() {
+        let key = (batch.shape, batch.lod);
+        batch.data = match lists.iter().position(|(k, _)| *k == key) {
+            Some(index) => lists.swap_remove(index).1,
+            None => Vec::new(),
+        };
+    }
+    for ((shape, lod), data) in lists {
+        commands.spawn((
+            SpatialBundle::default(),
+            meshes.add(prop_mesh(shape, lod)),
+            PropInstances { shape, lod, data },
+            // instances are spread all over the arena, the batch entity's
+            // own bounds say nothing about them
+            NoFrustumCulling,
+            Name::new("PropInstances"),
+        ));
+    }
+}
+
+fn queue_instanced(
+    draw_functions: Res<DrawFunctions<Opaque3d>>,
+    instanced_pipeline: Res<InstancedPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<InstancedPipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    meshes: Res<RenderAssets<Mesh>>,
+    batches: Query<(Entity, &MeshUniform, &Handle<Mesh>, &PropInstances)>,
+    mut views: Query<(&ExtractedView, &mut RenderPhase<Opaque3d>)>,
+) {
+    let Some(draw) = draw_functions.read().get_id::<DrawInstanced>() else {
+        return;
+    };
+    let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples);
+
+    for (view, mut phase) in views.iter_mut() {
+        let rangefinder = view.rangefinder3d();
+        for (entity, mesh_uniform, mesh_handle, instances) in batches.iter() {
+            let Some(mesh) = meshes.get(mesh_handle) else {
+                continue;
+            };
+            if instances.data.is_empty() {
+                continue;
+            }
+            let key = msaa_key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
+            let Ok(pipeline) =
+                pipelines.specialize(&mut pipeline_cache, &instanced_pipeline, key, &mesh.layout)
+            else {
+                continue;
+            };
+            phase.add(Opaque3d {
+                entity,
+                pipeline,
+                draw_function: draw,
+                distance: rangefinder.distance(&mesh_uniform.transform),
+            });
+        }
+    }
+}
+
+#[derive(Component)]
+struct InstanceBuffer {
+    buffer: Buffer,
+    length: usize,
+}
+
+// kept from frame to frame by batch, render entities are rebuilt every frame but keep
+// the id of the batch they were extracted from. the capacity is in bytes
+#[derive(Resource, Default)]
+struct InstanceBuffers(HashMap<Entity, (Buffer, u64)>);
+
+fn prepare_instance_buffers(
+    mut commands: Commands,
+    batches: Query<(Entity, &PropInstances)>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut buffers: ResMut<InstanceBuffers>,
+) {
+    // batches that went away drop their buffer
+    buffers.0.retain(|entity, _| batches.contains(*entity));
+    for (entity, instances) in batches.iter() {
+        if instances.data.is_empty() {
+            continue;
+        }
+        let bytes: Vec<u8> = instances
+            .data
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        let size = bytes.len() as u64;
+        // only a batch that outgrew its buffer gets a new one, with room to spare
+        if buffers.0.get(&entity).map_or(true, |(_, capacity)| *capacity < size) {
+            let capacity = size.next_power_of_two();
+            let buffer = render_device.create_buffer(&BufferDescriptor {
+                label: Some("prop instance buffer"),
+                size: capacity,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            buffers.0.insert(entity, (buffer, capacity));
+        }
+        let buffer = buffers.0[&entity].0.clone();
+        render_queue.write_buffer(&buffer, 0, &bytes);
+        commands.entity(entity).insert(InstanceBuffer {
+            buffer,
+            length: instances.data.len() / INSTANCE_FLOATS,
+        });
+    }
+}
+
+#[derive(Resource)]
+struct InstancedPipeline {
+    shader: Handle<Shader>,
+    mesh_pipeline: MeshPipeline,
+}
+
+impl FromWorld for InstancedPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let shader = world
+            .resource::<AssetServer>()
+            .load("shaders/instancing.wgsl");
+        let mesh_pipeline = world.resource::<MeshPipeline>().clone();
+        Self {
+            shader,
+            mesh_pipeline,
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for InstancedPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        descriptor.vertex.shader = self.shader.clone();
+        let vec4 = VertexFormat::Float32x4;
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: (INSTANCE_FLOATS * std::mem::size_of::<f32>()) as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: (0..5)
+                .map(|i| VertexAttribute {
+                    format: vec4,
+                    offset: vec4.size() * i,
+                    shader_location: 3 + i as u32,
+                })
+                .collect(),
+        });
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader = self.shader.clone();
+        }
+        descriptor.layout = Some(vec![
+            self.mesh_pipeline.view_layout.clone(),
+            self.mesh_pipeline.mesh_layout.clone(),
+        ]);
+        Ok(descriptor)
+    }
+}
+
+type DrawInstanced = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    DrawMeshInstanced,
+);
+
+struct DrawMeshInstanced;
+
+impl EntityRenderCommand for DrawMeshInstanced {
+    type Param = (
+        SRes<RenderAssets<Mesh>>,
+        SQuery<Read<Handle<Mesh>>>,
+        SQuery<Read<InstanceBuffer>>,
+    );
+
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (meshes, mesh_query, buffer_query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let (Ok(mesh_handle), Ok(instances)) = (mesh_query.get(item), buffer_query.get_inner(item))
+        else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, instances.buffer.slice(..));
+        let count = instances.length as u32;
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count: index_count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*index_count, 0, 0..count);
+            }
+            GpuBufferInfo::NonIndexed { vertex_count } => {
+                pass.draw(0..*vertex_count, 0..count);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
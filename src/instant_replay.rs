@@ -0,0 +1,231 @@
+use super::game_state::GameState;
+use super::input::PlayerInput;
+use super::marks::{Ball, Team};
+use super::rematch::RematchEvent;
+use super::replay::{Replay, ReplayFrame};
+use super::tape::TapeSample;
+use super::GoalScoredEvent;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::collections::VecDeque;
+
+const BUFFER_SECONDS: f32 = 5.0;
+// keeps recording a moment after the goal so the replay ends with the ball in the net
+const REPLAY_DELAY: f32 = 1.0;
+// low on the touchline, a little behind the ball
+const CAMERA_OFFSET: Vec3 = Vec3::new(9.0, 2.5, -3.0);
+const CAMERA_STIFFNESS: f32 = 3.0;
+
+pub struct InstantReplayPlugin;
+
+impl Plugin for InstantReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InstantReplay>()
+            .add_system(instant_reset_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(instant_record_system)
+                    .with_system(instant_trigger_system.after(instant_record_system)),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Replay).with_system(enter_replay))
+            .add_system_set(
+                SystemSet::on_update(GameState::Replay)
+                    .with_system(instant_playback_system)
+                    .with_system(instant_replay_ui_system),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Replay).with_system(exit_replay));
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct InstantReplay {
+    // the last few seconds of play, oldest first
+    buffer: VecDeque<ReplayFrame>,
+    time: f32,
+    // counts down from a goal to the start of the replay
+    delay: Option<Timer>,
+    playback: Option<Replay>,
+    // the ball that went in, the camera follows it through the replay
+    scorer: Option<Entity>,
+    cursor: f32,
+    // where everything really is, put back when the replay ends
+    live: Vec<(Entity, Transform)>,
+}
+
+impl InstantReplay {
+    // where a body really is, the playback has moved it somewhere in the past
+    pub fn live(&self, entity: Entity) -> Option<Transform> {
+        self.live
+            .iter()
+            .find(|(e, _)| *e == entity)
+            .map(|(_, tf)| *tf)
+    }
+}
+
+// films the replay so the game camera, whichever controller drives it, is left alone
+#[derive(Component)]
+struct ReplayCamera;
+
+fn instant_record_system(
+    mut instant: ResMut<InstantReplay>,
+    tracked_query: Query<(Entity, &Transform), Or<(With<Ball>, With<Team>)>>,
+    input: Res<PlayerInput>,
+    time: Res<Time>,
+) {
+    instant.time += time.delta_seconds();
+    let frame = ReplayFrame {
+        time: instant.time,
+        transforms: tracked_query.iter().map(|(e, tf)| (e, *tf)).collect(),
+        input: TapeSample::from(&*input),
+    };
+    instant.buffer.push_back(frame);
+    let oldest = instant.time - BUFFER_SECONDS;
+    while instant.buffer.front().map_or(false, |f| f.time < oldest) {
+        instant.buffer.pop_front();
+    }
+}
+
+fn instant_trigger_system(
+    mut instant: ResMut<InstantReplay>,
+    mut goal_events: EventReader<GoalScoredEvent>,
+    mut state: ResMut<State<GameState>>,
+    time: Res<Time>,
+) {
+    if let Some(ev) = goal_events.iter().last() {
+        instant.delay = Some(Timer::from_seconds(REPLAY_DELAY, TimerMode::Once));
+        instant.scorer = Some(ev.ball);
+    }
+    let Some(delay) = &mut instant.delay else {
+        return;
+    };
+    if !delay.tick(time.delta()).finished() {
+        return;
+    }
+    instant.delay = None;
+    if instant.buffer.len() < 2 {
+        return;
+    }
+
+    let frames = std::mem::take(&mut instant.buffer);
+    instant.playback = Some(Replay::from_frames(frames, Vec::new()));
+    if let Err(err) = state.push(GameState::Replay) {
+        debug!("Instant replay skipped: {:?}", err);
+        instant.playback = None;
+    }
+}
+
+fn enter_replay(
+    mut commands: Commands,
+    mut instant: ResMut<InstantReplay>,
+    tracked_query: Query<(Entity, &Transform), Or<(With<Ball>, With<Team>)>>,
+    ball_query: Query<&Transform, With<Ball>>,
+) {
+    instant.live = tracked_query.iter().map(|(e, tf)| (e, *tf)).collect();
+    instant.cursor = instant.playback.as_ref().map_or(0.0, Replay::start);
+
+    let focus = instant
+        .scorer
+        .and_then(|ball| ball_query.get(ball).ok())
+        .map(|tf| tf.translation)
+        .unwrap_or_default();
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                priority: 2,
+                ..default()
+            },
+            transform: Transform::from_translation(focus + CAMERA_OFFSET)
+                .looking_at(focus, Vec3::Y),
+            ..default()
+        },
+        // the hud belongs to the game camera underneath
+        UiCameraConfig { show_ui: false },
+        ReplayCamera,
+        Name::new("ReplayCamera"),
+    ));
+}
+
+fn instant_playback_system(
+    mut instant: ResMut<InstantReplay>,
+    mut state: ResMut<State<GameState>>,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut tf_query: Query<&mut Transform, Without<ReplayCamera>>,
+    mut camera_query: Query<&mut Transform, With<ReplayCamera>>,
+    time: Res<Time>,
+) {
+    let skipped =
+        keyboard.get_just_pressed().next().is_some() || buttons.get_just_pressed().next().is_some();
+    let Some(playback) = &instant.playback else {
+        state.pop().ok();
+        return;
+    };
+    let cursor = instant.cursor + time.delta_seconds();
+    if skipped || cursor >= playback.end() {
+        state.pop().ok();
+        return;
+    }
+
+    let ball = instant.scorer;
+    let mut focus = None;
+    for (entity, pose) in playback.transforms_at(cursor) {
+        if Some(entity) == ball {
+            focus = Some(pose.translation);
+        }
+        let Ok(mut tf) = tf_query.get_mut(entity) else {
+            continue;
+        };
+        tf.translation = pose.translation;
+        tf.rotation = pose.rotation;
+    }
+    instant.cursor = cursor;
+
+    let (Some(focus), Ok(mut camera_tf)) = (focus, camera_query.get_single_mut()) else {
+        return;
+    };
+    let t = (CAMERA_STIFFNESS * time.delta_seconds()).min(1.0);
+    camera_tf.translation = camera_tf.translation.lerp(focus + CAMERA_OFFSET, t);
+    camera_tf.look_at(focus, Vec3::Y);
+}
+
+fn exit_replay(
+    mut commands: Commands,
+    mut instant: ResMut<InstantReplay>,
+    mut tf_query: Query<&mut Transform>,
+    camera_query: Query<Entity, With<ReplayCamera>>,
+) {
+    for (entity, live) in instant.live.drain(..) {
+        if let Ok(mut tf) = tf_query.get_mut(entity) {
+            *tf = live;
+        }
+    }
+    instant.playback = None;
+    for entity in camera_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn instant_replay_ui_system(mut egui_context: ResMut<EguiContext>) {
+    egui::Area::new("instant_replay")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 48.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new("REPLAY")
+                    .size(28.0)
+                    .strong()
+                    .color(egui::Color32::YELLOW),
+            );
+            ui.label("press any key to skip");
+        });
+}
+
+// a fresh match starts with nothing to replay
+fn instant_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut instant: ResMut<InstantReplay>,
+) {
+    if rematch_events.iter().count() > 0 {
+        instant.buffer.clear();
+        instant.delay = None;
+    }
+}
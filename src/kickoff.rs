@@ -0,0 +1,208 @@
+use super::cli::StartupArgs;
+use super::determinism::GameRng;
+use super::marks::{Ball, Team};
+use super::net;
+use super::rematch::RematchEvent;
+use super::series::Series;
+use super::MatchEntities;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+
+// how far toward the possessing side the ball is placed at kickoff
+const KICKOFF_OFFSET: f32 = 1.0;
+// the side without the ball waits this far from the center line
+const HOLD_LINE: f32 = 2.0;
+// extra balls line up across the center line this far apart
+const MULTIBALL_SPACING: f32 = 1.5;
+
+pub struct KickoffPlugin;
+
+impl Plugin for KickoffPlugin {
+    fn build(&self, app: &mut App) {
+        let kickoff = Kickoff::new(
+            KickoffRule::CoinFlip,
+            &mut app.world.resource_mut::<GameRng>().rng,
+        );
+        app.insert_resource(kickoff)
+            .add_system(kickoff_decide_system)
+            .add_system(kickoff_ball_system.after(kickoff_decide_system))
+            .add_system(kickoff_ui_system);
+
+        // rollback sessions hold and release the kickoff in their own schedule
+        let rollback = app
+            .world
+            .get_resource::<StartupArgs>()
+            .map_or(false, net::rollback);
+        if !rollback {
+            app.add_system(kickoff_hold_system)
+                .add_system(kickoff_touch_system);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+pub enum KickoffRule {
+    #[default]
+    CoinFlip,
+    // the loser of the previous match starts with the ball
+    WinnerConcedes,
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Kickoff {
+    pub rule: KickoffRule,
+    pub possession: Team,
+    pub held: bool,
+}
+
+// only there so rollback can register it, the plugin inserts a coin flip
+impl Default for Kickoff {
+    fn default() -> Self {
+        Self {
+            rule: KickoffRule::default(),
+            possession: Team::Home,
+            held: true,
+        }
+    }
+}
+
+impl Kickoff {
+    pub fn new(rule: KickoffRule, rng: &mut impl Rng) -> Self {
+        Self {
+            rule,
+            possession: coin_flip(rng),
+            held: true,
+        }
+    }
+
+    // home defends the -z end, so its half of the center circle is negative
+    pub fn ball_spot(&self) -> Vec3 {
+        match self.possession {
+            Team::Home => Vec3::new(0.0, 4.0, -KICKOFF_OFFSET),
+            Team::Away => Vec3::new(0.0, 4.0, KICKOFF_OFFSET),
+        }
+    }
+
+    // the first ball takes the usual spot, the rest alternate out either side of it
+    pub fn ball_spot_at(&self, index: usize) -> Vec3 {
+        let side = if index % 2 == 1 { -1.0 } else { 1.0 };
+        let offset = ((index + 1) / 2) as f32 * MULTIBALL_SPACING * side;
+        self.ball_spot() + Vec3::X * offset
+    }
+
+    // restart after a goal with the conceding side on the ball
+    pub fn restart(&mut self, conceding: Team) {
+        self.possession = conceding;
+        self.held = true;
+    }
+}
+
+fn coin_flip(rng: &mut impl Rng) -> Team {
+    if rng.gen_bool(0.5) {
+        Team::Home
+    } else {
+        Team::Away
+    }
+}
+
+fn kickoff_decide_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut kickoff: ResMut<Kickoff>,
+    series: Option<Res<Series>>,
+    mut rng: ResMut<GameRng>,
+) {
+    if rematch_events.iter().count() == 0 {
+        return;
+    }
+
+    let previous_loser = series
+        .as_ref()
+        .and_then(|series| series.results.last())
+        .map(|r| match r.winner {
+            Team::Home => Team::Away,
+            Team::Away => Team::Home,
+        });
+    kickoff.possession = match (kickoff.rule, previous_loser) {
+        (KickoffRule::WinnerConcedes, Some(loser)) => loser,
+        _ => coin_flip(&mut rng.rng),
+    };
+    kickoff.held = true;
+    info!("{} kicks off", kickoff.possession.name());
+}
+
+fn kickoff_ball_system(
+    kickoff: Res<Kickoff>,
+    entities: Res<MatchEntities>,
+    mut ball_query: Query<(Entity, &mut Transform), Added<Ball>>,
+) {
+    for (entity, mut ball_tf) in ball_query.iter_mut() {
+        let index = entities.balls.iter().position(|ball| *ball == entity);
+        ball_tf.translation = kickoff.ball_spot_at(index.unwrap_or(0));
+    }
+}
+
+fn kickoff_hold_system(
+    kickoff: Res<Kickoff>,
+    mut team_query: Query<(&Team, &mut Transform), Without<Ball>>,
+) {
+    hold_line(&kickoff, team_query.iter_mut());
+}
+
+// keeps the side without the ball out of the center circle until someone touches it
+pub fn hold_line<'a>(
+    kickoff: &Kickoff,
+    players: impl Iterator<Item = (&'a Team, Mut<'a, Transform>)>,
+) {
+    if !kickoff.held {
+        return;
+    }
+
+    for (team, mut tf) in players {
+        if *team == kickoff.possession {
+            continue;
+        }
+        match team {
+            Team::Home => tf.translation.z = tf.translation.z.min(-HOLD_LINE),
+            Team::Away => tf.translation.z = tf.translation.z.max(HOLD_LINE),
+        }
+    }
+}
+
+fn kickoff_touch_system(
+    mut kickoff: ResMut<Kickoff>,
+    mut collision_events: EventReader<CollisionEvent>,
+    ball_query: Query<Entity, With<Ball>>,
+    team_query: Query<Entity, With<Team>>,
+) {
+    for ev in collision_events.iter() {
+        if !kickoff.held {
+            continue;
+        }
+        if let CollisionEvent::Started(a, b, _) = ev {
+            let touched = (ball_query.contains(*a) && team_query.contains(*b))
+                || (ball_query.contains(*b) && team_query.contains(*a));
+            if touched {
+                kickoff.held = false;
+            }
+        }
+    }
+}
+
+fn kickoff_ui_system(mut egui_context: ResMut<EguiContext>, kickoff: Res<Kickoff>) {
+    if !kickoff.held {
+        return;
+    }
+
+    egui::Area::new("kickoff")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 16.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(
+                egui::RichText::new(format!("{} kicks off", kickoff.possession.name()))
+                    .size(24.0)
+                    .color(egui::Color32::WHITE),
+            );
+        });
+}
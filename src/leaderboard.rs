@@ -0,0 +1,216 @@
+use super::clock::MatchClock;
+use super::config::{GameConfig, LeaderboardConfig};
+use super::game_state::GameState;
+use super::mode::ActiveMode;
+use super::nametags::profile_name;
+use super::Score;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy_egui::{egui, EguiContext};
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const TOP_COUNT: usize = 10;
+
+// the server is set in GameConfig::leaderboard, without one nothing is requested
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Leaderboard>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(fetch_top_system))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(submit_result_system),
+            )
+            .add_system(leaderboard_task_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu).with_system(leaderboard_ui_system),
+            );
+    }
+}
+
+// what gets posted at full time, the server decides how results rank
+#[derive(Serialize, Clone)]
+struct MatchResultPost {
+    name: String,
+    mode: String,
+    home_goals: u32,
+    away_goals: u32,
+    duration: f32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub mode: String,
+    pub home_goals: u32,
+    pub away_goals: u32,
+}
+
+// requests block a compute thread, never a frame, and give up after the configured
+// timeout; results go out one at a time in the order the matches ended
+#[derive(Resource, Default)]
+pub struct Leaderboard {
+    top: Vec<LeaderboardEntry>,
+    error: Option<String>,
+    fetch: Option<Task<Result<Vec<LeaderboardEntry>, String>>>,
+    pending: VecDeque<MatchResultPost>,
+    // the result in flight stays at the front of `pending` until the server has it
+    submit: Option<Task<Result<(), String>>>,
+}
+
+fn agent(config: &LeaderboardConfig) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs_f32(config.timeout))
+        .build()
+}
+
+fn endpoint(config: &LeaderboardConfig) -> Option<&str> {
+    config
+        .endpoint
+        .as_deref()
+        .map(|endpoint| endpoint.trim_end_matches('/'))
+}
+
+impl Leaderboard {
+    fn fetch(&mut self, config: &LeaderboardConfig) {
+        let Some(endpoint) = endpoint(config) else {
+            return;
+        };
+        if self.fetch.is_some() {
+            return;
+        }
+        let agent = agent(config);
+        let url = format!("{}/top?limit={}", endpoint, TOP_COUNT);
+        self.fetch = Some(AsyncComputeTaskPool::get().spawn(async move {
+            agent
+                .get(&url)
+                .call()
+                .map_err(|err| err.to_string())?
+                .into_json::<Vec<LeaderboardEntry>>()
+                .map_err(|err| err.to_string())
+        }));
+    }
+
+    fn send_next(&mut self, config: &LeaderboardConfig) {
+        let Some(endpoint) = endpoint(config) else {
+            return;
+        };
+        if self.submit.is_some() {
+            return;
+        }
+        let Some(result) = self.pending.front().cloned() else {
+            return;
+        };
+        let agent = agent(config);
+        let url = format!("{}/results", endpoint);
+        self.submit = Some(AsyncComputeTaskPool::get().spawn(async move {
+            agent
+                .post(&url)
+                .send_json(&result)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        }));
+    }
+}
+
+// also retries whatever failed to post last time
+fn fetch_top_system(mut leaderboard: ResMut<Leaderboard>, config: Res<GameConfig>) {
+    leaderboard.fetch(&config.leaderboard);
+    leaderboard.send_next(&config.leaderboard);
+}
+
+fn submit_result_system(
+    mut leaderboard: ResMut<Leaderboard>,
+    config: Res<GameConfig>,
+    score: Res<Score>,
+    clock: Res<MatchClock>,
+    mode: Res<ActiveMode>,
+) {
+    if config.leaderboard.endpoint.is_none() {
+        return;
+    }
+    leaderboard.pending.push_back(MatchResultPost {
+        name: profile_name(),
+        mode: mode.name().to_string(),
+        home_goals: score.home,
+        away_goals: score.away,
+        duration: clock.elapsed,
+    });
+    leaderboard.send_next(&config.leaderboard);
+}
+
+fn leaderboard_task_system(mut leaderboard: ResMut<Leaderboard>, config: Res<GameConfig>) {
+    if let Some(task) = &mut leaderboard.fetch {
+        if let Some(result) = future::block_on(future::poll_once(task)) {
+            leaderboard.fetch = None;
+            match result {
+                Ok(top) => {
+                    leaderboard.top = top.into_iter().take(TOP_COUNT).collect();
+                    leaderboard.error = None;
+                }
+                Err(err) => {
+                    warn!("Leaderboard fetch failed: {}", err);
+                    leaderboard.error = Some(err);
+                }
+            }
+        }
+    }
+
+    if let Some(task) = &mut leaderboard.submit {
+        if let Some(result) = future::block_on(future::poll_once(task)) {
+            leaderboard.submit = None;
+            match result {
+                Ok(()) => {
+                    leaderboard.pending.pop_front();
+                    leaderboard.send_next(&config.leaderboard);
+                    // the posted match may have made the list
+                    leaderboard.fetch(&config.leaderboard);
+                }
+                // kept queued, the next menu visit or match tries again
+                Err(err) => warn!(
+                    "Leaderboard submit failed, {} result(s) queued: {}",
+                    leaderboard.pending.len(),
+                    err
+                ),
+            }
+        }
+    }
+}
+
+fn leaderboard_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    leaderboard: Res<Leaderboard>,
+    config: Res<GameConfig>,
+) {
+    if config.leaderboard.endpoint.is_none() {
+        return;
+    }
+    egui::Window::new("Leaderboard")
+        .anchor(egui::Align2::RIGHT_TOP, [-16.0, 40.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            if let Some(error) = &leaderboard.error {
+                ui.colored_label(egui::Color32::RED, error);
+            } else if leaderboard.top.is_empty() {
+                let status = if leaderboard.fetch.is_some() {
+                    "Loading..."
+                } else {
+                    "No results yet"
+                };
+                ui.label(status);
+            }
+            egui::Grid::new("leaderboard").show(ui, |ui| {
+                for (rank, entry) in leaderboard.top.iter().enumerate() {
+                    ui.label(format!("{}.", rank + 1));
+                    ui.label(&entry.name);
+                    ui.label(format!("{} – {}", entry.home_goals, entry.away_goals));
+                    ui.label(&entry.mode);
+                    ui.end_row();
+                }
+            });
+        });
+}
@@ -0,0 +1,493 @@
+use super::boost_meter::{
+    boost_thrust, collect_pickup, spawn_pickup, BoostMeter, BoostPickup, PickupAssets,
+    MAX_PICKUPS, PICKUP_INTERVAL,
+};
+use super::boost_pads::SpeedBoost;
+use super::cli::StartupArgs;
+use super::determinism::{physics_schedule, state_hash, TICK_DT};
+use super::game_state::GameState;
+use super::marks::{Ball, Player, Team};
+use super::nametags::NameTag;
+use super::input::PlayerInput;
+use super::mutators::Mutators;
+use super::player::{
+    dash_poke, reach_direction, step_movement, Dash, Facing, Jump, Stamina,
+};
+use super::progression::ShotKind;
+use super::{MatchAssets, BOARD_DIM};
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::net::{SocketAddr, UdpSocket};
+
+// local inputs are scheduled this many ticks ahead to hide latency
+const INPUT_DELAY: u32 = 3;
+const HASH_INTERVAL: u32 = 60;
+// nothing acknowledges a hash, so each one is resent until it is this old
+const HASH_KEEP: u32 = HASH_INTERVAL * 5;
+// where the peer's capsule starts, and goes back to after a goal
+pub const AWAY_SPAWN: Vec3 = Vec3::new(0.0, 1.1, BOARD_DIM.2 / 2.0 - 1.5);
+
+const PHYSICS_STAGE: &str = "lockstep_physics";
+
+const PACKET_INPUT: u8 = 0;
+const PACKET_HASH: u8 = 1;
+
+// one bit per held key, sent little endian
+pub type InputBits = u16;
+
+const INPUT_FORWARD: InputBits = 1 << 0;
+const INPUT_BACK: InputBits = 1 << 1;
+const INPUT_LEFT: InputBits = 1 << 2;
+const INPUT_RIGHT: InputBits = 1 << 3;
+const INPUT_KICK: InputBits = 1 << 4;
+const INPUT_SPRINT: InputBits = 1 << 5;
+const INPUT_DASH: InputBits = 1 << 6;
+const INPUT_JUMP: InputBits = 1 << 7;
+const INPUT_BOOST: InputBits = 1 << 8;
+
+const PICKUP_TICKS: u32 = (PICKUP_INTERVAL / TICK_DT) as u32;
+
+// usage: --lockstep <local addr> <peer addr> <home|away>
+pub struct LockstepConfig {
+    pub local: SocketAddr,
+    pub peer: SocketAddr,
+    pub team: Team,
+}
+
+impl LockstepConfig {
+    pub fn from_args(args: &StartupArgs) -> Option<Self> {
+        let [local, peer, side] = args.lockstep.as_deref()? else {
+            return None;
+        };
+        let team = match side.as_str() {
+            "away" => Team::Away,
+            _ => Team::Home,
+        };
+        Some(Self {
+            local: local.parse().ok()?,
+            peer: peer.parse().ok()?,
+            team,
+        })
+    }
+}
+
+pub struct LockstepPlugin(pub LockstepConfig);
+
+impl Plugin for LockstepPlugin {
+    fn build(&self, app: &mut App) {
+        let socket = UdpSocket::bind(self.0.local).expect("failed to bind lockstep socket");
+        socket
+            .set_nonblocking(true)
+            .expect("failed to make lockstep socket non-blocking");
+
+        app.insert_resource(Lockstep {
+            socket,
+            peer: self.0.peer,
+            local_team: self.0.team,
+            tick: 0,
+            inputs: HashMap::new(),
+            previous: HashMap::new(),
+            local_hashes: HashMap::new(),
+            remote_hashes: HashMap::new(),
+            desync_at: None,
+            stepped: false,
+        })
+        // rapier must step the exact same dt on both peers
+        .insert_resource(RapierConfiguration {
+            timestep_mode: TimestepMode::Fixed {
+                dt: TICK_DT,
+                substeps: 1,
+            },
+            ..default()
+        })
+        // rapier only steps on frames that advanced a tick, so a stall holds it
+        // without touching the pause menu's hold on physics
+        .add_stage_after(
+            CoreStage::Update,
+            PHYSICS_STAGE,
+            physics_schedule().with_run_criteria(lockstep_stepped),
+        )
+        .add_system_set_to_stage(
+            CoreStage::Last,
+            RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsStages::DetectDespawn),
+        )
+        .add_startup_system_to_stage(StartupStage::PostStartup, spawn_away_player)
+        .add_system(lockstep_send_system)
+        .add_system(lockstep_receive_system.after(lockstep_send_system))
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(lockstep_tick_system.after(lockstep_receive_system)),
+        )
+        .add_system(lockstep_hash_system.after(lockstep_tick_system))
+        .add_system(lockstep_ui_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct Lockstep {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    local_team: Team,
+    tick: u32,
+    // input bits per (tick, team), filled from both the keyboard and the peer
+    inputs: HashMap<(u32, Team), InputBits>,
+    previous: HashMap<Team, InputBits>,
+    local_hashes: HashMap<u32, u64>,
+    remote_hashes: HashMap<u32, u64>,
+    pub desync_at: Option<u32>,
+    // whether this frame advanced a tick, for the physics stage to step once
+    stepped: bool,
+}
+
+impl Lockstep {
+    fn remote_team(&self) -> Team {
+        match self.local_team {
+            Team::Home => Team::Away,
+            Team::Away => Team::Home,
+        }
+    }
+
+    fn send(&self, kind: u8, tick: u32, payload: &[u8]) {
+        let mut packet = Vec::with_capacity(5 + payload.len());
+        packet.push(kind);
+        packet.extend_from_slice(&tick.to_le_bytes());
+        packet.extend_from_slice(payload);
+        if let Err(err) = self.socket.send_to(&packet, self.peer) {
+            debug!("Lockstep send failed: {}", err);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct NetPlayer;
+
+// the regular player capsule is always home, the peer-driven one always away
+fn spawn_away_player(
+    mut commands: Commands,
+    match_assets: Res<MatchAssets>,
+    lockstep: Res<Lockstep>,
+) {
+    commands.spawn(away_player_bundle(&match_assets, lockstep.peer));
+}
+
+pub fn away_player_bundle(match_assets: &MatchAssets, peer: SocketAddr) -> impl Bundle {
+    (
+        PbrBundle {
+            mesh: match_assets.player_mesh.clone(),
+            material: match_assets.player_material.clone(),
+            transform: Transform::from_translation(AWAY_SPAWN),
+            ..default()
+        },
+        Collider::capsule_y(0.5, 0.5),
+        RigidBody::KinematicPositionBased,
+        Restitution::coefficient(1.5),
+        KinematicCharacterController {
+            autostep: None,
+            ..default()
+        },
+        Facing::toward_goal(Team::Away),
+        Stamina::default(),
+        Dash::default(),
+        Jump::default(),
+        BoostMeter::default(),
+        NetPlayer,
+        Team::Away,
+        NameTag(peer.ip().to_string()),
+        Name::new("away player"),
+    )
+}
+
+pub fn keyboard_bits(keyboard: &Input<KeyCode>) -> InputBits {
+    let mut bits = 0;
+    if keyboard.pressed(KeyCode::W) {
+        bits |= INPUT_FORWARD;
+    }
+    if keyboard.pressed(KeyCode::S) {
+        bits |= INPUT_BACK;
+    }
+    if keyboard.pressed(KeyCode::A) {
+        bits |= INPUT_LEFT;
+    }
+    if keyboard.pressed(KeyCode::D) {
+        bits |= INPUT_RIGHT;
+    }
+    if keyboard.pressed(KeyCode::Space) {
+        bits |= INPUT_KICK;
+    }
+    if keyboard.pressed(KeyCode::LShift) {
+        bits |= INPUT_SPRINT;
+    }
+    if keyboard.pressed(KeyCode::LControl) {
+        bits |= INPUT_DASH;
+    }
+    if keyboard.pressed(KeyCode::LAlt) {
+        bits |= INPUT_JUMP;
+    }
+    if keyboard.pressed(KeyCode::Tab) {
+        bits |= INPUT_BOOST;
+    }
+    bits
+}
+
+// the bits as the PlayerInput local play reads, with presses taken from last tick
+fn bits_input(bits: InputBits, previous: InputBits) -> PlayerInput {
+    let pressed = |bit| bits & bit != 0 && previous & bit == 0;
+    let mut input = PlayerInput::default();
+    if bits & INPUT_FORWARD != 0 {
+        input.movement.y += 1.0;
+    }
+    if bits & INPUT_BACK != 0 {
+        input.movement.y -= 1.0;
+    }
+    if bits & INPUT_LEFT != 0 {
+        input.movement.x -= 1.0;
+    }
+    if bits & INPUT_RIGHT != 0 {
+        input.movement.x += 1.0;
+    }
+    input.sprint = bits & INPUT_SPRINT != 0;
+    input.dash_pressed = pressed(INPUT_DASH);
+    input.jump_pressed = pressed(INPUT_JUMP);
+    input.boost = bits & INPUT_BOOST != 0;
+    input
+}
+
+// schedule this frame's local input for a future tick and tell the peer about it;
+// the last few scheduled inputs are resent every frame to cover packet loss
+fn lockstep_send_system(mut lockstep: ResMut<Lockstep>, keyboard: Res<Input<KeyCode>>) {
+    let target = lockstep.tick + INPUT_DELAY;
+    let team = lockstep.local_team;
+    lockstep
+        .inputs
+        .entry((target, team))
+        .or_insert_with(|| keyboard_bits(&keyboard));
+
+    for tick in lockstep.tick..=target {
+        if let Some(bits) = lockstep.inputs.get(&(tick, team)) {
+            lockstep.send(PACKET_INPUT, tick, &bits.to_le_bytes());
+        }
+    }
+}
+
+fn lockstep_receive_system(mut lockstep: ResMut<Lockstep>) {
+    let mut buf = [0u8; 64];
+    while let Ok((len, from)) = lockstep.socket.recv_from(&mut buf) {
+        if from != lockstep.peer || len < 5 {
+            continue;
+        }
+        let tick = u32::from_le_bytes([buf[1], buf[2], buf[3], buf[4]]);
+        match buf[0] {
+            PACKET_INPUT if len >= 7 => {
+                let team = lockstep.remote_team();
+                let bits = InputBits::from_le_bytes([buf[5], buf[6]]);
+                lockstep.inputs.insert((tick, team), bits);
+            }
+            PACKET_HASH if len >= 13 => {
+                let mut hash = [0u8; 8];
+                hash.copy_from_slice(&buf[5..13]);
+                lockstep
+                    .remote_hashes
+                    .insert(tick, u64::from_le_bytes(hash));
+            }
+            _ => {}
+        }
+    }
+}
+
+// advance exactly one tick when both inputs are known, otherwise hold still
+fn lockstep_tick_system(
+    mut commands: Commands,
+    mut lockstep: ResMut<Lockstep>,
+    rapier_config: Res<RapierConfiguration>,
+    mutators: Res<Mutators>,
+    pickup_assets: Res<PickupAssets>,
+    mut player_query: Query<PlayerStep, Or<(With<Player>, With<NetPlayer>)>>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+    pickup_query: Query<(Entity, &Transform), With<BoostPickup>>,
+) {
+    let tick = lockstep.tick;
+    let home = lockstep.inputs.get(&(tick, Team::Home)).copied();
+    let away = lockstep.inputs.get(&(tick, Team::Away)).copied();
+    let (Some(home), Some(away)) = (home, away) else {
+        for (_, _, mut ctrl, ..) in player_query.iter_mut() {
+            ctrl.translation = None;
+        }
+        return;
+    };
+
+    let previous = |team| lockstep.previous.get(&team).copied().unwrap_or_default();
+    let bits = [
+        (home, previous(Team::Home)),
+        (away, previous(Team::Away)),
+    ];
+    let scales = (rapier_config.gravity.y, mutators.speed_scale());
+    step_players(&mut commands, bits, scales, &mut player_query, &ball_query);
+    step_pickups(&mut commands, tick, &pickup_assets, &mut player_query, &pickup_query);
+
+    lockstep.previous.insert(Team::Home, home);
+    lockstep.previous.insert(Team::Away, away);
+    lockstep.inputs.retain(|(t, _), _| *t > tick);
+    lockstep.tick += 1;
+    lockstep.stepped = true;
+}
+
+fn lockstep_stepped(mut lockstep: ResMut<Lockstep>) -> ShouldRun {
+    if std::mem::take(&mut lockstep.stepped) {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+pub type PlayerStep = (
+    &'static Team,
+    &'static Transform,
+    &'static mut KinematicCharacterController,
+    Option<&'static KinematicCharacterControllerOutput>,
+    &'static mut Facing,
+    &'static mut Stamina,
+    &'static mut Dash,
+    &'static mut Jump,
+    Option<&'static SpeedBoost>,
+    &'static mut BoostMeter,
+);
+
+// one tick of both capsules from their input bits, as (this tick, last tick) for
+// home then away, through the same movement code as local play; shared with
+// rollback sessions so both modes play the same
+pub fn step_players(
+    commands: &mut Commands,
+    bits: [(InputBits, InputBits); 2],
+    (gravity, speed_scale): (f32, f32),
+    player_query: &mut Query<PlayerStep, Or<(With<Player>, With<NetPlayer>)>>,
+    ball_query: &Query<(Entity, &Transform), With<Ball>>,
+) {
+    for (
+        team,
+        tf,
+        mut ctrl,
+        output,
+        mut facing,
+        mut stamina,
+        mut dash,
+        mut jump,
+        boost,
+        mut meter,
+    ) in player_query.iter_mut()
+    {
+        let (bits, previous) = match team {
+            Team::Home => bits[0],
+            Team::Away => bits[1],
+        };
+        let grounded = output.map_or(true, |output| output.grounded);
+        let motion = step_movement(
+            &bits_input(bits, previous),
+            TICK_DT,
+            gravity,
+            boost.map_or(1.0, SpeedBoost::scale) * speed_scale,
+            grounded,
+            (&mut facing, &mut stamina, &mut dash, &mut jump),
+        );
+        let boosting = bits & INPUT_BOOST != 0;
+        let thrust = boost_thrust(boosting, TICK_DT, &facing, &mut meter);
+        ctrl.translation = Some(motion + thrust);
+        dash_poke(commands, tf, &mut dash, ball_query.iter());
+
+        // kicks fire on the tick the button goes down, with no random shot modifiers
+        if bits & INPUT_KICK != 0 && previous & INPUT_KICK == 0 {
+            for (ball_entity, ball_tf) in ball_query.iter() {
+                let reach = reach_direction(tf.translation, ball_tf.translation, dash.kick_range());
+                if let Some(direction) = reach {
+                    let shot = ShotKind::Standard.params();
+                    commands.entity(ball_entity).insert(ExternalImpulse {
+                        impulse: (direction + Vec3::Y * shot.loft).normalize() * shot.power,
+                        torque_impulse: Vec3::ZERO,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// pickups drop on fixed ticks at spots seeded by the tick, so both peers see the
+// same ones, and go to whoever reaches them first in the same query order
+fn step_pickups(
+    commands: &mut Commands,
+    tick: u32,
+    assets: &PickupAssets,
+    player_query: &mut Query<PlayerStep, Or<(With<Player>, With<NetPlayer>)>>,
+    pickup_query: &Query<(Entity, &Transform), With<BoostPickup>>,
+) {
+    if tick > 0 && tick % PICKUP_TICKS == 0 && pickup_query.iter().count() < MAX_PICKUPS {
+        spawn_pickup(commands, assets, &mut StdRng::seed_from_u64(tick as u64));
+    }
+    for (pickup_entity, pickup_tf) in pickup_query.iter() {
+        let collected = player_query
+            .iter_mut()
+            .any(|(_, tf, .., mut meter)| collect_pickup(pickup_tf, tf, &mut meter));
+        if collected {
+            commands.entity(pickup_entity).despawn_recursive();
+        }
+    }
+}
+
+// both peers hash the same ticks and compare, any difference means the sims diverged
+fn lockstep_hash_system(
+    mut lockstep: ResMut<Lockstep>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    team_query: Query<(&Team, Option<&Name>, &Transform), Without<Ball>>,
+    meter_query: Query<(&Team, &BoostMeter)>,
+) {
+    let tick = lockstep.tick;
+    if tick > 0 && tick % HASH_INTERVAL == 0 && !lockstep.local_hashes.contains_key(&tick) {
+        // boost left in the tank is as much a part of the state as where everyone is
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        hasher.write_u64(state_hash(ball_query.iter(), team_query.iter()));
+        let mut meters: Vec<_> = meter_query.iter().collect();
+        meters.sort_by_key(|(team, _)| **team as u8);
+        for (_, meter) in meters {
+            hasher.write_u32(meter.fuel.to_bits());
+        }
+        lockstep.local_hashes.insert(tick, hasher.finish());
+    }
+
+    // a lost datagram would skip that check, so kept hashes go out every frame
+    // like inputs do, and both sides compare whenever the peer's copy arrives
+    for (t, hash) in &lockstep.local_hashes {
+        lockstep.send(PACKET_HASH, *t, &hash.to_le_bytes());
+    }
+    let checked: Vec<u32> = lockstep
+        .local_hashes
+        .keys()
+        .filter(|t| lockstep.remote_hashes.contains_key(t))
+        .copied()
+        .collect();
+    for t in checked {
+        let local = lockstep.local_hashes.get(&t).copied();
+        let remote = lockstep.remote_hashes.remove(&t);
+        if local != remote && lockstep.desync_at.is_none() {
+            error!("Lockstep desync detected at tick {}", t);
+            lockstep.desync_at = Some(t);
+        }
+    }
+    let keep = |t: &u32| *t + HASH_KEEP > tick;
+    lockstep.local_hashes.retain(|t, _| keep(t));
+    lockstep.remote_hashes.retain(|t, _| keep(t));
+}
+
+fn lockstep_ui_system(mut egui_context: ResMut<EguiContext>, lockstep: Res<Lockstep>) {
+    egui::Area::new("lockstep")
+        .anchor(egui::Align2::LEFT_TOP, [16.0, 60.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("Lockstep tick {}", lockstep.tick));
+            if let Some(tick) = lockstep.desync_at {
+                ui.colored_label(egui::Color32::RED, format!("Desync at tick {}", tick));
+            }
+        });
+}
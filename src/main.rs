@@ -1,11 +1,24 @@
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
 use bevy_rapier3d::prelude::*;
-use marks::{Ball, EnemyGoal, GameCamera, Player, ScoreText};
+use clap::Parser;
+use clock::MatchClock;
+use config::GameConfig;
+use game_state::GameState;
+use kickoff::Kickoff;
+use marks::{Ball, GameCamera, MatchEntity, Player, PlayerIndex, Team};
+use mode::{ActiveMode, ModePlugin, ModeRegistry};
+use mutators::Mutators;
+use rules::{GoalContext, LastTouch};
+use serde::{Deserialize, Serialize};
 use simula_viz::{
     grid::{Grid, GridBundle, GridPlugin},
     lines::{LineMesh, LinesMaterial, LinesPlugin},
 };
+use squash::{BallDeform, BallMesh, SquashStretch};
+use std::collections::HashMap;
+use std::process::ExitCode;
+use worldtext::WorldText;
 
 #[cfg(feature = "develop")]
 use bevy_inspector_egui::WorldInspectorPlugin;
@@ -14,39 +27,299 @@ use simula_action::ActionPlugin;
 #[cfg(feature = "develop")]
 use simula_camera::orbitcam::*;
 
+mod arena;
+mod arena_events;
+mod arena_validation;
+mod bench;
+mod bindings;
+mod boost_meter;
+mod boost_pads;
+mod boss;
+mod broadcast;
+#[cfg(feature = "develop")]
+mod cheats;
+mod cli;
+mod clock;
+mod config;
+mod dataset;
+#[cfg(feature = "develop")]
+mod debug_window;
+mod determinism;
+mod devices;
+mod difficulty;
+mod drills;
+#[cfg(feature = "develop")]
+mod editor;
+#[cfg(not(feature = "develop"))]
+mod follow_camera;
+mod game_state;
+mod goalkeeper;
+mod headless;
+mod heatmap;
+mod history;
+mod hud;
+mod input;
+mod instancing;
+mod instant_replay;
+mod kickoff;
+#[cfg(feature = "leaderboard")]
+mod leaderboard;
+mod lockstep;
+mod markings;
 mod marks;
+mod match_save;
+mod match_stats;
+mod menu;
+mod minimap;
+mod mode;
+mod mods;
+mod mutators;
+mod nametags;
+mod net;
+mod opponent;
+mod out_of_bounds;
+mod overlay;
+mod perf_overlay;
 mod player;
+mod power;
+mod proc_arena;
+mod progression;
+mod rematch;
+mod replay;
+mod replay_browser;
+mod rules;
+mod scripting;
+mod series;
+mod settings;
+mod sky;
+mod spin;
+mod splitscreen;
+mod squash;
+mod stadium;
+mod storage;
+mod survival;
+mod tape;
+mod trail;
+#[cfg(feature = "twitch")]
+mod twitch;
+#[cfg(feature = "develop")]
+mod undo;
+mod versus;
+mod vfx;
+mod warmup;
+#[cfg(feature = "wasm-mods")]
+mod wasm_mods;
+mod worldtext;
 
 const BOARD_DIM: (f32, f32, f32) = (10.0, 0.1, 20.0);
 const GOAL_GAP: f32 = 2.0;
+pub const PLAYER_SPAWN: Vec3 = Vec3::new(0.0, 1.1, -(BOARD_DIM.2 / 2.0) + 0.5);
+// long enough to watch the ball settle in the net
+const GOAL_RESET_DELAY: f32 = 1.5;
+
+fn main() -> ExitCode {
+    let args = cli::StartupArgs::parse();
+    // one-off tools that report and exit without starting the game
+    if let Some(path) = &args.validate_arena {
+        return arena::validate_file(path);
+    }
+    if let Some(path) = &args.export_history {
+        return history::export(path);
+    }
+    let modes = ModeRegistry::default();
+    let mode = modes.from_args(&args);
+    if args.headless {
+        headless::run(mode, args);
+        return ExitCode::SUCCESS;
+    }
 
-fn main() {
     let mut app = App::new();
+    app.insert_resource(args.clone());
+
+    // a rollback session steps rapier itself, inside the ggrs schedule, and a
+    // lockstep one once for every tick both sides have inputs for
+    #[cfg(not(feature = "develop"))]
+    let (net_config, lockstep_config) = (
+        net::NetConfig::from_args(&args),
+        lockstep::LockstepConfig::from_args(&args),
+    );
+    #[cfg(feature = "develop")]
+    let (net_config, lockstep_config): (Option<net::NetConfig>, Option<lockstep::LockstepConfig>) =
+        (None, None);
+    // so does a deterministic match, from a fixed-rate schedule of its own
+    let fixed_schedule = determinism::deterministic(&args) && !net::networked(&args);
+
+    // develop builds pick up edited assets, arenas included, without a restart
+    app.add_plugins(
+        DefaultPlugins
+            .set(AssetPlugin {
+                watch_for_changes: cfg!(feature = "develop"),
+                ..default()
+            })
+            .set(WindowPlugin {
+                window: args.window(),
+                ..default()
+            }),
+    )
+    .add_plugin(EguiPlugin)
+    .add_plugin(
+        RapierPhysicsPlugin::<NoUserData>::default()
+            .with_default_system_setup(
+                net_config.is_none() && lockstep_config.is_none() && !fixed_schedule,
+            ),
+    )
+    .add_plugin(determinism::DeterminismPlugin { fixed_schedule })
+    .add_plugin(GridPlugin)
+    .add_plugin(LinesPlugin)
+    .init_resource::<MatchEntities>()
+    .add_plugin(game_state::GameStatePlugin)
+    .add_plugin(menu::MenuPlugin)
+    .add_plugin(mutators::MutatorsPlugin)
+    .add_plugin(arena::ArenaPlugin)
+    .add_plugin(boost_pads::BoostPadPlugin)
+    .add_plugin(boost_meter::BoostMeterPlugin)
+    .add_plugin(proc_arena::ProcArenaPlugin)
+    .add_plugin(markings::MarkingsPlugin)
+    .add_plugin(sky::SkyPlugin)
+    .add_plugin(stadium::StadiumPlugin)
+    .add_plugin(vfx::VfxPlugin)
+    .add_plugin(trail::TrailPlugin)
+    .add_plugin(spin::SpinPlugin)
+    .add_plugin(out_of_bounds::OutOfBoundsPlugin)
+    .add_plugin(minimap::MinimapPlugin)
+    .add_plugin(instancing::InstancingPlugin)
+    .add_plugin(rematch::RematchPlugin)
+    .add_plugin(match_save::MatchSavePlugin)
+    .add_plugin(match_stats::MatchStatsPlugin)
+    .add_plugin(kickoff::KickoffPlugin)
+    .add_plugin(difficulty::DifficultyPlugin)
+    .add_plugin(dataset::DatasetPlugin)
+    .add_plugin(tape::TapePlugin)
+    .add_plugin(power::PowerPlugin)
+    .add_plugin(settings::SettingsPlugin)
+    .add_plugin(config::ConfigPlugin)
+    .add_plugin(squash::SquashPlugin)
+    .add_plugin(bindings::BindingsPlugin)
+    .add_plugin(devices::DevicesPlugin)
+    .add_plugin(input::ControlsPlugin)
+    .add_plugin(broadcast::BroadcastPlugin)
+    .add_plugin(arena_events::ArenaEventsPlugin)
+    .add_plugin(overlay::OverlayPlugin)
+    .add_plugin(perf_overlay::PerfOverlayPlugin)
+    .add_plugin(clock::ClockPlugin)
+    .add_plugin(hud::HudPlugin)
+    .add_plugin(splitscreen::SplitScreenPlugin)
+    .add_plugin(worldtext::WorldTextPlugin)
+    .add_plugin(nametags::NameTagPlugin)
+    .add_plugin(net::NetPlugin)
+    .add_plugin(replay::ReplayPlugin)
+    .add_plugin(instant_replay::InstantReplayPlugin)
+    .add_plugin(replay_browser::ReplayBrowserPlugin)
+    .add_plugin(heatmap::HeatmapPlugin)
+    .add_plugin(history::HistoryPlugin)
+    .add_plugin(mods::ModPlugin)
+    .add_plugin(scripting::ScriptingPlugin)
+    .add_plugin(rules::RulesPlugin)
+    .add_plugin(warmup::WarmupPlugin);
+
+    #[cfg(feature = "twitch")]
+    app.add_plugin(twitch::TwitchPlugin);
+
+    #[cfg(feature = "wasm-mods")]
+    app.add_plugin(wasm_mods::WasmModsPlugin);
+
+    #[cfg(feature = "leaderboard")]
+    app.add_plugin(leaderboard::LeaderboardPlugin);
 
-    app.add_plugins(DefaultPlugins)
-        .add_plugin(EguiPlugin)
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
-        .add_plugin(GridPlugin)
-        .add_plugin(LinesPlugin);
+    if let Some(bench) = bench::BenchPlugin::from_args(&args) {
+        app.add_plugin(bench);
+    }
+
+    app.add_plugin(ModePlugin {
+        registry: modes,
+        mode,
+    });
 
     #[cfg(feature = "develop")]
     app.add_plugin(ActionPlugin)
         .add_plugin(OrbitCameraPlugin)
         .add_plugin(RapierDebugRenderPlugin::default())
-        .add_plugin(WorldInspectorPlugin::default());
+        .add_plugin(WorldInspectorPlugin::default())
+        .add_plugin(debug_window::DebugWindowPlugin)
+        .add_plugin(cheats::CheatsPlugin)
+        .add_plugin(editor::EditorPlugin)
+        .add_plugin(undo::UndoPlugin);
 
     #[cfg(not(feature = "develop"))]
-    app.add_plugin(player::PlayerPlugin);
+    app.add_plugin(progression::ProgressionPlugin)
+        .add_plugin(follow_camera::FollowCameraPlugin);
+
+    // a rollback session decides goals inside its own schedule, see net.rs
+    let rollback = net::rollback(&args);
+
+    // online sessions drive both capsules from exchanged inputs instead of the local
+    // keyboard, rolling back on late inputs or waiting for them in lockstep
+    #[cfg(not(feature = "develop"))]
+    match (net_config, lockstep_config) {
+        (Some(config), _) => app.add_plugin(net::RollbackPlugin(config)),
+        (None, Some(config)) => app.add_plugin(lockstep::LockstepPlugin(config)),
+        (None, None) => app.add_plugin(player::PlayerPlugin),
+    };
 
     app.add_startup_system(setup_system)
-        .add_startup_system(setup_physics)
-        .add_system(goal_system)
-        .run();
+        .add_startup_system(setup_match)
+        .add_event::<GoalScoredEvent>()
+        .init_resource::<GoalReset>()
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(goal_popup_system.after(goal_system)),
+        );
+    if !rollback {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(goal_system)
+                .with_system(goal_score_system.after(goal_system))
+                .with_system(goal_reset_system.after(goal_system)),
+        );
+    }
+
+    // a played tape that drifted fails the process once the app has shut down
+    let tape_verdict = tape::TapeVerdict::default();
+    app.insert_resource(tape_verdict.clone());
+    app.run();
+    if tape_verdict.drifted() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
 }
 
-#[derive(Resource)]
+// rolled back with the rest of the match in rollback sessions, see net.rs
+#[derive(Resource, Default, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
 struct Score {
-    goals: u32,
+    home: u32,
+    away: u32,
+}
+
+impl Score {
+    pub fn of(&self, team: Team) -> u32 {
+        match team {
+            Team::Home => self.home,
+            Team::Away => self.away,
+        }
+    }
+
+    pub fn add(&mut self, team: Team, points: u32) {
+        match team {
+            Team::Home => self.home += points,
+            Team::Away => self.away += points,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.home + self.away
+    }
 }
 
 fn setup_system(
@@ -55,11 +328,7 @@ fn setup_system(
     mut lines_materials: ResMut<Assets<LinesMaterial>>,
     line_mesh: Res<LineMesh>,
 ) {
-    // lights
-    commands.insert_resource(AmbientLight {
-        color: Color::rgb(0.9, 1.0, 1.0),
-        brightness: 0.14,
-    });
+    // lights, the ambient term comes with the arena's sky
     commands.spawn(PointLightBundle {
         point_light: PointLight {
             intensity: 6000.,
@@ -79,10 +348,12 @@ fn setup_system(
         },
         #[cfg(feature = "develop")]
         OrbitCamera {
-            pan_sensitivity: 40.0,
+            pan_sensitivity: settings::ORBIT_PAN_SENSITIVITY,
             center: Vec3::ZERO,
             ..Default::default()
         },
+        #[cfg(not(feature = "develop"))]
+        follow_camera::FollowCamera::default(),
         GameCamera,
     ));
 
@@ -105,241 +376,384 @@ fn setup_system(
     ));
 
     // score resource
-    commands.insert_resource(Score { goals: 0 });
+    commands.insert_resource(Score::default());
+}
+
+#[derive(Resource, Clone)]
+pub struct MatchAssets {
+    ball_mesh: Handle<Mesh>,
+    ball_material: Handle<StandardMaterial>,
+    pub player_mesh: Handle<Mesh>,
+    player_material: Handle<StandardMaterial>,
+}
+
+// handles to the entities gameplay systems look up every frame, kept current
+// by whatever spawns them so nobody has to query for a single entity
+#[derive(Resource, Default)]
+pub struct MatchEntities {
+    // the first of `balls`, for whatever only follows one of them
+    pub ball: Option<Entity>,
+    // every ball in play, more than one with the multiball mutator
+    pub balls: Vec<Entity>,
+    pub player: Option<Entity>,
+    pub goal: Option<Entity>,
+    pub home_goal: Option<Entity>,
 }
 
-fn setup_physics(
+// ball and player wait for the menu's Play, only their assets are made up front
+fn setup_match(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     asset_server: Res<AssetServer>,
 ) {
-    // create a static floor
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(
-                BOARD_DIM.0,
-                BOARD_DIM.1,
-                BOARD_DIM.2,
-            ))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::BLACK.into(),
-                ..default()
-            }),
+    let ball_texture = asset_server.load("textures/ball/ball.png");
+    let match_assets = MatchAssets {
+        ball_mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 0.5,
             ..default()
-        },
-        Collider::cuboid(BOARD_DIM.0 / 2.0, BOARD_DIM.1 / 2.0, BOARD_DIM.2 / 2.0),
-        RigidBody::Fixed,
-        Name::new("floor"),
-    ));
+        })),
+        ball_material: materials.add(StandardMaterial {
+            base_color_texture: Some(ball_texture),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        player_mesh: meshes.add(Mesh::from(shape::Capsule::default())),
+        player_material: materials.add(StandardMaterial {
+            base_color: Color::FUCHSIA,
+            ..default()
+        }),
+    };
 
-    // create a bouncing ball
-    let ball_texture = asset_server.load("textures/ball/ball.png");
+    commands.insert_resource(match_assets);
+}
 
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::UVSphere {
-                radius: 0.5,
-                ..default()
-            })),
-            material: materials.add(StandardMaterial {
-                base_color_texture: Some(ball_texture),
-                alpha_mode: AlphaMode::Blend,
-                unlit: true,
-                ..default()
-            }),
-            transform: Transform::from_xyz(0.0, 4.0, 0.0),
-            ..default()
-        },
-        Collider::ball(0.5),
-        Restitution::coefficient(1.0),
-        RigidBody::Dynamic,
-        Damping {
-            angular_damping: 1.0,
-            linear_damping: 0.5,
-        },
-        ActiveCollisionTypes::default() | ActiveCollisionTypes::DYNAMIC_KINEMATIC,
-        ActiveEvents::COLLISION_EVENTS,
-        Ball,
-        Name::new("ball"),
-    ));
+// ball and players are the only entities rebuilt on a rematch
+pub fn spawn_match_entities(
+    commands: &mut Commands,
+    match_assets: &MatchAssets,
+    entities: &mut MatchEntities,
+    mutators: &Mutators,
+) {
+    entities.balls = (0..mutators.balls.max(1))
+        .map(|index| spawn_ball(commands, match_assets, mutators, index))
+        .collect();
 
     // spawn a player capsule
-    commands.spawn((
+    let player = commands.spawn((
         PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Capsule::default())),
-            material: materials.add(StandardMaterial {
-                base_color: Color::FUCHSIA,
-                ..default()
-            }),
-            transform: Transform::from_xyz(0.0, 1.1, -(BOARD_DIM.2 / 2.0) + 0.5),
+            mesh: match_assets.player_mesh.clone(),
+            material: match_assets.player_material.clone(),
+            transform: Transform::from_translation(PLAYER_SPAWN),
             ..default()
         },
         Collider::capsule_y(0.5, 0.5),
         RigidBody::KinematicPositionBased,
-        LockedAxes::TRANSLATION_LOCKED_Y,
         Restitution::coefficient(1.5),
         KinematicCharacterController {
             autostep: None,
             ..default()
         },
         Player,
+        PlayerIndex(0),
+        player::Facing::default(),
+        player::KickCharge::default(),
+        player::Stamina::default(),
+        player::Dash::default(),
+        player::Jump::default(),
+        boost_meter::BoostMeter::default(),
+        Team::Home,
+        MatchEntity,
         Name::new("player"),
     ));
+    entities.ball = entities.balls.first().copied();
+    entities.player = Some(player.id());
+}
 
-    // spawn a goal box
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(1.0, 1.0, 1.0))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::RED,
-                ..default()
-            }),
-            transform: Transform::from_xyz(-GOAL_GAP, 0.6, (BOARD_DIM.2 / 2.0) - 0.5),
-            ..default()
-        },
-        RigidBody::Fixed,
-        Collider::cuboid(0.5, 0.5, 0.5),
-        Name::new("EnemyGoalRight"),
-    ));
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(1.0, 1.0, 1.0))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::RED,
-                ..default()
-            }),
-            transform: Transform::from_xyz(GOAL_GAP, 0.6, (BOARD_DIM.2 / 2.0) - 0.5),
-            ..default()
-        },
-        RigidBody::Fixed,
-        Collider::cuboid(0.5, 0.5, 0.5),
-        Name::new("EnemyGoalLeft"),
-    ));
-
-    // spawn a left side wall
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(1., 2.0, BOARD_DIM.2))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::RED,
-                ..default()
-            }),
-            transform: Transform::from_xyz((BOARD_DIM.0 / 2.0) + 0.5, 1.0, 0.),
-            ..default()
-        },
-        RigidBody::Fixed,
-        Collider::cuboid(0.5, 1.0, BOARD_DIM.2 / 2.0),
-        Name::new("LeftSideWall"),
-    ));
-
-    // spawn a right side wall
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(1., 2.0, BOARD_DIM.2))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::RED,
-                ..default()
-            }),
-            transform: Transform::from_xyz(-(BOARD_DIM.0 / 2.0) - 0.5, 1.0, 0.),
-            ..default()
-        },
-        RigidBody::Fixed,
-        Collider::cuboid(0.5, 1.0, BOARD_DIM.2 / 2.0),
-        Name::new("RightSideWall"),
-    ));
-
-    // spawn a back wall
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(BOARD_DIM.0, 2.0, 0.2))),
-            material: materials.add(StandardMaterial {
-                base_color: Color::RED,
-                ..default()
-            }),
-            transform: Transform::from_xyz(0., 1.0, -(BOARD_DIM.2 / 2.0) - 0.2),
-            visibility: Visibility { is_visible: false },
-            ..default()
-        },
-        RigidBody::Fixed,
-        Collider::cuboid(BOARD_DIM.0 / 2.0, 1.0, 0.2),
-        Name::new("BackWall"),
-    ));
-
-    // spawn a front wall
+// every ball is the same, the kickoff spreads them out once they exist
+fn spawn_ball(
+    commands: &mut Commands,
+    match_assets: &MatchAssets,
+    mutators: &Mutators,
+    index: usize,
+) -> Entity {
+    // replays and saves match bodies by name, so extra balls need their own
+    let name = match index {
+        0 => "ball".to_string(),
+        _ => format!("ball {}", index + 1),
+    };
+    let scale = mutators.ball_scale();
+    // create a bouncing ball, the mesh lives two levels down so visual
+    // deformation never scales the collider
     commands
         .spawn((
-            PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Box::new(BOARD_DIM.0, 2.0, 0.2))),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::RED,
-                    ..default()
-                }),
-                transform: Transform::from_xyz(0., 1.0, (BOARD_DIM.2 / 2.0) + 0.2),
-                visibility: Visibility { is_visible: false },
+            SpatialBundle {
+                transform: Transform::from_xyz(0.0, 4.0, 0.0),
                 ..default()
             },
-            RigidBody::Fixed,
-            Collider::cuboid(BOARD_DIM.0 / 2.0, 1.0, 0.2),
-            Name::new("FrontWall"),
+            Collider::ball(0.5 * scale),
+            // tuned from the config once spawned
+            Restitution::coefficient(1.0),
+            RigidBody::Dynamic,
+            Velocity::default(),
+            // the magnus force from its spin, see spin.rs
+            ExternalForce::default(),
+            Ccd::enabled(),
+            Damping {
+                angular_damping: 1.0,
+                linear_damping: mutators.ball_damping(0.5),
+            },
+            ActiveCollisionTypes::default() | ActiveCollisionTypes::DYNAMIC_KINEMATIC,
+            ActiveEvents::COLLISION_EVENTS,
+            Ball,
+            SquashStretch::default(),
+            MatchEntity,
+            Name::new(name),
         ))
         .with_children(|parent| {
-            parent.spawn((
-                Collider::cuboid(GOAL_GAP - 0.5, 1.0, 0.1),
-                Sensor,
-                ActiveCollisionTypes::default() | ActiveCollisionTypes::DYNAMIC_STATIC,
-                ActiveEvents::COLLISION_EVENTS,
-                Transform::from_xyz(0.0, 0.1, -1.1),
-                EnemyGoal,
-                Name::new("GoalCollider"),
-            ));
-        });
-
-    // spawn a text2dbundle
-    commands.spawn((
-        TextBundle::from_section(
-            "Score: 0",
-            TextStyle {
-                font: asset_server.load("fonts/RubikSprayPaint-Regular.ttf"),
-                font_size: 40.0,
-                color: Color::WHITE,
-            },
-        )
-        .with_text_alignment(TextAlignment::TOP_RIGHT),
-        ScoreText,
-        Name::new("ScoreText"),
-    ));
+            parent
+                .spawn((SpatialBundle::default(), BallDeform))
+                .with_children(|parent| {
+                    parent.spawn((
+                        PbrBundle {
+                            mesh: match_assets.ball_mesh.clone(),
+                            material: match_assets.ball_material.clone(),
+                            // the squash only ever turns this one, so the size stays put
+                            transform: Transform::from_scale(Vec3::splat(scale)),
+                            ..default()
+                        },
+                        BallMesh,
+                        Name::new("ball mesh"),
+                    ));
+                });
+        })
+        .id()
+}
+
+// the reset waiting out GOAL_RESET_DELAY, no goals count until it's done
+#[derive(Resource, Default)]
+pub struct GoalReset {
+    pending: Option<(Team, Timer)>,
+}
+
+impl GoalReset {
+    pub fn pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+// a goal that stood, after the rules and scripts had their say
+pub struct GoalScoredEvent {
+    // whoever touched the ball last, own goals included
+    pub team: Team,
+    // the side whose goal was breached, the other side gets the points
+    pub against: Team,
+    pub points: u32,
+    // which ball went in, there can be several with the multiball mutator
+    pub ball: Entity,
+    pub position: Vec3,
+    pub ball_velocity: Vec3,
 }
 
+// only decides whether a goal happened, the consumers below act on it
 fn goal_system(
-    mut score_query: Query<&mut Text, With<ScoreText>>,
     mut collision_events: EventReader<CollisionEvent>,
-    mut ball_query: Query<(Entity, &mut Transform), With<Ball>>,
-    mut score: ResMut<Score>,
-    enemy_goal_query: Query<Entity, With<EnemyGoal>>,
-    player_query: Query<Entity, With<Player>>,
+    mut goal_events: EventWriter<GoalScoredEvent>,
+    ball_query: Query<(&Transform, &Velocity, &Collider), With<Ball>>,
+    score: Res<Score>,
+    mut last_ball_pos: Local<HashMap<Entity, Vec3>>,
+    rapier_context: Res<RapierContext>,
+    entities: Res<MatchEntities>,
+    goal_query: Query<&GlobalTransform>,
+    scripts: Option<Res<scripting::Scripts>>,
+    config: Res<GameConfig>,
+    mode: Res<ActiveMode>,
+    touch: Res<LastTouch>,
+    clock: Res<MatchClock>,
+    goal_reset: Res<GoalReset>,
 ) {
-    let enemy_entity = enemy_goal_query.get_single().unwrap();
-    let player_entity = player_query.get_single().unwrap();
-
-    let (ball_entity, mut ball_tf) = ball_query.get_single_mut().unwrap();
-    let mut score_text = score_query.get_single_mut().unwrap();
-
-    for ev in collision_events.iter() {
-        if let CollisionEvent::Stopped(a, b, _) = ev {
-            if a == &enemy_entity || b == &enemy_entity {
-                ball_tf.translation = Vec3::new(0.0, 4.0, 0.0);
-                score.goals += 1;
-                score_text.sections[0].value = format!("Score: {}", score.goals);
+    // the ball is still in the net from the last one
+    if goal_reset.pending() {
+        collision_events.clear();
+        last_ball_pos.clear();
+        return;
+    }
+    let stopped: Vec<(Entity, Entity)> = collision_events
+        .iter()
+        .filter_map(|ev| match ev {
+            CollisionEvent::Stopped(a, b, _) => Some((*a, *b)),
+            _ => None,
+        })
+        .collect();
+
+    // between a despawn and the respawn (rematch, arena rebuild) there is nothing to score
+    let mut positions = HashMap::new();
+    for &ball_entity in &entities.balls {
+        let Ok((ball_tf, ball_vel, ball_collider)) = ball_query.get(ball_entity) else {
+            continue;
+        };
+        positions.insert(ball_entity, ball_tf.translation);
+        let prev = last_ball_pos.get(&ball_entity).copied();
+
+        // away defends the far goal at +z, home the near one
+        for (goal, against) in [
+            (entities.goal, Team::Away),
+            (entities.home_goal, Team::Home),
+        ] {
+            let Some(goal_entity) = goal else {
+                continue;
+            };
+            let Ok(goal_tf) = goal_query.get(goal_entity) else {
+                continue;
+            };
+            let mut goals = 0;
+
+            // even with ccd a max power shot can clear the thin sensor between two
+            // steps without ever overlapping it, so sweep the ball along its travel
+            let goal_z = goal_tf.translation().z;
+            let past_line = |z: f32| match against {
+                Team::Away => z >= goal_z,
+                Team::Home => z <= goal_z,
+            };
+            if let Some(prev) = prev {
+                let travel = ball_tf.translation - prev;
+                let crossed = !past_line(prev.z) && past_line(ball_tf.translation.z);
+                if crossed
+                    && rapier_context.intersection_pair(ball_entity, goal_entity) != Some(true)
+                {
+                    let only_goal = |e: Entity| e == goal_entity;
+                    let hit = rapier_context.cast_shape(
+                        prev,
+                        Quat::IDENTITY,
+                        travel,
+                        ball_collider,
+                        1.0,
+                        QueryFilter::new().predicate(&only_goal),
+                    );
+                    if hit.is_some() {
+                        goals += 1;
+                    }
+                }
+            }
+
+            // only this ball leaving the sensor counts for it
+            goals += stopped
+                .iter()
+                .filter(|pair| {
+                    *pair == (ball_entity, goal_entity) || *pair == (goal_entity, ball_entity)
+                })
+                .count() as u32;
+
+            // the mode's rules decide whether the goal stands and what it is worth
+            let rules = config.rules_for(mode.name());
+            let ball_touch = touch.of(ball_entity);
+            let ctx = GoalContext {
+                scorer: ball_touch.team.unwrap_or(against.other()),
+                goal_position: goal_tf.translation(),
+                touch: ball_touch,
+                clock: &clock,
+                score: &score,
+            };
+            if goals > 0 && !rules.allows(&ctx) {
+                info!("Goal disallowed for {}", ctx.scorer.name());
+                goals = 0;
+            }
+            let points = goals * rules.points(&ctx);
+
+            // scripts get a say before the goal stands
+            if goals > 0 {
+                if let Some(scripts) = &scripts {
+                    let (home, away) = match against {
+                        Team::Away => (score.home + points, score.away),
+                        Team::Home => (score.home, score.away + points),
+                    };
+                    if !scripts.pre_goal(home, away) {
+                        goals = 0;
+                    }
+                }
+            }
+
+            if goals > 0 {
+                goal_events.send(GoalScoredEvent {
+                    team: ctx.scorer,
+                    against,
+                    points,
+                    ball: ball_entity,
+                    position: ball_tf.translation,
+                    ball_velocity: ball_vel.linvel,
+                });
             }
         }
+    }
+    *last_ball_pos = positions;
+}
 
-        if let CollisionEvent::Started(a, b, _) = ev {
-            if (a == &player_entity && b == &ball_entity)
-                || (a == &ball_entity && b == &player_entity)
-            {
-                warn!("Collision detected ball ppll");
+fn goal_score_system(mut goal_events: EventReader<GoalScoredEvent>, mut score: ResMut<Score>) {
+    // the points go to whoever attacks the breached goal, own goals included
+    for ev in goal_events.iter() {
+        score.add(ev.against.other(), ev.points);
+    }
+}
+
+fn goal_popup_system(mut commands: Commands, mut goal_events: EventReader<GoalScoredEvent>) {
+    for ev in goal_events.iter() {
+        let label = if ev.points > 1 {
+            format!("GOAL! +{}", ev.points)
+        } else {
+            "GOAL!".to_string()
+        };
+        worldtext::spawn_popup(
+            &mut commands,
+            ev.position,
+            WorldText::new(label)
+                .with_size(worldtext::POPUP_SIZE)
+                .with_color(Color::YELLOW),
+            1.5,
+        );
+    }
+}
+
+// puts the ball, players and kickoff back as far as the mode's rules ask, once
+// the ball has had a moment in the net
+fn goal_reset_system(
+    mut goal_events: EventReader<GoalScoredEvent>,
+    mut goal_reset: ResMut<GoalReset>,
+    mut kickoff: ResMut<Kickoff>,
+    mut transforms: Query<&mut Transform>,
+    entities: Res<MatchEntities>,
+    config: Res<GameConfig>,
+    mode: Res<ActiveMode>,
+    time: Res<Time>,
+) {
+    // the side that conceded kicks off
+    if let Some(against) = goal_events.iter().last().map(|ev| ev.against) {
+        let timer = Timer::from_seconds(GOAL_RESET_DELAY, TimerMode::Once);
+        goal_reset.pending = Some((against, timer));
+    }
+    let Some((against, timer)) = &mut goal_reset.pending else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+    let against = *against;
+    goal_reset.pending = None;
+
+    let rules = config.rules_for(mode.name());
+    if rules.on_goal.kickoff {
+        kickoff.restart(against);
+    }
+    if rules.on_goal.ball {
+        for (index, ball) in entities.balls.iter().enumerate() {
+            if let Ok(mut ball_tf) = transforms.get_mut(*ball) {
+                ball_tf.translation = kickoff.ball_spot_at(index);
             }
         }
     }
+    if rules.on_goal.players {
+        if let Some(Ok(mut player_tf)) = entities.player.map(|player| transforms.get_mut(player)) {
+            player_tf.translation = PLAYER_SPAWN;
+        }
+    }
 }
@@ -0,0 +1,126 @@
+use super::arena::{Arena, ArenaPieceId, PieceKind};
+use super::{BOARD_DIM, GOAL_GAP};
+use bevy::prelude::*;
+use simula_viz::lines::{LineMesh, Lines, LinesBundle, LinesMaterial};
+
+const MARKING_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+// just above the floor's top face, in the floor's own space
+const MARKING_LIFT: f32 = 0.01;
+const CENTER_CIRCLE_RADIUS: f32 = 1.5;
+const CIRCLE_SEGMENTS: usize = 32;
+// the box reaches this far past each post and this far up the pitch
+const BOX_MARGIN: f32 = 1.5;
+const BOX_DEPTH: f32 = 3.0;
+
+pub struct MarkingsPlugin;
+
+impl Plugin for MarkingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_markings_system)
+            .add_system(draw_markings_system);
+    }
+}
+
+// pitch lines, parented to the floor so they follow it when it is edited
+#[derive(Component)]
+struct PitchMarkings {
+    // across the pitch, where each goal's gap is centred
+    goal_x: f32,
+    home_goal_x: f32,
+}
+
+fn spawn_markings_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut lines_materials: ResMut<Assets<LinesMaterial>>,
+    line_mesh: Res<LineMesh>,
+    arena: Res<Arena>,
+    piece_query: Query<(Entity, &ArenaPieceId, Option<&Children>)>,
+    markings_query: Query<(), With<PitchMarkings>>,
+) {
+    let sensor_x = |kind: PieceKind| {
+        arena
+            .descriptor
+            .pieces
+            .iter()
+            .find(|piece| piece.kind == kind)
+            .map(|piece| piece.position[0])
+            .unwrap_or_default()
+    };
+    let goal_x = sensor_x(PieceKind::GoalSensor);
+    let home_goal_x = sensor_x(PieceKind::HomeGoalSensor);
+
+    for (entity, ArenaPieceId(id), children) in piece_query.iter() {
+        let Some(piece) = arena.descriptor.pieces.get(*id) else {
+            continue;
+        };
+        if piece.kind != PieceKind::Floor {
+            continue;
+        }
+        let marked = children
+            .map(|children| children.iter().any(|child| markings_query.contains(*child)))
+            .unwrap_or(false);
+        if marked {
+            continue;
+        }
+        let top = piece.half_extents[1] + MARKING_LIFT;
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                LinesBundle {
+                    mesh: meshes.add(line_mesh.clone()),
+                    material: lines_materials.add(LinesMaterial {}),
+                    transform: Transform::from_xyz(0.0, top, 0.0),
+                    ..default()
+                },
+                PitchMarkings {
+                    goal_x,
+                    home_goal_x,
+                },
+                Name::new("markings"),
+            ));
+        });
+    }
+}
+
+fn draw_box(lines: &mut Lines, min: Vec2, max: Vec2) {
+    let corners = [
+        Vec3::new(min.x, 0.0, min.y),
+        Vec3::new(max.x, 0.0, min.y),
+        Vec3::new(max.x, 0.0, max.y),
+        Vec3::new(min.x, 0.0, max.y),
+    ];
+    for i in 0..corners.len() {
+        lines.line_colored(corners[i], corners[(i + 1) % corners.len()], MARKING_COLOR);
+    }
+}
+
+// lines are rebuilt every frame like the grid's
+fn draw_markings_system(mut markings_query: Query<(&mut Lines, &PitchMarkings)>) {
+    let (width, _, length) = BOARD_DIM;
+    let half = Vec2::new(width, length) / 2.0;
+
+    for (mut lines, markings) in markings_query.iter_mut() {
+        // touchlines and goal lines
+        draw_box(&mut lines, -half, half);
+        // halfway line
+        lines.line_colored(
+            Vec3::new(-half.x, 0.0, 0.0),
+            Vec3::new(half.x, 0.0, 0.0),
+            MARKING_COLOR,
+        );
+        // center circle
+        for i in 0..CIRCLE_SEGMENTS {
+            let angle = |i: usize| i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            let point = |a: f32| Vec3::new(a.cos(), 0.0, a.sin()) * CENTER_CIRCLE_RADIUS;
+            lines.line_colored(point(angle(i)), point(angle(i + 1)), MARKING_COLOR);
+        }
+        // a box in front of each goal, following its gap
+        let reach = GOAL_GAP + BOX_MARGIN;
+        for (x, end) in [(markings.goal_x, half.y), (markings.home_goal_x, -half.y)] {
+            let inner = end - end.signum() * BOX_DEPTH;
+            let min = Vec2::new((x - reach).max(-half.x), end.min(inner));
+            let max = Vec2::new((x + reach).min(half.x), end.max(inner));
+            draw_box(&mut lines, min, max);
+        }
+    }
+}
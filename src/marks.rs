@@ -1,9 +1,16 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Component)]
 pub struct Player;
+// which local player slot drives this player, 0 is the one on the home side
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerIndex(pub usize);
 #[derive(Component)]
 pub struct Ball;
+// the computer-driven capsule playing for the away side
+#[derive(Component)]
+pub struct Opponent;
 #[derive(Component)]
 pub struct Floor;
 
@@ -14,7 +21,48 @@ pub struct GameCamera;
 pub struct PlayerCamera;
 
 #[derive(Component)]
-pub struct ScoreText;
+pub struct EnemyGoal;
+
+#[derive(
+    Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect, FromReflect,
+)]
+pub enum Team {
+    Home,
+    Away,
+}
+
+impl Team {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Team::Home => "Home",
+            Team::Away => "Away",
+        }
+    }
+
+    pub fn other(&self) -> Team {
+        match self {
+            Team::Home => Team::Away,
+            Team::Away => Team::Home,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Team::Home => Color::FUCHSIA,
+            Team::Away => Color::ORANGE_RED,
+        }
+    }
+}
 
 #[derive(Component)]
-pub struct EnemyGoal;
+pub struct MatchEntity;
+
+#[derive(Component)]
+pub struct PlayerGoal;
+
+// a post or crossbar, tagged with the side defending that goal
+#[derive(Component)]
+pub struct GoalPost(pub Team);
+
+#[derive(Component)]
+pub struct ClockText;
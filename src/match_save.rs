@@ -0,0 +1,186 @@
+use super::clock::MatchClock;
+use super::game_state::{app_exiting, GameState};
+use super::instant_replay::InstantReplay;
+use super::kickoff::Kickoff;
+use super::marks::{Ball, Player, Team};
+use super::mode::ActiveMode;
+use super::rematch::RematchEvent;
+use super::storage;
+use super::Score;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const SAVE_FILE: &str = "match.ron";
+
+pub struct MatchSavePlugin;
+
+impl Plugin for MatchSavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MatchResume::default())
+            .add_system_to_stage(CoreStage::Last, save_on_exit_system)
+            .add_system(resume_system)
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(remove_save));
+    }
+}
+
+// a match left mid-way, written when quitting and removed once it's resumed or over
+#[derive(Serialize, Deserialize)]
+struct SavedMatch {
+    mode: String,
+    score: Score,
+    clock: MatchClock,
+    // the ball and everyone on a team, matched by name like saved replays
+    bodies: Vec<(String, SavedBody)>,
+}
+
+impl storage::Versioned for SavedMatch {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct SavedBody {
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    linvel: [f32; 3],
+    angvel: [f32; 3],
+}
+
+impl SavedBody {
+    fn capture(tf: &Transform, vel: Option<&Velocity>) -> Self {
+        let vel = vel.copied().unwrap_or_default();
+        Self {
+            translation: tf.translation.to_array(),
+            rotation: tf.rotation.to_array(),
+            linvel: vel.linvel.to_array(),
+            angvel: vel.angvel.to_array(),
+        }
+    }
+}
+
+// the save the menu's Continue picked, applied once the rematch has spawned everyone
+#[derive(Resource, Default)]
+pub struct MatchResume {
+    pending: Option<SavedMatch>,
+}
+
+impl MatchResume {
+    pub fn available() -> bool {
+        storage::data_dir().join(SAVE_FILE).exists()
+    }
+
+    // loads the save and starts the match it gets poured into
+    pub fn resume(&mut self, rematch_events: &mut EventWriter<RematchEvent>) {
+        let Some(saved) = storage::load::<SavedMatch>(SAVE_FILE) else {
+            return;
+        };
+        self.pending = Some(saved);
+        rematch_events.send(RematchEvent);
+    }
+}
+
+fn remove_save() {
+    let path = storage::data_dir().join(SAVE_FILE);
+    if path.exists() {
+        if let Err(err) = std::fs::remove_file(&path) {
+            warn!("Failed to remove {}: {}", path.display(), err);
+        }
+    }
+}
+
+// only a match still being played is worth coming back to
+fn save_on_exit_system(
+    mut exit_events: EventReader<AppExit>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    state: Res<State<GameState>>,
+    instant: Res<InstantReplay>,
+    mode: Res<ActiveMode>,
+    score: Res<Score>,
+    clock: Res<MatchClock>,
+    body_query: Query<
+        (Entity, &Name, &Transform, Option<&Velocity>),
+        Or<(With<Ball>, With<Team>)>,
+    >,
+) {
+    if !app_exiting(&mut exit_events, &mut close_events) {
+        return;
+    }
+    if !matches!(
+        state.current(),
+        GameState::Playing | GameState::Paused | GameState::HalfTime | GameState::Replay
+    ) {
+        return;
+    }
+
+    // a goal replay has rewound everyone, save where they really are
+    let replaying = state.current() == &GameState::Replay;
+    let saved = SavedMatch {
+        mode: mode.name().to_string(),
+        score: score.clone(),
+        clock: clock.clone(),
+        bodies: body_query
+            .iter()
+            .map(|(entity, name, tf, vel)| {
+                let live = instant.live(entity).filter(|_| replaying);
+                let tf = live.as_ref().unwrap_or(tf);
+                (name.as_str().to_string(), SavedBody::capture(tf, vel))
+            })
+            .collect(),
+    };
+    storage::save(SAVE_FILE, &saved);
+    info!("Saved the match in progress");
+}
+
+fn resume_system(
+    mut resume: ResMut<MatchResume>,
+    mut score: ResMut<Score>,
+    mut clock: ResMut<MatchClock>,
+    mut kickoff: ResMut<Kickoff>,
+    mode: Res<ActiveMode>,
+    player_query: Query<(), With<Player>>,
+    mut body_query: Query<
+        (Entity, &Name, &mut Transform, Option<&mut Velocity>),
+        Or<(With<Ball>, With<Team>)>,
+    >,
+) {
+    if resume.pending.is_none() || player_query.is_empty() {
+        return;
+    }
+    let Some(saved) = resume.pending.take() else {
+        return;
+    };
+    remove_save();
+    if saved.mode != mode.name() {
+        warn!(
+            "The saved match was {}, not {}, starting fresh",
+            saved.mode,
+            mode.name()
+        );
+        return;
+    }
+
+    *score = saved.score;
+    *clock = saved.clock;
+    // play carries on from where it stopped, nobody waits for a kickoff
+    kickoff.held = false;
+
+    let mut used = Vec::new();
+    for (name, body) in saved.bodies.iter() {
+        let Some((entity, _, mut tf, vel)) = body_query
+            .iter_mut()
+            .find(|(e, n, ..)| n.as_str() == name && !used.contains(e))
+        else {
+            continue;
+        };
+        used.push(entity);
+        tf.translation = Vec3::from_array(body.translation);
+        tf.rotation = Quat::from_array(body.rotation);
+        if let Some(mut vel) = vel {
+            vel.linvel = Vec3::from_array(body.linvel);
+            vel.angvel = Vec3::from_array(body.angvel);
+        }
+    }
+    info!("Resumed the saved match");
+}
@@ -0,0 +1,149 @@
+use super::game_state::GameState;
+use super::marks::Team;
+use super::player::KickEvent;
+use super::rematch::RematchEvent;
+use super::rules::LastTouch;
+use super::GoalScoredEvent;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+const BAR_WIDTH: f32 = 220.0;
+
+pub struct MatchStatsPlugin;
+
+impl Plugin for MatchStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchStats>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(shot_count_system)
+                    .with_system(possession_system)
+                    .with_system(fastest_goal_system),
+            )
+            .add_system(stats_reset_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver).with_system(stats_ui_system),
+            );
+    }
+}
+
+// both sides' numbers for the current match, shown on the results screen
+#[derive(Resource, Default)]
+pub struct MatchStats {
+    pub shots: [u32; 2],
+    // seconds each side was the last to touch the ball
+    pub possession: [f32; 2],
+    // ball speed in m/s and who scored it
+    pub fastest_goal: Option<(Team, f32)>,
+}
+
+impl MatchStats {
+    fn index(team: Team) -> usize {
+        match team {
+            Team::Home => 0,
+            Team::Away => 1,
+        }
+    }
+
+    pub fn shots(&self, team: Team) -> u32 {
+        self.shots[Self::index(team)]
+    }
+
+    // share of the touched time, split evenly until anyone has touched the ball
+    pub fn possession_share(&self, team: Team) -> f32 {
+        let total = self.possession[0] + self.possession[1];
+        if total <= 0.0 {
+            return 0.5;
+        }
+        self.possession[Self::index(team)] / total
+    }
+}
+
+// any kick sent toward the other side's goal, home attacks +z and away -z
+fn shot_count_system(mut stats: ResMut<MatchStats>, mut kick_events: EventReader<KickEvent>) {
+    for ev in kick_events.iter() {
+        let forward = match ev.team {
+            Team::Home => ev.impulse.z,
+            Team::Away => -ev.impulse.z,
+        };
+        if forward > 0.0 {
+            stats.shots[MatchStats::index(ev.team)] += 1;
+        }
+    }
+}
+
+fn possession_system(mut stats: ResMut<MatchStats>, touch: Res<LastTouch>, time: Res<Time>) {
+    if let Some(team) = touch.latest {
+        stats.possession[MatchStats::index(team)] += time.delta_seconds();
+    }
+}
+
+fn fastest_goal_system(
+    mut stats: ResMut<MatchStats>,
+    mut goal_events: EventReader<GoalScoredEvent>,
+) {
+    for ev in goal_events.iter() {
+        let speed = ev.ball_velocity.length();
+        if stats.fastest_goal.map_or(true, |(_, fastest)| speed > fastest) {
+            stats.fastest_goal = Some((ev.against.other(), speed));
+        }
+    }
+}
+
+fn stats_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut stats: ResMut<MatchStats>,
+) {
+    if rematch_events.iter().count() > 0 {
+        *stats = MatchStats::default();
+    }
+}
+
+// a home bar growing from the left against an away bar from the right
+fn split_bar(ui: &mut egui::Ui, home: f32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(BAR_WIDTH, 12.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let split = rect.left() + rect.width() * home.clamp(0.0, 1.0);
+    painter.rect_filled(
+        egui::Rect::from_min_max(rect.min, egui::pos2(split, rect.bottom())),
+        0.0,
+        egui::Color32::LIGHT_BLUE,
+    );
+    painter.rect_filled(
+        egui::Rect::from_min_max(egui::pos2(split, rect.top()), rect.max),
+        0.0,
+        egui::Color32::LIGHT_RED,
+    );
+}
+
+fn stats_ui_system(mut egui_context: ResMut<EguiContext>, stats: Res<MatchStats>) {
+    egui::Window::new("Match stats")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 60.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let (home, away) = (stats.shots(Team::Home), stats.shots(Team::Away));
+            ui.label(format!("Shots  {} – {}", home, away));
+            let share = if home + away == 0 {
+                0.5
+            } else {
+                home as f32 / (home + away) as f32
+            };
+            split_bar(ui, share);
+
+            let possession = stats.possession_share(Team::Home);
+            ui.label(format!(
+                "Possession  {:.0}% – {:.0}%",
+                possession * 100.0,
+                (1.0 - possession) * 100.0
+            ));
+            split_bar(ui, possession);
+
+            match stats.fastest_goal {
+                Some((team, speed)) => {
+                    ui.label(format!("Fastest goal  {:.1} m/s by {}", speed, team.name()))
+                }
+                None => ui.label("No goals"),
+            };
+        });
+}
@@ -0,0 +1,83 @@
+use super::cli::StartupArgs;
+use super::game_state::GameState;
+use super::match_save::MatchResume;
+use super::net::networked;
+use super::mutators::{Mutators, MAX_BALLS};
+use super::proc_arena::{ArenaSeed, RandomArenaEvent, SaveArenaEvent};
+use super::rematch::RematchEvent;
+use super::settings::SettingsOpen;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(skip_menu_system)
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(title_screen_system));
+    }
+}
+
+// a networked session was already chosen in the menu of the copy that launched it
+fn skip_menu_system(mut rematch_events: EventWriter<RematchEvent>, args: Res<StartupArgs>) {
+    if networked(&args) {
+        rematch_events.send(RematchEvent);
+    }
+}
+
+fn title_screen_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut rematch_events: EventWriter<RematchEvent>,
+    mut exit_events: EventWriter<bevy::app::AppExit>,
+    mut settings_open: ResMut<SettingsOpen>,
+    mut random_events: EventWriter<RandomArenaEvent>,
+    mut save_events: EventWriter<SaveArenaEvent>,
+    seed: Option<Res<ArenaSeed>>,
+    mut resume: ResMut<MatchResume>,
+    mut mutators: ResMut<Mutators>,
+) {
+    // edited on a copy so the resource only reads as changed when a setting does
+    let mut edited = mutators.clone();
+    egui::Window::new("Playground")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            // picks up the match that was quit half way through
+            if MatchResume::available() && ui.button("Continue").clicked() {
+                resume.resume(&mut rematch_events);
+            }
+            // a fresh match, the rematch spawns the players and puts the state back to Playing
+            if ui.button("Play").clicked() {
+                rematch_events.send(RematchEvent);
+            }
+            // picked up by the next Play, custom games without touching the config
+            ui.collapsing("Mutators", |ui| {
+                ui.add(egui::Slider::new(&mut edited.balls, 1..=MAX_BALLS).text("Balls"));
+                ui.checkbox(&mut edited.low_gravity, "Low gravity");
+                ui.checkbox(&mut edited.beach_ball, "Beach ball");
+                ui.checkbox(&mut edited.tiny_ball, "Tiny ball");
+                ui.checkbox(&mut edited.double_speed, "Double speed");
+            });
+            if ui.button("Random arena").clicked() {
+                random_events.send(RandomArenaEvent(None));
+            }
+            if let Some(seed) = &seed {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Arena seed {}", seed.seed));
+                    if !seed.saved && ui.button("Save").clicked() {
+                        save_events.send(SaveArenaEvent);
+                    }
+                });
+            }
+            if ui.button("Settings").clicked() {
+                settings_open.0 = !settings_open.0;
+            }
+            if ui.button("Quit").clicked() {
+                exit_events.send(bevy::app::AppExit);
+            }
+        });
+    if edited != *mutators {
+        *mutators = edited;
+    }
+}
@@ -0,0 +1,94 @@
+use super::arena::{Arena, PieceKind};
+use super::config::GameConfig;
+use super::game_state::GameState;
+use super::marks::{Ball, Team};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+// the longer side of the arena is drawn this many points long
+const MINIMAP_SIZE: f32 = 180.0;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Playing).with_system(minimap_system));
+    }
+}
+
+fn to_color32(color: Color) -> egui::Color32 {
+    let [r, g, b, _] = color.as_rgba_f32();
+    egui::Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+// top-down view of the whole arena, home end at the bottom like the shot chart
+fn minimap_system(
+    mut egui_context: ResMut<EguiContext>,
+    config: Res<GameConfig>,
+    arena: Res<Arena>,
+    ball_query: Query<&GlobalTransform, With<Ball>>,
+    team_query: Query<(&GlobalTransform, &Team)>,
+) {
+    if !config.hud.show_minimap {
+        return;
+    }
+    // scaled to whatever the arena's visible pieces span
+    let pieces: Vec<_> = arena
+        .descriptor
+        .pieces
+        .iter()
+        .filter(|piece| piece.visible && piece.kind != PieceKind::Prop)
+        .map(|piece| (piece, piece.bounds()))
+        .collect();
+    let Some((min, max)) = pieces
+        .iter()
+        .map(|(_, bounds)| *bounds)
+        .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))
+    else {
+        return;
+    };
+    let span = Vec2::new(max.x - min.x, max.z - min.z);
+    let scale = MINIMAP_SIZE / span.max_element().max(f32::EPSILON);
+    let center = Vec2::new(min.x + max.x, min.z + max.z) / 2.0;
+
+    egui::Area::new("minimap")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let (rect, _) =
+                ui.allocate_exact_size(egui::vec2(span.x, span.y) * scale, egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            let to_screen = |p: Vec3| {
+                egui::pos2(
+                    rect.center().x - (p.x - center.x) * scale,
+                    rect.center().y - (p.z - center.y) * scale,
+                )
+            };
+
+            painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(140));
+            for (piece, (min, max)) in pieces.iter() {
+                let area = egui::Rect::from_two_pos(to_screen(*min), to_screen(*max));
+                match piece.kind {
+                    PieceKind::Floor => {
+                        painter.rect_filled(area, 0.0, egui::Color32::from_rgb(20, 60, 20))
+                    }
+                    PieceKind::GoalNet => painter.rect_stroke(
+                        area,
+                        0.0,
+                        egui::Stroke::new(1.0, egui::Color32::from_white_alpha(160)),
+                    ),
+                    _ => {
+                        let [r, g, b] = piece.color;
+                        painter.rect_filled(area, 0.0, to_color32(Color::rgb(r, g, b)))
+                    }
+                }
+            }
+
+            for (tf, team) in team_query.iter() {
+                painter.circle_filled(to_screen(tf.translation()), 4.0, to_color32(team.color()));
+            }
+            for tf in ball_query.iter() {
+                painter.circle_filled(to_screen(tf.translation()), 3.0, egui::Color32::WHITE);
+            }
+        });
+}
@@ -0,0 +1,214 @@
+use super::boss::BossMode;
+use super::cli::StartupArgs;
+use super::clock::MatchClock;
+use super::config::GameConfig;
+use super::drills::DrillMode;
+use super::marks::Team;
+use super::rematch::RematchEvent;
+use super::rules::RuleSet;
+use super::series::ClassicMode;
+use super::survival::SurvivalMode;
+use super::versus::VersusMode;
+use super::Score;
+use bevy::prelude::*;
+use std::sync::Arc;
+
+// what every mode goes through: set up once, tick its rules, say when someone
+// has won, and clean up before the next match starts
+pub trait GameMode: Send + Sync + 'static {
+    // stable key, used for config overrides and match history
+    fn name(&self) -> &'static str;
+    fn title(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+
+    // adds the mode's own plugins and systems
+    fn build(&self, app: &mut App);
+
+    fn tick(&self, _ctx: &ModeContext, _commands: &mut Commands) {}
+
+    fn winner(&self, _ctx: &ModeContext) -> Option<Team> {
+        None
+    }
+
+    // modes that only end by their own rules play without the match clock, so no
+    // half time and no full time
+    fn timed(&self) -> bool {
+        true
+    }
+
+    // modes that show their own end of match window, with its own way on to the
+    // next one, get no generic results screen on top of it
+    fn own_results(&self) -> bool {
+        false
+    }
+
+    fn teardown(&self, _commands: &mut Commands) {}
+}
+
+pub struct ModeContext<'a> {
+    pub score: &'a Score,
+    pub clock: &'a MatchClock,
+    pub time: &'a Time,
+    // the active mode's rules, from the config and any --score-limit
+    pub rules: &'a RuleSet,
+}
+
+pub struct MatchFinishedEvent {
+    pub winner: Team,
+    pub home_goals: u32,
+    pub away_goals: u32,
+    pub duration: f32,
+}
+
+// every mode the menu can offer
+#[derive(Resource, Clone)]
+pub struct ModeRegistry {
+    modes: Vec<Arc<dyn GameMode>>,
+}
+
+impl Default for ModeRegistry {
+    fn default() -> Self {
+        Self {
+            modes: vec![
+                Arc::new(ClassicMode),
+                Arc::new(BossMode),
+                Arc::new(SurvivalMode),
+                Arc::new(DrillMode),
+                Arc::new(VersusMode),
+            ],
+        }
+    }
+}
+
+impl ModeRegistry {
+    pub fn get(&self, name: &str) -> Option<Arc<dyn GameMode>> {
+        self.modes.iter().find(|mode| mode.name() == name).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn GameMode>> {
+        self.modes.iter()
+    }
+
+    // `--mode <name>`, with the older `--boss`, `--survival` and `--drill` flags still honoured
+    pub fn from_args(&self, args: &StartupArgs) -> Arc<dyn GameMode> {
+        let name = if let Some(mode) = &args.mode {
+            Some(mode.clone())
+        } else if args.boss {
+            Some("boss".to_string())
+        } else if args.survival {
+            Some("survival".to_string())
+        } else if args.drill.is_some() {
+            Some("drill".to_string())
+        } else {
+            None
+        };
+
+        name.and_then(|name| {
+            let mode = self.get(&name);
+            if mode.is_none() {
+                let known: Vec<_> = self.iter().map(|mode| mode.name()).collect();
+                warn!(
+                    "Unknown mode {}, playing classic (known: {})",
+                    name,
+                    known.join(", ")
+                );
+            }
+            mode
+        })
+        .unwrap_or_else(|| self.modes[0].clone())
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct ActiveMode(pub Arc<dyn GameMode>);
+
+impl ActiveMode {
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
+
+pub struct ModePlugin {
+    pub registry: ModeRegistry,
+    pub mode: Arc<dyn GameMode>,
+}
+
+impl Plugin for ModePlugin {
+    fn build(&self, app: &mut App) {
+        info!("Starting {} mode", self.mode.name());
+        app.insert_resource(self.registry.clone())
+            .insert_resource(ActiveMode(self.mode.clone()))
+            .insert_resource(MatchDecided(false))
+            .add_event::<MatchFinishedEvent>()
+            .add_system(mode_tick_system)
+            .add_system(mode_win_system.after(mode_tick_system))
+            .add_system_to_stage(CoreStage::PostUpdate, mode_teardown_system);
+
+        self.mode.build(app);
+    }
+}
+
+// a match is only decided once, until the next rematch
+#[derive(Resource)]
+struct MatchDecided(bool);
+
+fn mode_tick_system(
+    mut commands: Commands,
+    mode: Res<ActiveMode>,
+    score: Res<Score>,
+    clock: Res<MatchClock>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+) {
+    let ctx = ModeContext {
+        score: &score,
+        clock: &clock,
+        time: &time,
+        rules: config.rules_for(mode.name()),
+    };
+    mode.0.tick(&ctx, &mut commands);
+}
+
+fn mode_win_system(
+    mode: Res<ActiveMode>,
+    mut decided: ResMut<MatchDecided>,
+    score: Res<Score>,
+    clock: Res<MatchClock>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut finished_events: EventWriter<MatchFinishedEvent>,
+) {
+    if decided.0 {
+        return;
+    }
+    let ctx = ModeContext {
+        score: &score,
+        clock: &clock,
+        time: &time,
+        rules: config.rules_for(mode.name()),
+    };
+    let limit = ctx.rules.limit_winner(&score);
+    let Some(winner) = mode.0.winner(&ctx).or(limit) else {
+        return;
+    };
+
+    decided.0 = true;
+    finished_events.send(MatchFinishedEvent {
+        winner,
+        home_goals: ctx.score.home,
+        away_goals: ctx.score.away,
+        duration: ctx.clock.elapsed,
+    });
+}
+
+fn mode_teardown_system(
+    mut commands: Commands,
+    mode: Res<ActiveMode>,
+    mut decided: ResMut<MatchDecided>,
+    mut rematch_events: EventReader<RematchEvent>,
+) {
+    if rematch_events.iter().count() > 0 {
+        mode.0.teardown(&mut commands);
+        decided.0 = false;
+    }
+}
@@ -0,0 +1,200 @@
+use super::cli::StartupArgs;
+use super::config::{PhysicsConfig, PhysicsOverrides};
+use super::player::KickEvent;
+use super::settings::GameSettings;
+use super::{MatchAssets, Score};
+use bevy::audio::PlaybackSettings;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const MODS_DIR: &str = "mods";
+const MANIFEST_FILE: &str = "mod.ron";
+
+pub struct ModPlugin;
+
+impl Plugin for ModPlugin {
+    fn build(&self, app: &mut App) {
+        let manifests = scan_mods(Path::new(MODS_DIR));
+        app.insert_resource(ModManifests(manifests))
+            .insert_resource(ModRegistry::default())
+            .add_startup_system(load_mods_system)
+            .add_system(ball_skin_system)
+            .add_system(mod_sound_system);
+    }
+}
+
+// mods/<package>/mod.ron, every path inside is relative to the package folder
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ModManifest {
+    pub name: String,
+    pub version: String,
+    pub ball_skins: HashMap<String, String>,
+    // gameplay event name ("goal", "kick") to sound file
+    pub sounds: HashMap<String, String>,
+    // physics overrides keyed by mode name, on top of config/playground.ron
+    pub rules: HashMap<String, PhysicsConfig>,
+    pub arenas: Vec<String>,
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl Default for ModManifest {
+    fn default() -> Self {
+        Self {
+            name: "unnamed".to_string(),
+            version: "0.0.0".to_string(),
+            ball_skins: HashMap::new(),
+            sounds: HashMap::new(),
+            rules: HashMap::new(),
+            arenas: Vec::new(),
+            root: PathBuf::new(),
+        }
+    }
+}
+
+impl ModManifest {
+    // asset paths outside the assets folder have to be absolute for the asset server
+    fn asset_path(&self, relative: &str) -> String {
+        let path = self.root.join(relative);
+        std::fs::canonicalize(&path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[derive(Resource)]
+struct ModManifests(Vec<ModManifest>);
+
+// everything mods contributed, later packages override earlier ones by name
+#[derive(Resource, Default)]
+pub struct ModRegistry {
+    pub loaded: Vec<String>,
+    pub ball_skins: HashMap<String, Handle<Image>>,
+    pub sounds: HashMap<String, Handle<AudioSource>>,
+    pub arenas: Vec<PathBuf>,
+    pub active_skin: Option<String>,
+}
+
+fn scan_mods(dir: &Path) -> Vec<ModManifest> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut roots: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .collect();
+    // load order is alphabetical so overrides are predictable
+    roots.sort();
+
+    roots
+        .into_iter()
+        .filter_map(|root| {
+            let path = root.join(MANIFEST_FILE);
+            let text = std::fs::read_to_string(&path).ok()?;
+            match ron::from_str::<ModManifest>(&text) {
+                Ok(mut manifest) => {
+                    manifest.root = root;
+                    Some(manifest)
+                }
+                Err(err) => {
+                    warn!("Skipping mod {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn load_mods_system(
+    manifests: Res<ModManifests>,
+    mut registry: ResMut<ModRegistry>,
+    mut overrides: ResMut<PhysicsOverrides>,
+    asset_server: Res<AssetServer>,
+    args: Res<StartupArgs>,
+) {
+    for manifest in manifests.0.iter() {
+        for (name, texture) in manifest.ball_skins.iter() {
+            let handle = asset_server.load(manifest.asset_path(texture).as_str());
+            registry.ball_skins.insert(name.clone(), handle);
+        }
+        for (event, sound) in manifest.sounds.iter() {
+            let handle = asset_server.load(manifest.asset_path(sound).as_str());
+            registry.sounds.insert(event.clone(), handle);
+        }
+        // kept apart from GameConfig so saving the config never writes them to the file
+        for (mode, physics) in manifest.rules.iter() {
+            overrides.0.insert(mode.clone(), physics.clone());
+        }
+        for arena in manifest.arenas.iter() {
+            registry.arenas.push(manifest.root.join(arena));
+        }
+        info!("Loaded mod {} {}", manifest.name, manifest.version);
+        registry
+            .loaded
+            .push(format!("{} {}", manifest.name, manifest.version));
+    }
+
+    registry.active_skin = args
+        .ball_skin
+        .clone()
+        .filter(|skin| registry.ball_skins.contains_key(skin));
+}
+
+// F9 cycles through the mod ball skins, back to the stock ball after the last
+fn ball_skin_system(
+    mut registry: ResMut<ModRegistry>,
+    keyboard: Res<Input<KeyCode>>,
+    match_assets: Option<Res<MatchAssets>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    if keyboard.just_pressed(KeyCode::F9) && !registry.ball_skins.is_empty() {
+        let mut names: Vec<_> = registry.ball_skins.keys().cloned().collect();
+        names.sort();
+        let next = match &registry.active_skin {
+            Some(current) => names.iter().skip_while(|name| *name != current).nth(1),
+            None => names.first(),
+        };
+        registry.active_skin = next.cloned();
+    }
+    if !registry.is_changed() {
+        return;
+    }
+    let Some(match_assets) = match_assets else {
+        return;
+    };
+    let Some(material) = materials.get_mut(&match_assets.ball_material) else {
+        return;
+    };
+
+    material.base_color_texture = Some(match &registry.active_skin {
+        Some(skin) => registry.ball_skins[skin].clone(),
+        None => asset_server.load("textures/ball/ball.png"),
+    });
+}
+
+fn mod_sound_system(
+    registry: Res<ModRegistry>,
+    audio: Res<Audio>,
+    settings: Res<GameSettings>,
+    score: Res<Score>,
+    mut kick_events: EventReader<KickEvent>,
+    mut last_goals: Local<u32>,
+) {
+    let playback = PlaybackSettings::ONCE.with_volume(settings.master_volume);
+    if kick_events.iter().count() > 0 {
+        if let Some(sound) = registry.sounds.get("kick") {
+            audio.play_with_settings(sound.clone(), playback);
+        }
+    }
+    if score.home > *last_goals {
+        if let Some(sound) = registry.sounds.get("goal") {
+            audio.play_with_settings(sound.clone(), playback);
+        }
+    }
+    *last_goals = score.home;
+}
@@ -0,0 +1,96 @@
+use super::cli::StartupArgs;
+use bevy::prelude::*;
+
+// more than this and the kickoff spots run into the walls
+pub const MAX_BALLS: usize = 4;
+const LOW_GRAVITY_SCALE: f32 = 0.35;
+// a beach ball keeps most of its bounce but the air soon slows it down
+const BEACH_BALL_RESTITUTION: f32 = 0.95;
+const BEACH_BALL_DAMPING: f32 = 1.5;
+const TINY_BALL_SCALE: f32 = 0.5;
+const DOUBLE_SPEED_SCALE: f32 = 2.0;
+
+pub struct MutatorsPlugin;
+
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut mutators = Mutators::default();
+        if let Some(balls) = app
+            .world
+            .get_resource::<StartupArgs>()
+            .and_then(|args| args.multiball)
+        {
+            mutators.balls = balls.clamp(2, MAX_BALLS);
+        }
+        app.insert_resource(mutators);
+    }
+}
+
+// tweaks to a normal match, read whenever the match entities are spawned so they
+// take effect from the next rematch
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Mutators {
+    // how many balls are in play at once, every one of them can score
+    pub balls: usize,
+    pub low_gravity: bool,
+    pub beach_ball: bool,
+    pub tiny_ball: bool,
+    // everyone runs twice as fast
+    pub double_speed: bool,
+}
+
+impl Default for Mutators {
+    fn default() -> Self {
+        Self {
+            balls: 1,
+            low_gravity: false,
+            beach_ball: false,
+            tiny_ball: false,
+            double_speed: false,
+        }
+    }
+}
+
+impl Mutators {
+    pub fn gravity_scale(&self) -> f32 {
+        if self.low_gravity {
+            LOW_GRAVITY_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    // the config's bounce unless the beach ball is on
+    pub fn restitution(&self, configured: f32) -> f32 {
+        if self.beach_ball {
+            BEACH_BALL_RESTITUTION
+        } else {
+            configured
+        }
+    }
+
+    pub fn ball_damping(&self, configured: f32) -> f32 {
+        if self.beach_ball {
+            BEACH_BALL_DAMPING
+        } else {
+            configured
+        }
+    }
+
+    // radius of the ball relative to the usual half a unit
+    pub fn ball_scale(&self) -> f32 {
+        if self.tiny_ball {
+            TINY_BALL_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    pub fn speed_scale(&self) -> f32 {
+        if self.double_speed {
+            DOUBLE_SPEED_SCALE
+        } else {
+            1.0
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::marks::{Player, PlayerIndex};
+use super::worldtext::WorldText;
+use bevy::prelude::*;
+
+// height above the capsule centre
+const TAG_OFFSET: f32 = 1.5;
+const FADE_START: f32 = 12.0;
+const FADE_END: f32 = 28.0;
+
+pub struct NameTagPlugin;
+
+impl Plugin for NameTagPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(player_name_system)
+            .add_system(name_tag_system.after(player_name_system));
+    }
+}
+
+#[derive(Component)]
+pub struct NameTag(pub String);
+
+pub fn profile_name() -> String {
+    std::env::var("PLAYGROUND_PLAYER").unwrap_or_else(|_| "Player".to_string())
+}
+
+// the local player is respawned on every rematch; other local players bring their own tag
+fn player_name_system(
+    mut commands: Commands,
+    player_query: Query<(Entity, &PlayerIndex), Added<Player>>,
+) {
+    for (entity, index) in player_query.iter() {
+        if index.0 == 0 {
+            commands.entity(entity).insert(NameTag(profile_name()));
+        }
+    }
+}
+
+fn name_tag_system(mut commands: Commands, tag_query: Query<(Entity, &NameTag), Changed<NameTag>>) {
+    for (entity, tag) in tag_query.iter() {
+        commands.entity(entity).insert(
+            WorldText::new(tag.0.clone())
+                .with_offset(Vec3::Y * TAG_OFFSET)
+                .with_fade(FADE_START, FADE_END),
+        );
+    }
+}
@@ -0,0 +1,759 @@
+use super::boost_meter::BoostMeter;
+use super::boost_pads::{step_pad, BoostPad, SpeedBoost};
+use super::cli::StartupArgs;
+use super::clock::MatchClock;
+use super::config::GameConfig;
+use super::determinism::TICK_DT;
+use super::game_state::GameState;
+use super::kickoff::{hold_line, Kickoff};
+use super::lockstep::{
+    away_player_bundle, keyboard_bits, step_players, InputBits, LockstepConfig, NetPlayer,
+    PlayerStep, AWAY_SPAWN,
+};
+use super::marks::{Ball, Player, Team};
+use super::mode::ActiveMode;
+use super::mutators::Mutators;
+use super::player::{Dash, Facing, Jump, Stamina};
+use super::rules::{GoalContext, Touch};
+use super::spin;
+use super::{
+    GoalScoredEvent, MatchAssets, MatchEntities, Score, GOAL_RESET_DELAY, PLAYER_SPAWN,
+};
+use bevy::prelude::*;
+use bevy::transform::transform_propagate_system;
+use bevy_egui::{egui, EguiContext};
+use bevy_ggrs::{GGRSPlugin, PlayerInputs, Rollback, RollbackIdProvider, Session};
+use bevy_rapier3d::prelude::*;
+use ggrs::{
+    Config, Message, NonBlockingSocket, PlayerHandle, PlayerType, SessionBuilder, SessionState,
+};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+// room codes travel at the front of every datagram
+const ROOM_CODE_LEN: usize = 16;
+// how far ahead of the peer's last known input we keep guessing before stalling
+const MAX_PREDICTION: usize = 8;
+const INPUT_DELAY: usize = 2;
+
+const ROLLBACK_STAGE: &str = "net_rollback";
+const RULES_STAGE: &str = "net_rules";
+const SAVE_STAGE: &str = "net_save";
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NetMenu::default())
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(net_menu_system));
+    }
+}
+
+// whether this copy was launched into an online match, lockstep or rollback
+pub fn networked(args: &StartupArgs) -> bool {
+    NetConfig::from_args(args).is_some() || LockstepConfig::from_args(args).is_some()
+}
+
+// whether goals, kickoffs and pads belong to the rollback schedule instead of the
+// usual systems; develop builds never start a session
+pub fn rollback(args: &StartupArgs) -> bool {
+    !cfg!(feature = "develop") && NetConfig::from_args(args).is_some()
+}
+
+// usage: --net <local addr> <peer addr> <home|away> [--room <code>]
+pub struct NetConfig {
+    pub local: SocketAddr,
+    pub peer: SocketAddr,
+    pub team: Team,
+    // both sides must give the same code before any datagram is accepted
+    pub room: Option<String>,
+}
+
+impl NetConfig {
+    pub fn from_args(args: &StartupArgs) -> Option<Self> {
+        let [local, peer, side] = args.net.as_deref()? else {
+            return None;
+        };
+        let team = match side.as_str() {
+            "away" => Team::Away,
+            _ => Team::Home,
+        };
+        Some(Self {
+            local: local.parse().ok()?,
+            peer: peer.parse().ok()?,
+            team,
+            room: args.room.clone(),
+        })
+    }
+
+    pub fn to_args(&self) -> Vec<String> {
+        let team = match self.team {
+            Team::Home => "home",
+            Team::Away => "away",
+        };
+        let mut args = vec![
+            "--net".to_string(),
+            self.local.to_string(),
+            self.peer.to_string(),
+            team.to_string(),
+        ];
+        if let Some(room) = &self.room {
+            args.push("--room".to_string());
+            args.push(room.clone());
+        }
+        args
+    }
+}
+
+#[derive(Debug)]
+pub struct GgrsConfig;
+
+impl Config for GgrsConfig {
+    // the same input bits lockstep sends
+    type Input = InputBits;
+    type State = u8;
+    type Address = SocketAddr;
+}
+
+// ggrs messages over plain udp, each one prefixed with the room code; anything from
+// another address or another room is dropped before ggrs sees it
+struct RoomSocket {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    room: Vec<u8>,
+}
+
+impl NonBlockingSocket<SocketAddr> for RoomSocket {
+    fn send_to(&mut self, msg: &Message, addr: &SocketAddr) {
+        let Ok(body) = bincode::serialize(msg) else {
+            return;
+        };
+        let mut packet = Vec::with_capacity(1 + self.room.len() + body.len());
+        packet.push(self.room.len() as u8);
+        packet.extend_from_slice(&self.room);
+        packet.extend_from_slice(&body);
+        if let Err(err) = self.socket.send_to(&packet, addr) {
+            debug!("Net send failed: {}", err);
+        }
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(SocketAddr, Message)> {
+        let mut messages = Vec::new();
+        let mut buf = [0u8; 4096];
+        while let Ok((len, from)) = self.socket.recv_from(&mut buf) {
+            if from != self.peer {
+                warn!("Ignoring datagram from {}, expected {}", from, self.peer);
+                continue;
+            }
+            let room_len = buf[0] as usize;
+            if len < 1 + room_len || buf[1..1 + room_len] != self.room[..] {
+                warn!("Ignoring datagram from {} for another room", from);
+                continue;
+            }
+            if let Ok(msg) = bincode::deserialize(&buf[1 + room_len..len]) {
+                messages.push((from, msg));
+            }
+        }
+        messages
+    }
+}
+
+// rapier's own state, saved after every rollback frame so ggrs can rewind it along
+// with the transforms and velocities
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+struct PhysicsRollback {
+    frame: u32,
+    rapier: Vec<u8>,
+}
+
+// the last frame simulated, a PhysicsRollback behind it means ggrs just loaded
+// an older frame
+#[derive(Resource, Default)]
+struct SimulatedFrame(u32);
+
+// last frame's input bits, so kicks only fire on the frame the button goes down
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+struct PreviousInputs {
+    home: InputBits,
+    away: InputBits,
+}
+
+// the wait after a goal, counted in frames so a rollback rewinds it with the ball
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+struct NetGoalReset {
+    against: Option<Team>,
+    frames_left: u32,
+}
+
+pub struct RollbackPlugin(pub NetConfig);
+
+impl Plugin for RollbackPlugin {
+    fn build(&self, app: &mut App) {
+        let socket = UdpSocket::bind(self.0.local).expect("failed to bind net socket");
+        socket
+            .set_nonblocking(true)
+            .expect("failed to make net socket non-blocking");
+        let socket = RoomSocket {
+            socket,
+            peer: self.0.peer,
+            room: self
+                .0
+                .room
+                .iter()
+                .flat_map(|room| room.bytes())
+                .take(ROOM_CODE_LEN)
+                .collect(),
+        };
+
+        // handle 0 is always home and 1 away, whichever side this copy plays
+        let (home, away) = match self.0.team {
+            Team::Home => (PlayerType::Local, PlayerType::Remote(self.0.peer)),
+            Team::Away => (PlayerType::Remote(self.0.peer), PlayerType::Local),
+        };
+        let session = SessionBuilder::<GgrsConfig>::new()
+            .with_num_players(2)
+            .with_max_prediction_window(MAX_PREDICTION)
+            .with_input_delay(INPUT_DELAY)
+            .add_player(home, 0)
+            .and_then(|builder| builder.add_player(away, 1))
+            .and_then(|builder| builder.start_p2p_session(socket))
+            .expect("failed to start net session");
+
+        GGRSPlugin::<GgrsConfig>::new()
+            .with_update_frequency((1.0 / TICK_DT).round() as usize)
+            .with_input_system(net_input_system)
+            .register_rollback_component::<Transform>()
+            .register_rollback_component::<Velocity>()
+            .register_rollback_component::<ExternalImpulse>()
+            .register_rollback_component::<Facing>()
+            .register_rollback_component::<Stamina>()
+            .register_rollback_component::<Dash>()
+            .register_rollback_component::<Jump>()
+            // pickups spawn and despawn, which ggrs can't roll back, so online matches
+            // play on the meter each side starts with
+            .register_rollback_component::<BoostMeter>()
+            .register_rollback_component::<BoostPad>()
+            .register_rollback_component::<SpeedBoost>()
+            .register_rollback_resource::<PhysicsRollback>()
+            .register_rollback_resource::<PreviousInputs>()
+            .register_rollback_resource::<Score>()
+            .register_rollback_resource::<Kickoff>()
+            .register_rollback_resource::<NetGoalReset>()
+            .with_rollback_schedule(
+                Schedule::default()
+                    .with_stage(
+                        ROLLBACK_STAGE,
+                        SystemStage::parallel()
+                            .with_system(net_restore_system)
+                            .with_system(net_step_system.after(net_restore_system)),
+                    )
+                    .with_stage_after(
+                        ROLLBACK_STAGE,
+                        PhysicsStages::SyncBackend,
+                        SystemStage::parallel().with_system_set(
+                            RapierPhysicsPlugin::<NoUserData>::get_systems(
+                                PhysicsStages::SyncBackend,
+                            ),
+                        ),
+                    )
+                    .with_stage_before(
+                        PhysicsStages::SyncBackend,
+                        spin::MAGNUS_STAGE,
+                        spin::magnus_stage(),
+                    )
+                    .with_stage_after(
+                        PhysicsStages::SyncBackend,
+                        PhysicsStages::StepSimulation,
+                        SystemStage::parallel().with_system_set(
+                            RapierPhysicsPlugin::<NoUserData>::get_systems(
+                                PhysicsStages::StepSimulation,
+                            ),
+                        ),
+                    )
+                    .with_stage_after(
+                        PhysicsStages::StepSimulation,
+                        PhysicsStages::Writeback,
+                        SystemStage::parallel().with_system_set(
+                            RapierPhysicsPlugin::<NoUserData>::get_systems(
+                                PhysicsStages::Writeback,
+                            ),
+                        ),
+                    )
+                    // goals, kickoffs and pads judge the frame rapier just wrote back, so
+                    // a resimulated frame decides them again instead of a stale event
+                    .with_stage_after(
+                        PhysicsStages::Writeback,
+                        RULES_STAGE,
+                        SystemStage::parallel()
+                            .with_system(net_pad_system)
+                            .with_system(net_kickoff_system)
+                            .with_system(net_goal_system.after(net_kickoff_system)),
+                    )
+                    // later frames of the same rollback read the propagated transforms
+                    .with_stage_after(
+                        RULES_STAGE,
+                        SAVE_STAGE,
+                        SystemStage::parallel()
+                            .with_system(transform_propagate_system)
+                            .with_system(net_save_system),
+                    ),
+            )
+            .build(app);
+
+        app.insert_resource(Session::P2PSession(session))
+            .insert_resource(NetPeer(self.0.peer))
+            .init_resource::<SimulatedFrame>()
+            .init_resource::<NetGoalReset>()
+            // rapier must step the exact same dt on both peers
+            .insert_resource(RapierConfiguration {
+                timestep_mode: TimestepMode::Fixed {
+                    dt: TICK_DT,
+                    substeps: 1,
+                },
+                ..default()
+            })
+            // the ggrs schedule steps rapier, only despawns are left to the main one
+            .add_system_set_to_stage(
+                CoreStage::Last,
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsStages::DetectDespawn),
+            )
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_away_player)
+            .add_system(net_tag_system)
+            .add_system(net_goal_events_system)
+            .add_system(net_events_system)
+            .add_system(net_ui_system);
+    }
+}
+
+#[derive(Resource)]
+struct NetPeer(SocketAddr);
+
+fn spawn_away_player(mut commands: Commands, match_assets: Res<MatchAssets>, peer: Res<NetPeer>) {
+    commands.spawn(away_player_bundle(&match_assets, peer.0));
+}
+
+fn net_input_system(_handle: In<PlayerHandle>, keyboard: Res<Input<KeyCode>>) -> InputBits {
+    keyboard_bits(&keyboard)
+}
+
+// ggrs only saves and restores entities with a rollback id; both peers spawn the
+// match in the same order, so handing ids out in entity order keeps them paired
+fn net_tag_system(
+    mut commands: Commands,
+    mut ids: ResMut<RollbackIdProvider>,
+    query: Query<
+        Entity,
+        (
+            Or<(With<Ball>, With<Player>, With<NetPlayer>, With<BoostPad>)>,
+            Without<Rollback>,
+        ),
+    >,
+) {
+    let mut entities: Vec<Entity> = query.iter().collect();
+    entities.sort();
+    for entity in entities {
+        commands.entity(entity).insert(Rollback::new(ids.next_id()));
+    }
+}
+
+// put rapier back to the loaded frame before resimulating from it; handles stay
+// valid because nothing is added to or removed from the physics world in between
+fn net_restore_system(
+    mut physics: ResMut<PhysicsRollback>,
+    mut simulated: ResMut<SimulatedFrame>,
+    mut rapier: ResMut<RapierContext>,
+) {
+    if physics.frame != simulated.0 && !physics.rapier.is_empty() {
+        match bincode::deserialize::<RapierContext>(&physics.rapier) {
+            Ok(context) => {
+                rapier.islands = context.islands;
+                rapier.broad_phase = context.broad_phase;
+                rapier.narrow_phase = context.narrow_phase;
+                rapier.bodies = context.bodies;
+                rapier.colliders = context.colliders;
+                rapier.impulse_joints = context.impulse_joints;
+                rapier.multibody_joints = context.multibody_joints;
+                rapier.ccd_solver = context.ccd_solver;
+                rapier.query_pipeline = context.query_pipeline;
+                rapier.integration_parameters = context.integration_parameters;
+            }
+            Err(err) => error!("Failed to restore physics for a rollback: {}", err),
+        }
+    }
+    physics.frame += 1;
+    simulated.0 = physics.frame;
+}
+
+fn net_step_system(
+    mut commands: Commands,
+    inputs: Res<PlayerInputs<GgrsConfig>>,
+    mut previous: ResMut<PreviousInputs>,
+    rapier_config: Res<RapierConfiguration>,
+    mutators: Res<Mutators>,
+    mut player_query: Query<PlayerStep, Or<(With<Player>, With<NetPlayer>)>>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+) {
+    let (home, away) = (inputs[0].0, inputs[1].0);
+    let bits = [(home, previous.home), (away, previous.away)];
+    let scales = (rapier_config.gravity.y, mutators.speed_scale());
+    step_players(&mut commands, bits, scales, &mut player_query, &ball_query);
+    previous.home = home;
+    previous.away = away;
+}
+
+fn net_pad_system(
+    mut commands: Commands,
+    mut pad_query: Query<(Entity, &GlobalTransform, &mut BoostPad)>,
+    mut boost_query: Query<(Entity, &mut SpeedBoost)>,
+    player_query: Query<Entity, Or<(With<Player>, With<NetPlayer>)>>,
+    ball_query: Query<Entity, With<Ball>>,
+    rapier: Res<RapierContext>,
+) {
+    let tick = Duration::from_secs_f32(TICK_DT);
+    for (entity, mut boost) in boost_query.iter_mut() {
+        if boost.run_down(tick) {
+            commands.entity(entity).remove::<SpeedBoost>();
+        }
+    }
+    for (pad_entity, pad_tf, mut pad) in pad_query.iter_mut() {
+        step_pad(
+            &mut commands,
+            (pad_entity, pad_tf, &mut *pad),
+            &rapier,
+            |entity| player_query.contains(entity),
+            |entity| ball_query.contains(entity),
+            tick,
+        );
+    }
+}
+
+// the kickoff is released by a contact in the rolled back physics, not a collision
+// event that a resimulated frame would never send again
+fn net_kickoff_system(
+    mut kickoff: ResMut<Kickoff>,
+    rapier: Res<RapierContext>,
+    ball_query: Query<Entity, With<Ball>>,
+    mut player_query: Query<
+        (Entity, &Team, &mut Transform),
+        (Or<(With<Player>, With<NetPlayer>)>, Without<Ball>),
+    >,
+) {
+    if !kickoff.held {
+        return;
+    }
+    let touched = ball_query.iter().any(|ball| {
+        player_query.iter().any(|(player, ..)| {
+            rapier
+                .contact_pair(ball, player)
+                .map_or(false, |pair| pair.has_any_active_contacts())
+        })
+    });
+    if touched {
+        kickoff.held = false;
+    }
+    hold_line(
+        &kickoff,
+        player_query.iter_mut().map(|(_, team, tf)| (team, tf)),
+    );
+}
+
+// the same goal check as the usual goal_system, but on the rolled back ball and
+// sensors, so a mispredicted goal is undone along with the score it added
+fn net_goal_system(
+    mut score: ResMut<Score>,
+    mut reset: ResMut<NetGoalReset>,
+    mut kickoff: ResMut<Kickoff>,
+    rapier: Res<RapierContext>,
+    entities: Res<MatchEntities>,
+    config: Res<GameConfig>,
+    mode: Res<ActiveMode>,
+    clock: Res<MatchClock>,
+    goal_query: Query<&GlobalTransform>,
+    mut ball_query: Query<(&mut Transform, &Velocity, &Collider), With<Ball>>,
+    mut player_query: Query<
+        (&Team, &mut Transform),
+        (Or<(With<Player>, With<NetPlayer>)>, Without<Ball>),
+    >,
+) {
+    let rules = config.rules_for(mode.name());
+
+    // the ball is still in the net from the last one
+    if let Some(against) = reset.against {
+        reset.frames_left = reset.frames_left.saturating_sub(1);
+        if reset.frames_left > 0 {
+            return;
+        }
+        reset.against = None;
+        if rules.on_goal.kickoff {
+            kickoff.restart(against);
+        }
+        if rules.on_goal.ball {
+            for (index, ball) in entities.balls.iter().enumerate() {
+                if let Ok((mut ball_tf, ..)) = ball_query.get_mut(*ball) {
+                    ball_tf.translation = kickoff.ball_spot_at(index);
+                }
+            }
+        }
+        if rules.on_goal.players {
+            for (team, mut player_tf) in player_query.iter_mut() {
+                player_tf.translation = match team {
+                    Team::Home => PLAYER_SPAWN,
+                    Team::Away => AWAY_SPAWN,
+                };
+            }
+        }
+        return;
+    }
+
+    for &ball_entity in &entities.balls {
+        let Ok((ball_tf, ball_vel, ball_collider)) = ball_query.get(ball_entity) else {
+            continue;
+        };
+        // away defends the far goal at +z, home the near one
+        for (goal, against) in [
+            (entities.goal, Team::Away),
+            (entities.home_goal, Team::Home),
+        ] {
+            let Some(goal_entity) = goal else {
+                continue;
+            };
+            let Ok(goal_tf) = goal_query.get(goal_entity) else {
+                continue;
+            };
+
+            // a hard shot can clear the sensor in one frame, so sweep back along it
+            let goal_z = goal_tf.translation().z;
+            let past_line = |z: f32| match against {
+                Team::Away => z >= goal_z,
+                Team::Home => z <= goal_z,
+            };
+            let travel = ball_vel.linvel * TICK_DT;
+            let prev = ball_tf.translation - travel;
+            let only_goal = |e: Entity| e == goal_entity;
+            let scored = rapier.intersection_pair(ball_entity, goal_entity) == Some(true)
+                || (!past_line(prev.z)
+                    && past_line(ball_tf.translation.z)
+                    && rapier
+                        .cast_shape(
+                            prev,
+                            Quat::IDENTITY,
+                            travel,
+                            ball_collider,
+                            1.0,
+                            QueryFilter::new().predicate(&only_goal),
+                        )
+                        .is_some());
+            if !scored {
+                continue;
+            }
+
+            // touches and scripts come from local events that a rollback can't
+            // replay, so online goals go to the attacking side on the rules alone
+            let ctx = GoalContext {
+                scorer: against.other(),
+                goal_position: goal_tf.translation(),
+                touch: Touch::default(),
+                clock: &clock,
+                score: &score,
+            };
+            if !rules.allows(&ctx) {
+                continue;
+            }
+            let points = rules.points(&ctx);
+            score.add(against.other(), points);
+            reset.against = Some(against);
+            reset.frames_left = (GOAL_RESET_DELAY / TICK_DT).round() as u32;
+            return;
+        }
+    }
+}
+
+// popups, stats and replays still listen for goal events, so goals are announced
+// from the rolled back score moving up; one undone by a rollback stays announced
+fn net_goal_events_system(
+    score: Res<Score>,
+    mut last: Local<(u32, u32)>,
+    mut goal_events: EventWriter<GoalScoredEvent>,
+    entities: Res<MatchEntities>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+) {
+    let totals = (score.of(Team::Home), score.of(Team::Away));
+    for (team, total, before) in [
+        (Team::Home, totals.0, last.0),
+        (Team::Away, totals.1, last.1),
+    ] {
+        if total <= before {
+            continue;
+        }
+        let Some(&ball) = entities.balls.first() else {
+            continue;
+        };
+        let Ok((ball_tf, ball_vel)) = ball_query.get(ball) else {
+            continue;
+        };
+        goal_events.send(GoalScoredEvent {
+            team,
+            against: team.other(),
+            points: total - before,
+            ball,
+            position: ball_tf.translation,
+            ball_velocity: ball_vel.linvel,
+        });
+    }
+    *last = totals;
+}
+
+fn net_save_system(mut physics: ResMut<PhysicsRollback>, rapier: Res<RapierContext>) {
+    match bincode::serialize(&*rapier) {
+        Ok(bytes) => physics.rapier = bytes,
+        Err(err) => error!("Failed to save physics for rollback: {}", err),
+    }
+}
+
+fn net_events_system(mut session: ResMut<Session<GgrsConfig>>) {
+    if let Session::P2PSession(session) = session.as_mut() {
+        for event in session.events() {
+            info!("Net session: {:?}", event);
+        }
+    }
+}
+
+fn net_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    session: Res<Session<GgrsConfig>>,
+    peer: Res<NetPeer>,
+) {
+    let Session::P2PSession(session) = session.as_ref() else {
+        return;
+    };
+    egui::Area::new("net")
+        .anchor(egui::Align2::LEFT_TOP, [16.0, 60.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            if session.current_state() == SessionState::Synchronizing {
+                ui.label(format!("Waiting for {}", peer.0));
+            }
+            ui.label(format!("Rollback frame {}", session.current_frame()));
+        });
+}
+
+// what the online panel has typed so far
+#[derive(Resource)]
+struct NetMenu {
+    local: String,
+    peer: String,
+    room: String,
+    error: Option<String>,
+}
+
+impl Default for NetMenu {
+    fn default() -> Self {
+        Self {
+            local: "0.0.0.0:7777".to_string(),
+            peer: String::new(),
+            room: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl NetMenu {
+    fn config(&self, team: Team) -> Result<NetConfig, String> {
+        let local: SocketAddr = self
+            .local
+            .trim()
+            .parse()
+            .map_err(|_| format!("\"{}\" is not an address like 0.0.0.0:7777", self.local))?;
+        let peer: SocketAddr =
+            self.peer.trim().parse().map_err(|_| {
+                format!("\"{}\" is not an address like 192.168.1.20:7777", self.peer)
+            })?;
+        let room = self.room.trim();
+        Ok(NetConfig {
+            local,
+            peer,
+            team,
+            room: (!room.is_empty()).then(|| room.to_string()),
+        })
+    }
+}
+
+// everything this copy was started with apart from an earlier session, so arena,
+// mode and window flags carry over to the match
+fn forwarded_args() -> Vec<String> {
+    let mut args = Vec::new();
+    let mut rest = std::env::args().skip(1);
+    while let Some(arg) = rest.next() {
+        let values = match arg.as_str() {
+            "--net" | "--lockstep" => 3,
+            "--room" => 1,
+            _ => {
+                args.push(arg);
+                continue;
+            }
+        };
+        for _ in 0..values {
+            rest.next();
+        }
+    }
+    args
+}
+
+// sessions are set up when the app is built, so connecting starts a fresh copy
+// of the game with the session on its command line
+fn launch(config: &NetConfig) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    std::process::Command::new(exe)
+        .args(forwarded_args())
+        .args(config.to_args())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+fn net_menu_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut menu: ResMut<NetMenu>,
+    mut exit_events: EventWriter<bevy::app::AppExit>,
+) {
+    let mut chosen = None;
+    egui::Window::new("Online")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Grid::new("net_menu").show(ui, |ui| {
+                ui.label("Listen on");
+                ui.text_edit_singleline(&mut menu.local);
+                ui.end_row();
+                ui.label("Peer address");
+                ui.text_edit_singleline(&mut menu.peer);
+                ui.end_row();
+                ui.label("Room code");
+                ui.add(egui::TextEdit::singleline(&mut menu.room).char_limit(ROOM_CODE_LEN));
+                ui.end_row();
+            });
+            // whoever hosts plays home, both sides need the same room code
+            ui.horizontal(|ui| {
+                if ui.button("Host").clicked() {
+                    chosen = Some(Team::Home);
+                }
+                if ui.button("Join").clicked() {
+                    chosen = Some(Team::Away);
+                }
+            });
+            if let Some(error) = &menu.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+
+    let Some(team) = chosen else {
+        return;
+    };
+    match menu.config(team).and_then(|config| launch(&config)) {
+        Ok(()) => exit_events.send(bevy::app::AppExit),
+        Err(err) => menu.error = Some(err),
+    }
+}
@@ -0,0 +1,208 @@
+use super::arena::Arena;
+use super::difficulty::Difficulty;
+use super::game_state::GameState;
+use super::goalkeeper::closest_threat;
+use super::marks::{Ball, EnemyGoal, MatchEntity, Opponent, Team};
+use super::mutators::Mutators;
+use super::nametags::NameTag;
+use super::player::{kick_direction, KickEvent, PLAYER_SPEED};
+use super::progression::ShotKind;
+use super::{MatchAssets, MatchEntities, BOARD_DIM};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+// a touch slower than the player at the middle difficulty
+const OPPONENT_SPEED: f32 = PLAYER_SPEED * 0.8;
+const OPPONENT_KICK_POWER: f32 = 12.0;
+const OPPONENT_KICK_COOLDOWN: f32 = 0.6;
+// how far in front of the goal the opponent waits when the ball is far away
+const GUARD_DEPTH: f32 = 3.0;
+// the ball counts as a threat once it is this deep into the opponent's half
+const THREAT_DEPTH: f32 = 2.0;
+
+pub struct OpponentPlugin;
+
+impl Plugin for OpponentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_opponent)
+            .add_system(opponent_spawn_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(opponent_plan_system)
+                    .with_system(opponent_steer_system.after(opponent_plan_system))
+                    .with_system(opponent_kick_system.after(opponent_steer_system)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct OpponentBrain {
+    target: Vec3,
+    // the target is only re-planned once per reaction window
+    reaction: Timer,
+    kick_cooldown: Timer,
+}
+
+#[derive(Resource)]
+struct OpponentMaterial(Handle<StandardMaterial>);
+
+fn setup_opponent(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(OpponentMaterial(materials.add(StandardMaterial {
+        base_color: Color::TEAL,
+        ..default()
+    })));
+}
+
+fn away_spawn(arena: &Arena) -> Vec3 {
+    arena
+        .descriptor
+        .spawns
+        .iter()
+        .find(|spawn| spawn.team == Team::Away)
+        .map(|spawn| Vec3::from(spawn.position))
+        .unwrap_or(Vec3::new(0.0, 1.1, BOARD_DIM.2 / 2.0 - 1.5))
+}
+
+// rematches despawn every match entity, so the opponent simply comes back whenever it's missing
+fn opponent_spawn_system(
+    mut commands: Commands,
+    opponent_query: Query<(), With<Opponent>>,
+    match_assets: Option<Res<MatchAssets>>,
+    material: Res<OpponentMaterial>,
+    arena: Res<Arena>,
+    entities: Res<MatchEntities>,
+) {
+    let Some(match_assets) = match_assets else {
+        return;
+    };
+    // nobody to play against until the menu starts the first match
+    if !opponent_query.is_empty() || entities.player.is_none() {
+        return;
+    }
+
+    let spawn = away_spawn(&arena);
+    commands.spawn((
+        PbrBundle {
+            mesh: match_assets.player_mesh.clone(),
+            material: material.0.clone(),
+            transform: Transform::from_translation(spawn),
+            ..default()
+        },
+        Collider::capsule_y(0.5, 0.5),
+        RigidBody::KinematicPositionBased,
+        LockedAxes::TRANSLATION_LOCKED_Y,
+        Restitution::coefficient(1.5),
+        KinematicCharacterController {
+            autostep: None,
+            ..default()
+        },
+        OpponentBrain {
+            target: spawn,
+            reaction: Timer::from_seconds(0.0, TimerMode::Once),
+            kick_cooldown: Timer::from_seconds(OPPONENT_KICK_COOLDOWN, TimerMode::Once),
+        },
+        Opponent,
+        Team::Away,
+        MatchEntity,
+        NameTag("CPU".to_string()),
+        Name::new("opponent"),
+    ));
+}
+
+// chase the ball when it threatens the goal, otherwise sit between it and the goal
+fn opponent_plan_system(
+    mut opponent_query: Query<(&mut OpponentBrain, &Transform), Without<Ball>>,
+    ball_query: Query<(&Transform, &Velocity), With<Ball>>,
+    goal_query: Query<&GlobalTransform, With<EnemyGoal>>,
+    difficulty: Res<Difficulty>,
+    time: Res<Time>,
+) {
+    let Some((ball_tf, ball_vel)) = closest_threat(ball_query.iter()) else {
+        return;
+    };
+    let Ok(goal_tf) = goal_query.get_single() else {
+        return;
+    };
+    let goal = goal_tf.translation();
+    let speed = OPPONENT_SPEED * difficulty.speed_scale();
+
+    for (mut brain, tf) in opponent_query.iter_mut() {
+        brain.reaction.tick(time.delta());
+        if !brain.reaction.finished() {
+            continue;
+        }
+        brain.reaction = Timer::from_seconds(difficulty.reaction_time(), TimerMode::Once);
+
+        let ball = ball_tf.translation;
+        let threatened = ball.z > THREAT_DEPTH || ball_vel.linvel.z > 1.0;
+        let mut target = if threatened {
+            // lead the ball by roughly the time it takes to get there
+            let lead = tf.translation.distance(ball) / speed;
+            let ahead = ball + ball_vel.linvel * lead.min(1.0);
+            // come at it from the goal side so contact knocks it back up the pitch
+            ahead + (goal - ahead).normalize_or_zero() * 0.8
+        } else {
+            goal + (ball - goal).normalize_or_zero() * GUARD_DEPTH
+        };
+        target.y = tf.translation.y;
+        brain.target = target;
+    }
+}
+
+fn opponent_steer_system(
+    mut opponent_query: Query<(
+        &OpponentBrain,
+        &Transform,
+        &mut KinematicCharacterController,
+    )>,
+    difficulty: Res<Difficulty>,
+    mutators: Res<Mutators>,
+    time: Res<Time>,
+) {
+    let speed = OPPONENT_SPEED * difficulty.speed_scale() * mutators.speed_scale();
+    for (brain, tf, mut ctrl) in opponent_query.iter_mut() {
+        let mut offset = brain.target - tf.translation;
+        offset.y = 0.0;
+        let step = speed * time.delta_seconds();
+        ctrl.translation = Some(offset.clamp_length_max(step));
+    }
+}
+
+// clears the ball back toward the home end whenever it's in reach
+fn opponent_kick_system(
+    mut commands: Commands,
+    mut opponent_query: Query<(&mut OpponentBrain, &Transform), Without<Ball>>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+    difficulty: Res<Difficulty>,
+    mut kick_events: EventWriter<KickEvent>,
+    time: Res<Time>,
+) {
+    for (mut brain, tf) in opponent_query.iter_mut() {
+        brain.kick_cooldown.tick(time.delta());
+        if !brain.kick_cooldown.finished() {
+            continue;
+        }
+        for (ball_entity, ball_tf) in ball_query.iter() {
+            let Some(direction) = kick_direction(tf.translation, ball_tf.translation) else {
+                continue;
+            };
+            // only kick when the clearance doesn't send it into its own goal
+            if direction.z > 0.0 {
+                continue;
+            }
+            let power = OPPONENT_KICK_POWER * difficulty.speed_scale();
+            let impulse = (direction + Vec3::Y * 0.3).normalize() * power;
+            commands.entity(ball_entity).insert(ExternalImpulse {
+                impulse,
+                torque_impulse: Vec3::ZERO,
+            });
+            kick_events.send(KickEvent {
+                ball: ball_entity,
+                team: Team::Away,
+                shot: ShotKind::Standard,
+                impulse,
+            });
+            brain.kick_cooldown.reset();
+        }
+    }
+}
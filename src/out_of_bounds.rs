@@ -0,0 +1,165 @@
+use super::arena::{Arena, PieceKind};
+use super::game_state::GameState;
+use super::marks::{Ball, Team};
+use super::rules::LastTouch;
+use super::worldtext::{self, WorldText};
+use super::{GoalReset, MatchEntities, BOARD_DIM};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const BALL_RADIUS: f32 = 0.5;
+// below this the ball has fallen off the world
+const FALL_DEPTH: f32 = -5.0;
+// restarts are placed this far in from the lines
+const LINE_INSET: f32 = 0.6;
+// goal kicks go from the edge of the box
+const GOAL_KICK_DEPTH: f32 = 2.5;
+const RESTART_HEIGHT: f32 = 1.0;
+
+pub struct OutOfBoundsPlugin;
+
+impl Plugin for OutOfBoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BallOutEvent>().add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(out_of_bounds_system),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Restart {
+    ThrowIn,
+    Corner,
+    GoalKick,
+}
+
+impl Restart {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Restart::ThrowIn => "Throw-in",
+            Restart::Corner => "Corner",
+            Restart::GoalKick => "Goal kick",
+        }
+    }
+}
+
+// the ball went out and was put back at `spot` for `team` to restart
+pub struct BallOutEvent {
+    pub restart: Restart,
+    pub team: Team,
+    pub position: Vec3,
+    pub spot: Vec3,
+}
+
+// which restart a ball leaving at `position` earns, and where it is taken from
+fn restart_for(position: Vec3, last: Option<Team>) -> Option<(Restart, Team, Vec3)> {
+    let (width, _, length) = BOARD_DIM;
+    let half = Vec2::new(width, length) / 2.0;
+    // nobody touched it yet, treat it as the home side's
+    let last = last.unwrap_or(Team::Home);
+
+    if position.x.abs() > half.x + BALL_RADIUS {
+        let x = position.x.signum() * (half.x - LINE_INSET);
+        let z = position.z.clamp(-half.y + LINE_INSET, half.y - LINE_INSET);
+        return Some((
+            Restart::ThrowIn,
+            last.other(),
+            Vec3::new(x, RESTART_HEIGHT, z),
+        ));
+    }
+    if position.z.abs() <= half.y + BALL_RADIUS {
+        return None;
+    }
+    // home defends the -z end, away the +z end
+    let defending = if position.z > 0.0 {
+        Team::Away
+    } else {
+        Team::Home
+    };
+    let end = position.z.signum() * half.y;
+    if last == defending {
+        let x = position.x.signum() * (half.x - LINE_INSET);
+        let z = end - end.signum() * LINE_INSET;
+        Some((
+            Restart::Corner,
+            defending.other(),
+            Vec3::new(x, RESTART_HEIGHT, z),
+        ))
+    } else {
+        let z = end - end.signum() * GOAL_KICK_DEPTH;
+        Some((
+            Restart::GoalKick,
+            defending,
+            Vec3::new(0.0, RESTART_HEIGHT, z),
+        ))
+    }
+}
+
+fn out_of_bounds_system(
+    mut commands: Commands,
+    mut ball_query: Query<(&mut Transform, &mut Velocity), With<Ball>>,
+    mut out_events: EventWriter<BallOutEvent>,
+    entities: Res<MatchEntities>,
+    arena: Res<Arena>,
+    touch: Res<LastTouch>,
+    goal_reset: Res<GoalReset>,
+) {
+    // a scored ball sits in the net until the goal reset moves it
+    if goal_reset.pending() {
+        return;
+    }
+    // every ball goes back in on its own, multiball included
+    for &ball in &entities.balls {
+        let Ok((mut ball_tf, mut velocity)) = ball_query.get_mut(ball) else {
+            continue;
+        };
+        let position = ball_tf.translation;
+
+        // past the line but inside a net is a goal in the making, not an out
+        let in_net = arena
+            .descriptor
+            .pieces
+            .iter()
+            .filter(|piece| piece.kind == PieceKind::GoalNet)
+            .any(|piece| {
+                let local = piece
+                    .transform()
+                    .compute_matrix()
+                    .inverse()
+                    .transform_point3(position);
+                local.abs().cmple(Vec3::from(piece.half_extents)).all()
+            });
+        if in_net {
+            continue;
+        }
+
+        let restart = if position.y < FALL_DEPTH {
+            // fell through somewhere, whoever didn't touch it last throws it back in
+            let side = if position.x < 0.0 { -1.0 } else { 1.0 };
+            let probe = Vec3::new(side * (BOARD_DIM.0 / 2.0 + 1.0), 0.0, position.z);
+            restart_for(probe, touch.of(ball).team)
+        } else {
+            restart_for(position, touch.of(ball).team)
+        };
+        let Some((restart, team, spot)) = restart else {
+            continue;
+        };
+
+        info!("{} to {}", restart.name(), team.name());
+        worldtext::spawn_popup(
+            &mut commands,
+            spot,
+            WorldText::new(format!("{} - {}", restart.name(), team.name())),
+            1.5,
+        );
+        ball_tf.translation = spot;
+        velocity.linvel = Vec3::ZERO;
+        velocity.angvel = Vec3::ZERO;
+        out_events.send(BallOutEvent {
+            restart,
+            team,
+            position,
+            spot,
+        });
+    }
+}
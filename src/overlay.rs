@@ -0,0 +1,92 @@
+use super::broadcast::scorebug_ui;
+use super::cli::StartupArgs;
+use super::Score;
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::window::{CreateWindow, WindowId};
+use bevy_egui::{egui, EguiContext};
+
+const CHROMA_GREEN: Color = Color::rgb(0.0, 1.0, 0.0);
+
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        // --overlay for a transparent window, --overlay-chroma where the
+        // compositor can't do per-pixel alpha and OBS keys out green instead
+        let Some(args) = app.world.get_resource::<StartupArgs>() else {
+            return;
+        };
+        let background = if args.overlay {
+            Color::NONE
+        } else if args.overlay_chroma {
+            CHROMA_GREEN
+        } else {
+            return;
+        };
+
+        app.insert_resource(StreamerOverlay {
+            window: WindowId::new(),
+            background,
+        })
+        .add_startup_system(setup_overlay)
+        .add_system(overlay_ui_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct StreamerOverlay {
+    pub window: WindowId,
+    pub background: Color,
+}
+
+fn setup_overlay(
+    mut commands: Commands,
+    mut create_window_events: EventWriter<CreateWindow>,
+    overlay: Res<StreamerOverlay>,
+) {
+    create_window_events.send(CreateWindow {
+        id: overlay.window,
+        descriptor: WindowDescriptor {
+            width: 480.0,
+            height: 120.0,
+            title: "Playground overlay".to_string(),
+            transparent: overlay.background.a() == 0.0,
+            decorations: overlay.background.a() != 0.0,
+            ..default()
+        },
+    });
+
+    // a 2d camera draws no world geometry, only the clear color under egui
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(overlay.window),
+                priority: 3,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(overlay.background),
+            },
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        Name::new("OverlayCamera"),
+    ));
+}
+
+fn overlay_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    overlay: Res<StreamerOverlay>,
+    score: Res<Score>,
+) {
+    let Some(ctx) = egui_context.try_ctx_for_window_mut(overlay.window) else {
+        return;
+    };
+
+    // areas paint no background, so everything but the scorebug stays clear
+    egui::Area::new("overlay_scorebug")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| scorebug_ui(ui, &score));
+}
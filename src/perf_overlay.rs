@@ -0,0 +1,132 @@
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticId, Diagnostics, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+
+// milliseconds rapier spent in its last step
+pub const PHYSICS_STEP: DiagnosticId =
+    DiagnosticId::from_u128(0x6a1f_3c02_9b4e_4d17_a5c8_2e71_0f93_b6d4);
+const HISTORY_LENGTH: usize = 20;
+
+pub struct PerfOverlayPlugin;
+
+impl Plugin for PerfOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .add_plugin(EntityCountDiagnosticsPlugin)
+            .insert_resource(PerfOverlay(false))
+            .init_resource::<PhysicsCounters>()
+            .add_startup_system(setup_physics_diagnostic)
+            .add_system(perf_toggle_system)
+            .add_system_to_stage(CoreStage::Last, physics_diagnostic_system)
+            .add_system(perf_ui_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct PerfOverlay(pub bool);
+
+// rapier's counters are a single switch shared by the overlay and the bench, they
+// stay on while anything still reads them
+#[derive(Resource, Default)]
+pub struct PhysicsCounters {
+    users: u32,
+}
+
+impl PhysicsCounters {
+    pub fn acquire(&mut self, rapier_context: &mut RapierContext) {
+        self.users += 1;
+        rapier_context.pipeline.counters.enable();
+    }
+
+    pub fn release(&mut self, rapier_context: &mut RapierContext) {
+        self.users = self.users.saturating_sub(1);
+        if self.users == 0 {
+            rapier_context.pipeline.counters.disable();
+        }
+    }
+}
+
+fn setup_physics_diagnostic(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics
+        .add(Diagnostic::new(PHYSICS_STEP, "physics_step", HISTORY_LENGTH).with_suffix("ms"));
+}
+
+// F3, rapier only times its steps while the overlay or the bench is up
+fn perf_toggle_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut overlay: ResMut<PerfOverlay>,
+    mut counters: ResMut<PhysicsCounters>,
+    mut rapier_context: ResMut<RapierContext>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.0 = !overlay.0;
+    if overlay.0 {
+        counters.acquire(&mut rapier_context);
+    } else {
+        counters.release(&mut rapier_context);
+    }
+}
+
+fn physics_diagnostic_system(
+    mut diagnostics: ResMut<Diagnostics>,
+    overlay: Res<PerfOverlay>,
+    rapier_context: Res<RapierContext>,
+) {
+    if !overlay.0 {
+        return;
+    }
+    diagnostics.add_measurement(PHYSICS_STEP, || {
+        rapier_context.pipeline.counters.step_time()
+    });
+}
+
+fn perf_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    overlay: Res<PerfOverlay>,
+    diagnostics: Res<Diagnostics>,
+) {
+    if !overlay.0 {
+        return;
+    }
+    let smoothed = |id: DiagnosticId| diagnostics.get(id).and_then(|d| d.smoothed());
+    let row = |value: Option<f64>, precision: usize, suffix: &str| match value {
+        Some(value) => format!("{:.*} {}", precision, value, suffix),
+        None => "–".to_string(),
+    };
+
+    egui::Area::new("perf_overlay")
+        .anchor(egui::Align2::LEFT_TOP, [8.0, 8.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                egui::Grid::new("perf_overlay_grid").show(ui, |ui| {
+                    ui.label("fps");
+                    ui.label(row(smoothed(FrameTimeDiagnosticsPlugin::FPS), 0, ""));
+                    ui.end_row();
+                    ui.label("frame");
+                    ui.label(row(
+                        smoothed(FrameTimeDiagnosticsPlugin::FRAME_TIME).map(|s| s * 1000.0),
+                        2,
+                        "ms",
+                    ));
+                    ui.end_row();
+                    ui.label("entities");
+                    ui.label(row(
+                        diagnostics
+                            .get(EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+                            .and_then(|d| d.value()),
+                        0,
+                        "",
+                    ));
+                    ui.end_row();
+                    ui.label("physics");
+                    ui.label(row(smoothed(PHYSICS_STEP), 2, "ms"));
+                    ui.end_row();
+                });
+            });
+        });
+}
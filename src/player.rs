@@ -1,50 +1,394 @@
-use super::marks::Player;
+use super::boost_pads::SpeedBoost;
+use super::config::GameConfig;
+use super::determinism::GameRng;
+use super::game_state::GameState;
+use super::input::{LocalInputs, PlayerInput};
+use super::marks::{Ball, Player, PlayerIndex, Team};
+use super::mutators::Mutators;
+use super::progression::{SelectedShot, ShotKind};
+use super::scripting::Scripts;
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use std::f32::consts::FRAC_PI_2;
+use rand::Rng;
+use std::time::Duration;
+
+pub const PLAYER_SPEED: f32 = 8.0;
+const KICK_RANGE: f32 = 1.6;
+// seconds of holding kick to reach full power
+const CHARGE_TIME: f32 = 1.5;
+// a tap still moves the ball, a full charge hits harder than the tuned shot
+const MIN_KICK_SCALE: f32 = 0.4;
+const MAX_KICK_SCALE: f32 = 1.6;
+// sidespin from bending a shot with the aim stick, it curls back toward the facing line
+const SLICE_SPIN: f32 = 0.5;
+const SPRINT_SCALE: f32 = 1.6;
+// a full bar lasts two seconds of sprinting and takes four of walking to refill
+const STAMINA_DRAIN: f32 = 0.5;
+const STAMINA_REGEN: f32 = 0.25;
+const DASH_TIME: f32 = 0.3;
+const DASH_SPEED: f32 = PLAYER_SPEED * 2.5;
+const DASH_COOLDOWN: f32 = 1.5;
+// a lunge reaches further than a standing kick, far enough to get a toe to the ball
+const DASH_KICK_RANGE: f32 = 2.4;
+const POKE_POWER: f32 = 6.0;
+// enough to clear the ball at its resting height
+const JUMP_SPEED: f32 = 6.0;
+// how far above or below the player's centre a kick still connects, a jump lifts it
+// to balls bouncing over the capsule
+const KICK_HEIGHT: f32 = 1.2;
+
+pub struct KickEvent {
+    pub ball: Entity,
+    pub team: Team,
+    pub shot: ShotKind,
+    pub impulse: Vec3,
+}
+
+// the way the player last moved, kicks go this way rather than wherever the ball sits
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Facing(pub Vec3);
+
+impl Default for Facing {
+    fn default() -> Self {
+        // toward the enemy goal
+        Self(Vec3::Z)
+    }
+}
+
+impl Facing {
+    // up the pitch for the home side, down it for the away side
+    pub fn toward_goal(team: Team) -> Self {
+        match team {
+            Team::Home => Self(Vec3::Z),
+            Team::Away => Self(-Vec3::Z),
+        }
+    }
+}
+
+// how long kick has been held, None while it's up
+#[derive(Component, Default)]
+pub struct KickCharge {
+    held: Option<f32>,
+}
+
+impl KickCharge {
+    pub fn fraction(&self) -> Option<f32> {
+        self.held.map(|held| held / CHARGE_TIME)
+    }
+}
+
+// sprint left in the legs, from 0 to 1
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Stamina(pub f32);
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+// the lunge in progress, its direction and how long it has left
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Dash {
+    lunge: Option<(Vec3, f32)>,
+    // a dash only pokes the ball once
+    poked: bool,
+    cooldown: Timer,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(DASH_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self {
+            lunge: None,
+            poked: false,
+            cooldown,
+        }
+    }
+}
+
+impl Dash {
+    pub fn active(&self) -> bool {
+        self.lunge.is_some()
+    }
+
+    pub fn kick_range(&self) -> f32 {
+        if self.active() {
+            DASH_KICK_RANGE
+        } else {
+            KICK_RANGE
+        }
+    }
+}
+
+// upward speed, gravity takes it away again until the player lands
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Jump {
+    pub velocity: f32,
+}
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(move_player).add_system(player_kick);
+        app.add_event::<KickEvent>().add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(move_player)
+                .with_system(kick_ball.after(move_player))
+                .with_system(dash_poke_system.after(move_player)),
+        );
+    }
+}
+
+// the first player reads PlayerInput itself, which is what input tapes override,
+// everyone else reads their slot of LocalInputs
+pub fn player_input<'a>(
+    index: &PlayerIndex,
+    first: &'a PlayerInput,
+    locals: &'a LocalInputs,
+) -> Option<&'a PlayerInput> {
+    match index.0 {
+        0 => Some(first),
+        slot => locals.0.get(slot),
     }
 }
 
-fn move_player(
-    mut player_query: Query<&mut KinematicCharacterController, With<Player>>,
-    keyboard: Res<Input<KeyCode>>,
+pub fn move_player(
+    mut player_query: Query<
+        (
+            &PlayerIndex,
+            &mut KinematicCharacterController,
+            Option<&KinematicCharacterControllerOutput>,
+            &mut Facing,
+            &mut Stamina,
+            &mut Dash,
+            &mut Jump,
+            Option<&SpeedBoost>,
+        ),
+        With<Player>,
+    >,
+    input: Res<PlayerInput>,
+    locals: Res<LocalInputs>,
+    rapier_config: Res<RapierConfiguration>,
+    mutators: Res<Mutators>,
     time: Res<Time>,
 ) {
-    let mut player_ctrl = player_query.single_mut();
-    let mut direction = Vec3::new(0.0, 0.0, 0.0);
-
-    if keyboard.pressed(KeyCode::W) {
-        direction += Vec3::Z;
+    for (index, mut player_ctrl, output, mut facing, mut stamina, mut dash, mut jump, boost) in
+        player_query.iter_mut()
+    {
+        let Some(input) = player_input(index, &input, &locals) else {
+            player_ctrl.translation = None;
+            continue;
+        };
+        let grounded = output.map_or(true, |output| output.grounded);
+        let speed_scale = boost.map_or(1.0, SpeedBoost::scale) * mutators.speed_scale();
+        let motion = step_movement(
+            input,
+            time.delta_seconds(),
+            rapier_config.gravity.y,
+            speed_scale,
+            grounded,
+            (&mut facing, &mut stamina, &mut dash, &mut jump),
+        );
+        player_ctrl.translation = Some(motion);
     }
-    if keyboard.pressed(KeyCode::S) {
-        direction -= Vec3::Z;
+}
+
+// one step of the player's own motion; local play and online sessions both move
+// through here so sprint, dash and jump behave the same everywhere
+pub fn step_movement(
+    input: &PlayerInput,
+    dt: f32,
+    gravity: f32,
+    speed_scale: f32,
+    grounded: bool,
+    (facing, stamina, dash, jump): (&mut Facing, &mut Stamina, &mut Dash, &mut Jump),
+) -> Vec3 {
+    let direction = input.world_movement();
+
+    // a dash lunges the way the stick points, or straight ahead without it
+    dash.cooldown.tick(Duration::from_secs_f32(dt));
+    if input.dash_pressed && !dash.active() && dash.cooldown.finished() {
+        let flat = Vec3::new(direction.x, 0.0, direction.z).normalize_or_zero();
+        let lunge = if flat == Vec3::ZERO { facing.0 } else { flat };
+        dash.lunge = Some((lunge, DASH_TIME));
+        dash.poked = false;
+        facing.0 = lunge;
     }
-    if keyboard.pressed(KeyCode::A) {
-        direction += Vec3::X;
+    let mut motion = if let Some((lunge, left)) = &mut dash.lunge {
+        let motion = *lunge * dt * DASH_SPEED;
+        *left -= dt;
+        if *left <= 0.0 {
+            dash.lunge = None;
+            dash.cooldown.reset();
+        }
+        motion
+    } else {
+        // sprinting on the spot costs nothing, only covering ground drains the bar
+        let sprinting = input.sprint && direction != Vec3::ZERO && stamina.0 > 0.0;
+        let speed = if sprinting {
+            stamina.0 = (stamina.0 - STAMINA_DRAIN * dt).max(0.0);
+            PLAYER_SPEED * SPRINT_SCALE
+        } else {
+            stamina.0 = (stamina.0 + STAMINA_REGEN * dt).min(1.0);
+            PLAYER_SPEED
+        };
+        let flat = Vec3::new(direction.x, 0.0, direction.z);
+        if flat != Vec3::ZERO {
+            facing.0 = flat.normalize();
+        }
+        direction * dt * speed * speed_scale
+    };
+
+    // the controller has no gravity of its own, the player falls with the ball's
+    jump.velocity += gravity * dt;
+    if grounded && jump.velocity < 0.0 {
+        jump.velocity = 0.0;
     }
-    if keyboard.pressed(KeyCode::D) {
-        direction -= Vec3::X;
+    if grounded && input.jump_pressed {
+        jump.velocity = JUMP_SPEED;
     }
+    motion.y = jump.velocity * dt;
+    motion
+}
 
-    player_ctrl.translation = Some(direction * time.delta_seconds() * 8.0);
+fn kick_ball(
+    mut commands: Commands,
+    mut player_query: Query<
+        (&PlayerIndex, &Team, &Transform, &Facing, &Dash, &mut KickCharge),
+        With<Player>,
+    >,
+    mut ball_query: Query<(Entity, &Transform, &mut Velocity), (With<Ball>, Without<Player>)>,
+    selected: Res<SelectedShot>,
+    input: Res<PlayerInput>,
+    locals: Res<LocalInputs>,
+    config: Res<GameConfig>,
+    scripts: Option<Res<Scripts>>,
+    mut kick_events: EventWriter<KickEvent>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    for (index, team, player_tf, facing, dash, mut charge) in player_query.iter_mut() {
+        let Some(input) = player_input(index, &input, &locals) else {
+            continue;
+        };
+        if input.kick_pressed {
+            charge.held = Some(0.0);
+        }
+        if let (Some(held), true) = (charge.held.as_mut(), input.kick) {
+            *held = (*held + time.delta_seconds()).min(CHARGE_TIME);
+        }
+        // the kick goes off on release, as hard as it was charged
+        if !input.kick_released {
+            continue;
+        }
+        let Some(fraction) = charge.fraction() else {
+            continue;
+        };
+        charge.held = None;
+        let scale = MIN_KICK_SCALE + (MAX_KICK_SCALE - MIN_KICK_SCALE) * fraction;
+
+        for (ball_entity, ball_tf, mut ball_vel) in ball_query.iter_mut() {
+            let Some(to_ball) =
+                reach_direction(player_tf.translation, ball_tf.translation, dash.kick_range())
+            else {
+                continue;
+            };
+            // a ball behind the player can't be kicked forward through them
+            if to_ball.dot(facing.0) <= 0.0 {
+                continue;
+            }
+            let mut direction = facing.0;
+            // the aim stick bends the shot away from the straight line through the ball
+            if input.aim != Vec2::ZERO {
+                direction = (direction + input.world_aim()).normalize_or_zero();
+            }
+            let slice = Vec3::Y * -facing.0.cross(direction).y * SLICE_SPIN;
+            let mut shot = selected.0.params();
+            shot.power *= config.kick.strength * scale;
+            if let Some(scripts) = &scripts {
+                shot.power *= scripts.pre_kick(shot.power);
+            }
+
+            let mut impulse = (direction + Vec3::Y * shot.loft).normalize() * shot.power;
+            if shot.wobble > 0.0 {
+                let side = direction.cross(Vec3::Y);
+                impulse += side * rng.rng.gen_range(-shot.wobble..shot.wobble);
+                ball_vel.angvel = Vec3::ZERO;
+            }
+
+            commands.entity(ball_entity).insert(ExternalImpulse {
+                impulse,
+                torque_impulse: direction.cross(Vec3::Y) * -shot.topspin + slice,
+            });
+            kick_events.send(KickEvent {
+                ball: ball_entity,
+                team: *team,
+                shot: selected.0,
+                impulse,
+            });
+        }
+    }
 }
 
-fn player_kick(
-    mut player_query: Query<&mut Transform, With<Player>>,
-    keyboard: Res<Input<KeyCode>>,
+fn dash_poke_system(
+    mut commands: Commands,
+    mut player_query: Query<(&Transform, &mut Dash), With<Player>>,
+    ball_query: Query<(Entity, &Transform), (With<Ball>, Without<Player>)>,
 ) {
-    let mut player_tf = player_query.single_mut();
+    for (player_tf, mut dash) in player_query.iter_mut() {
+        dash_poke(&mut commands, player_tf, &mut dash, ball_query.iter());
+    }
+}
 
-    if keyboard.just_pressed(KeyCode::Space) {
-        player_tf.rotate_x(-FRAC_PI_2);
+// a lunge that reaches the ball knocks it on along the dash, no kick needed
+pub fn dash_poke<'a>(
+    commands: &mut Commands,
+    player_tf: &Transform,
+    dash: &mut Dash,
+    balls: impl Iterator<Item = (Entity, &'a Transform)>,
+) {
+    let Some((lunge, _)) = dash.lunge else {
+        return;
+    };
+    if dash.poked {
+        return;
+    }
+    for (ball_entity, ball_tf) in balls {
+        let Some(to_ball) =
+            reach_direction(player_tf.translation, ball_tf.translation, DASH_KICK_RANGE)
+        else {
+            continue;
+        };
+        if to_ball.dot(lunge) <= 0.0 {
+            continue;
+        }
+        commands.entity(ball_entity).insert(ExternalImpulse {
+            impulse: (lunge + to_ball).normalize_or_zero() * POKE_POWER,
+            torque_impulse: Vec3::ZERO,
+        });
+        dash.poked = true;
     }
+}
 
-    if keyboard.just_released(KeyCode::Space) {
-        player_tf.rotate_x(FRAC_PI_2);
+// flat direction from the kicker to the ball, if the ball is close enough to reach
+pub fn kick_direction(player: Vec3, ball: Vec3) -> Option<Vec3> {
+    reach_direction(player, ball, KICK_RANGE)
+}
+
+pub fn reach_direction(player: Vec3, ball: Vec3, range: f32) -> Option<Vec3> {
+    let mut direction = ball - player;
+    if direction.y.abs() > KICK_HEIGHT {
+        return None;
+    }
+    direction.y = 0.0;
+    if direction.length() > range {
+        return None;
     }
+    Some(direction.normalize_or_zero())
 }
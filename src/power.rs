@@ -0,0 +1,94 @@
+use super::cli::StartupArgs;
+use super::config::GameConfig;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy::winit::{UpdateMode, WinitSettings};
+use bevy_rapier3d::prelude::*;
+
+pub struct PowerPlugin;
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        let args = app
+            .world
+            .get_resource::<StartupArgs>()
+            .cloned()
+            .unwrap_or_default();
+        app.insert_resource(PowerSaving::from_args(&args))
+            .add_system(power_saving_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct PowerSaving {
+    // --low-power, always throttled
+    pub low_power: bool,
+    // spectator roles don't need a full frame rate when nobody is looking
+    pub background_role: bool,
+    pub active: bool,
+    base_substeps: usize,
+}
+
+impl PowerSaving {
+    fn from_args(args: &StartupArgs) -> Self {
+        Self {
+            low_power: args.low_power,
+            background_role: args.broadcast,
+            active: false,
+            base_substeps: 1,
+        }
+    }
+}
+
+fn power_saving_system(
+    mut power: ResMut<PowerSaving>,
+    mut winit_settings: ResMut<WinitSettings>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut msaa: ResMut<Msaa>,
+    windows: Res<Windows>,
+    config: Res<GameConfig>,
+) {
+    let focused = windows.iter().any(|window| window.is_focused());
+    let reduce = power.low_power || (power.background_role && !focused);
+    if reduce == power.active {
+        return;
+    }
+    power.active = reduce;
+
+    // frame rate while saving power, physics substeps make up the difference
+    let reduced_dt = 1.0 / config.power.reduced_rate;
+    let max_wait = Duration::from_secs_f32(reduced_dt);
+    let mode = if reduce {
+        UpdateMode::Reactive { max_wait }
+    } else {
+        UpdateMode::Continuous
+    };
+    winit_settings.focused_mode = mode;
+    winit_settings.unfocused_mode = mode;
+
+    // shadows follow `active` through the settings
+    msaa.samples = if reduce { 1 } else { 4 };
+
+    // fewer, longer frames each take several fixed-size physics steps so the
+    // simulation keeps running at the same speed. Lockstep already drives the
+    // pipeline one tick at a time and is left alone
+    if let TimestepMode::Variable {
+        max_dt, substeps, ..
+    } = &mut rapier_config.timestep_mode
+    {
+        if reduce {
+            let steps = (reduced_dt / config.power.max_dt).ceil() as usize;
+            power.base_substeps = *substeps;
+            *max_dt = reduced_dt;
+            *substeps = power.base_substeps * steps;
+        } else {
+            *max_dt = config.power.max_dt;
+            *substeps = power.base_substeps;
+        }
+    }
+
+    info!(
+        "Power saving {}",
+        if reduce { "enabled" } else { "disabled" }
+    );
+}
@@ -0,0 +1,197 @@
+use super::arena::{
+    goal, Arena, ArenaDescriptor, ArenaPiece, ArenaPieceId, CullingSettings, LodSettings,
+    PieceKind, SkySettings, SpawnPoint,
+};
+use super::arena_validation::{self, Severity};
+use super::cli::StartupArgs;
+use super::marks::Team;
+use super::{MatchEntities, BOARD_DIM, GOAL_GAP, PLAYER_SPAWN};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::path::PathBuf;
+
+// seeds tried in a row before giving up on a layout
+const MAX_ATTEMPTS: u64 = 32;
+const MAX_PILLARS: usize = 4;
+// pillars keep this far from spawns and the center spot
+const PILLAR_CLEARANCE: f32 = 2.5;
+
+pub struct ProcArenaPlugin;
+
+impl Plugin for ProcArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RandomArenaEvent>()
+            .add_event::<SaveArenaEvent>()
+            .add_startup_system(seed_from_args_system)
+            .add_system(random_arena_system)
+            .add_system(save_arena_system.after(random_arena_system));
+    }
+}
+
+// swap the arena for a generated one, None picks a fresh seed
+pub struct RandomArenaEvent(pub Option<u64>);
+
+// write the generated arena next to the other arenas so a good one can be kept or edited
+pub struct SaveArenaEvent;
+
+// the seed the current arena was generated from, shown so it can be shared, and
+// whether the layout has been written to disk yet
+#[derive(Resource)]
+pub struct ArenaSeed {
+    pub seed: u64,
+    pub saved: bool,
+}
+
+// a layout for the seed, or for the first seed after it that passes validation
+pub fn generate(seed: u64) -> Option<(u64, ArenaDescriptor)> {
+    (seed..seed.saturating_add(MAX_ATTEMPTS)).find_map(|seed| {
+        let descriptor = layout(seed);
+        let issues = arena_validation::validate(&descriptor);
+        let playable = !issues.iter().any(|issue| issue.severity == Severity::Error);
+        playable.then_some((seed, descriptor))
+    })
+}
+
+fn layout(seed: u64) -> ArenaDescriptor {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (width, height, length) = BOARD_DIM;
+
+    let mut pieces = vec![ArenaPiece {
+        color: [0.0; 3],
+        ..ArenaPiece::new(
+            "floor",
+            PieceKind::Floor,
+            Vec3::ZERO,
+            Vec3::new(width, height, length) / 2.0,
+        )
+    }];
+
+    // the enemy goal slides along the end line and varies in width, the home one stays put
+    let gap = rng.gen_range(1.5..2.5);
+    let reach = width / 2.0 - gap - 1.0;
+    let goal_x = rng.gen_range(-reach..reach);
+    pieces.extend(goal(Team::Away, goal_x, gap - 0.5));
+    pieces.extend(goal(Team::Home, 0.0, GOAL_GAP - 0.5));
+
+    // side walls in segments of different heights, the inner face stays on the touchline
+    for (side, sign) in [("Left", 1.0), ("Right", -1.0)] {
+        let segments = rng.gen_range(2..=4);
+        let segment = length / segments as f32;
+        for i in 0..segments {
+            let thickness = rng.gen_range(0.3..0.7);
+            let wall_height = rng.gen_range(0.8..1.5);
+            let z = -length / 2.0 + segment * (i as f32 + 0.5);
+            pieces.push(ArenaPiece::new(
+                &format!("{}SideWall{}", side, i),
+                PieceKind::Wall,
+                Vec3::new(sign * (width / 2.0 + thickness), wall_height, z),
+                Vec3::new(thickness, wall_height, segment / 2.0),
+            ));
+        }
+    }
+
+    let away_spawn = Vec3::new(0.0, 1.1, length / 2.0 - 1.5);
+    let keep_clear = [PLAYER_SPAWN, away_spawn, Vec3::ZERO];
+    let pillars = rng.gen_range(0..=MAX_PILLARS);
+    for i in 0..pillars {
+        let position = Vec3::new(
+            rng.gen_range(-width / 2.0 + 1.0..width / 2.0 - 1.0),
+            1.0,
+            rng.gen_range(-length / 2.0 + 3.0..length / 2.0 - 3.0),
+        );
+        let crowded = keep_clear.iter().any(|spot| {
+            Vec2::new(spot.x, spot.z).distance(Vec2::new(position.x, position.z)) < PILLAR_CLEARANCE
+        });
+        if crowded {
+            continue;
+        }
+        let size = rng.gen_range(0.3..0.6);
+        pieces.push(ArenaPiece::new(
+            &format!("Pillar{}", i),
+            PieceKind::Wall,
+            position,
+            Vec3::new(size, 1.0, size),
+        ));
+    }
+
+    ArenaDescriptor {
+        name: format!("random-{}", seed),
+        pieces,
+        spawns: vec![
+            SpawnPoint {
+                team: Team::Home,
+                position: PLAYER_SPAWN.to_array(),
+            },
+            SpawnPoint {
+                team: Team::Away,
+                position: away_spawn.to_array(),
+            },
+        ],
+        lod: LodSettings::default(),
+        culling: CullingSettings::default(),
+        sky: SkySettings::default(),
+        stadium: None,
+    }
+}
+
+// --arena-seed <n> replays a layout someone shared
+fn seed_from_args_system(
+    mut random_events: EventWriter<RandomArenaEvent>,
+    args: Res<StartupArgs>,
+) {
+    if let Some(seed) = args.arena_seed {
+        random_events.send(RandomArenaEvent(Some(seed)));
+    }
+}
+
+// the old pieces are cleared and the next Play builds the new layout
+fn random_arena_system(
+    mut commands: Commands,
+    mut random_events: EventReader<RandomArenaEvent>,
+    mut arena: ResMut<Arena>,
+    mut entities: ResMut<MatchEntities>,
+    piece_query: Query<Entity, With<ArenaPieceId>>,
+) {
+    let Some(RandomArenaEvent(seed)) = random_events.iter().last() else {
+        return;
+    };
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let Some((seed, descriptor)) = generate(seed) else {
+        warn!("No playable arena near seed {}", seed);
+        return;
+    };
+
+    // kept in memory, written there only when the player saves it
+    let path = PathBuf::from(format!("assets/arenas/{}.arena.ron", descriptor.name));
+    info!("Generated arena from seed {}", seed);
+
+    for entity in piece_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    entities.goal = None;
+    entities.home_goal = None;
+    arena.descriptor = descriptor;
+    arena.path = path;
+    commands.insert_resource(ArenaSeed { seed, saved: false });
+}
+
+fn save_arena_system(
+    mut save_events: EventReader<SaveArenaEvent>,
+    arena: Res<Arena>,
+    seed: Option<ResMut<ArenaSeed>>,
+) {
+    if save_events.iter().count() == 0 {
+        return;
+    }
+    let Some(mut seed) = seed else {
+        return;
+    };
+    match arena.descriptor.save(&arena.path) {
+        Ok(()) => {
+            info!("Saved {}", arena.path.display());
+            seed.saved = true;
+        }
+        Err(err) => warn!("Failed to write {}: {}", arena.path.display(), err),
+    }
+}
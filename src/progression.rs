@@ -0,0 +1,191 @@
+use super::storage;
+use super::Score;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+const PROGRESSION_FILE: &str = "progression.ron";
+const XP_PER_GOAL: u32 = 10;
+const XP_PER_LEVEL: u32 = 100;
+
+pub struct ProgressionPlugin;
+
+impl Plugin for ProgressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Progression>(PROGRESSION_FILE).unwrap_or_default())
+            .insert_resource(SelectedShot::default())
+            .add_system(goal_xp_system)
+            .add_system(shot_select_system)
+            .add_system(shot_ui_system);
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct Progression {
+    pub xp: u32,
+    #[serde(skip)]
+    last_goals: u32,
+}
+
+impl storage::Versioned for Progression {
+    const VERSION: u32 = 1;
+}
+
+impl Progression {
+    pub fn level(&self) -> u32 {
+        self.xp / XP_PER_LEVEL
+    }
+
+    pub fn unlocked(&self, shot: ShotKind) -> bool {
+        self.level() >= shot.unlock_level()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShotKind {
+    Standard,
+    Knuckleball,
+    Lob,
+    PowerDrive,
+}
+
+// physics knobs a shot applies on top of the base kick direction
+pub struct ShotParams {
+    pub power: f32,
+    pub loft: f32,
+    pub topspin: f32,
+    // knuckleballs leave with no spin and a random sideways nudge
+    pub wobble: f32,
+}
+
+impl ShotKind {
+    pub const ALL: [ShotKind; 4] = [
+        ShotKind::Standard,
+        ShotKind::Knuckleball,
+        ShotKind::Lob,
+        ShotKind::PowerDrive,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShotKind::Standard => "Standard",
+            ShotKind::Knuckleball => "Knuckleball",
+            ShotKind::Lob => "Lob",
+            ShotKind::PowerDrive => "Power drive",
+        }
+    }
+
+    pub fn unlock_level(&self) -> u32 {
+        match self {
+            ShotKind::Standard => 0,
+            ShotKind::Knuckleball => 1,
+            ShotKind::Lob => 2,
+            ShotKind::PowerDrive => 3,
+        }
+    }
+
+    pub fn params(&self) -> ShotParams {
+        match self {
+            ShotKind::Standard => ShotParams {
+                power: 5.0,
+                loft: 0.15,
+                topspin: 0.0,
+                wobble: 0.0,
+            },
+            ShotKind::Knuckleball => ShotParams {
+                power: 6.0,
+                loft: 0.2,
+                topspin: 0.0,
+                wobble: 1.2,
+            },
+            ShotKind::Lob => ShotParams {
+                power: 4.5,
+                loft: 1.0,
+                topspin: -0.05,
+                wobble: 0.0,
+            },
+            ShotKind::PowerDrive => ShotParams {
+                power: 9.0,
+                loft: 0.05,
+                topspin: 0.1,
+                wobble: 0.0,
+            },
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SelectedShot(pub ShotKind);
+
+impl Default for SelectedShot {
+    fn default() -> Self {
+        Self(ShotKind::Standard)
+    }
+}
+
+fn goal_xp_system(score: Res<Score>, mut progression: ResMut<Progression>) {
+    if !score.is_changed() {
+        return;
+    }
+
+    // score resets to zero on rematch, only count increases
+    if score.home > progression.last_goals {
+        let before = progression.level();
+        progression.xp += (score.home - progression.last_goals) * XP_PER_GOAL;
+        if progression.level() > before {
+            info!("Reached level {}", progression.level());
+        }
+        storage::save(PROGRESSION_FILE, &*progression);
+    }
+    progression.last_goals = score.home;
+}
+
+fn shot_select_system(
+    keyboard: Res<Input<KeyCode>>,
+    progression: Res<Progression>,
+    mut selected: ResMut<SelectedShot>,
+) {
+    let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+    for (key, shot) in keys.iter().zip(ShotKind::ALL) {
+        if keyboard.just_pressed(*key) && progression.unlocked(shot) {
+            selected.0 = shot;
+        }
+    }
+}
+
+fn shot_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    progression: Res<Progression>,
+    selected: Res<SelectedShot>,
+) {
+    egui::Area::new("shot_select")
+        .anchor(egui::Align2::LEFT_BOTTOM, [16.0, -16.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!(
+                "Level {} ({} / {} xp)",
+                progression.level(),
+                progression.xp % XP_PER_LEVEL,
+                XP_PER_LEVEL
+            ));
+            for (i, shot) in ShotKind::ALL.iter().enumerate() {
+                let text = if progression.unlocked(*shot) {
+                    format!("[{}] {}", i + 1, shot.name())
+                } else {
+                    format!(
+                        "[{}] {} (level {})",
+                        i + 1,
+                        shot.name(),
+                        shot.unlock_level()
+                    )
+                };
+                let color = if selected.0 == *shot {
+                    egui::Color32::YELLOW
+                } else if progression.unlocked(*shot) {
+                    egui::Color32::WHITE
+                } else {
+                    egui::Color32::GRAY
+                };
+                ui.label(egui::RichText::new(text).color(color));
+            }
+        });
+}
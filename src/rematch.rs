@@ -0,0 +1,42 @@
+use super::marks::MatchEntity;
+use super::mutators::Mutators;
+use super::{spawn_match_entities, GoalReset, MatchAssets, MatchEntities, Score};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub struct RematchPlugin;
+
+impl Plugin for RematchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RematchEvent>()
+            .add_system_to_stage(CoreStage::PostUpdate, rematch_system);
+    }
+}
+
+// despawns and respawns only the match entities, the arena stays untouched
+pub struct RematchEvent;
+
+pub fn rematch_system(
+    mut commands: Commands,
+    mut rematch_events: EventReader<RematchEvent>,
+    mut score: ResMut<Score>,
+    mut goal_reset: ResMut<GoalReset>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    match_assets: Res<MatchAssets>,
+    mutators: Res<Mutators>,
+    mut entities: ResMut<MatchEntities>,
+    match_entities: Query<Entity, With<MatchEntity>>,
+) {
+    if rematch_events.iter().count() == 0 {
+        return;
+    }
+
+    for entity in match_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *score = Score::default();
+    *goal_reset = GoalReset::default();
+    rapier_config.physics_pipeline_active = true;
+    spawn_match_entities(&mut commands, &match_assets, &mut entities, &mutators);
+}
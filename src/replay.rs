@@ -0,0 +1,373 @@
+use super::game_state::GameState;
+use super::input::PlayerInput;
+use super::marks::{Ball, Team};
+use super::rematch::RematchEvent;
+use super::tape::TapeSample;
+use super::Score;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::collections::VecDeque;
+
+const RECORD_RATE: f32 = 30.0;
+// five minutes of history is plenty for a single match
+const MAX_FRAMES: usize = 9000;
+const SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+// seconds of match covered per second an arrow key is held
+const SCRUB_RATE: f32 = 4.0;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Replay::default())
+            .insert_resource(ReplayViewer::default())
+            .add_system(replay_record_system)
+            .add_system(replay_reset_system)
+            .add_system(replay_toggle_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playback)
+                    .with_system(replay_keys_system.after(replay_toggle_system))
+                    .with_system(replay_timeline_ui_system),
+            )
+            .add_system(replay_playback_system.after(replay_keys_system));
+    }
+}
+
+pub struct ReplayFrame {
+    pub time: f32,
+    pub transforms: Vec<(Entity, Transform)>,
+    // what the local player was pressing, kept for analysing saved matches
+    pub input: TapeSample,
+}
+
+#[derive(Resource)]
+pub struct Replay {
+    pub frames: VecDeque<ReplayFrame>,
+    // match time of every goal, shown as markers on the timeline
+    pub goals: Vec<f32>,
+    time: f32,
+    timer: Timer,
+    last_total: u32,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            goals: Vec::new(),
+            time: 0.0,
+            timer: Timer::from_seconds(1.0 / RECORD_RATE, TimerMode::Repeating),
+            last_total: 0,
+        }
+    }
+}
+
+impl Replay {
+    pub fn from_frames(frames: VecDeque<ReplayFrame>, goals: Vec<f32>) -> Self {
+        Self {
+            time: frames.back().map(|f| f.time).unwrap_or_default(),
+            frames,
+            goals,
+            ..default()
+        }
+    }
+
+    pub fn start(&self) -> f32 {
+        self.frames.front().map(|f| f.time).unwrap_or_default()
+    }
+
+    pub fn end(&self) -> f32 {
+        self.frames.back().map(|f| f.time).unwrap_or_default()
+    }
+
+    // the two frames around a time and how far between them it lies
+    fn sample(&self, time: f32) -> Option<(&ReplayFrame, &ReplayFrame, f32)> {
+        let next = self.frames.partition_point(|f| f.time < time);
+        let b = self.frames.get(next).or_else(|| self.frames.back())?;
+        let a = next
+            .checked_sub(1)
+            .and_then(|i| self.frames.get(i))
+            .unwrap_or(b);
+        let span = b.time - a.time;
+        let t = if span > 0.0 {
+            ((time - a.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Some((a, b, t))
+    }
+
+    // every recorded entity blended to where it was at a time
+    pub fn transforms_at(&self, time: f32) -> Vec<(Entity, Transform)> {
+        let Some((a, b, t)) = self.sample(time) else {
+            return Vec::new();
+        };
+        a.transforms
+            .iter()
+            .map(|(entity, from)| {
+                let to = b
+                    .transforms
+                    .iter()
+                    .find(|(e, _)| e == entity)
+                    .map(|(_, tf)| tf)
+                    .unwrap_or(from);
+                let mut tf = *from;
+                tf.translation = from.translation.lerp(to.translation, t);
+                tf.rotation = from.rotation.slerp(to.rotation, t);
+                (*entity, tf)
+            })
+            .collect()
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayViewer {
+    pub active: bool,
+    pub cursor: f32,
+    pub playing: bool,
+    pub speed: f32,
+    // loaded from disk rather than recorded this match
+    pub from_file: bool,
+}
+
+impl ReplayViewer {
+    // over a match in play or one that has finished, the other screens pushed on top
+    // of a match already hold physics and would hand it back too early
+    pub fn open(&mut self, replay: &Replay, state: &mut State<GameState>) -> bool {
+        if !matches!(state.current(), GameState::Playing | GameState::GameOver)
+            || state.push(GameState::Playback).is_err()
+        {
+            return false;
+        }
+        self.active = true;
+        self.cursor = replay.start();
+        self.playing = true;
+        true
+    }
+
+    pub fn close(&mut self, replay: &Replay, state: &mut State<GameState>) {
+        // snap back to the live state before handing control back to physics
+        self.active = false;
+        self.cursor = replay.end();
+        state.pop().ok();
+    }
+}
+
+impl Default for ReplayViewer {
+    fn default() -> Self {
+        Self {
+            active: false,
+            cursor: 0.0,
+            playing: true,
+            speed: 1.0,
+            from_file: false,
+        }
+    }
+}
+
+fn replay_record_system(
+    mut replay: ResMut<Replay>,
+    viewer: Res<ReplayViewer>,
+    score: Res<Score>,
+    input: Res<PlayerInput>,
+    time: Res<Time>,
+    tracked_query: Query<(Entity, &Transform), Or<(With<Ball>, With<Team>)>>,
+) {
+    if viewer.active {
+        return;
+    }
+
+    replay.time += time.delta_seconds();
+    let total = score.total();
+    if total > replay.last_total {
+        let now = replay.time;
+        replay.goals.push(now);
+    }
+    replay.last_total = total;
+
+    replay.timer.tick(time.delta());
+    if !replay.timer.just_finished() {
+        return;
+    }
+    let frame = ReplayFrame {
+        time: replay.time,
+        transforms: tracked_query.iter().map(|(e, tf)| (e, *tf)).collect(),
+        input: TapeSample::from(&*input),
+    };
+    replay.frames.push_back(frame);
+    if replay.frames.len() > MAX_FRAMES {
+        replay.frames.pop_front();
+    }
+    let start = replay.start();
+    replay.goals.retain(|t| *t >= start);
+}
+
+pub fn replay_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut replay: ResMut<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+) {
+    if rematch_events.iter().count() > 0 {
+        *replay = Replay::default();
+        viewer.active = false;
+        viewer.from_file = false;
+    }
+}
+
+fn replay_toggle_system(
+    keyboard: Res<Input<KeyCode>>,
+    replay: Res<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+    mut state: ResMut<State<GameState>>,
+    mut rematch_events: EventWriter<RematchEvent>,
+) {
+    if !keyboard.just_pressed(KeyCode::F6) || replay.frames.is_empty() {
+        return;
+    }
+
+    if !viewer.active {
+        viewer.open(&replay, &mut state);
+    } else if viewer.from_file {
+        // a loaded replay has no live state to return to, start over instead
+        viewer.active = false;
+        viewer.from_file = false;
+        rematch_events.send(RematchEvent);
+    } else {
+        viewer.close(&replay, &mut state);
+    }
+}
+
+// space pauses, holding left or right scrubs through the match
+fn replay_keys_system(
+    keyboard: Res<Input<KeyCode>>,
+    replay: Res<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+    time: Res<Time>,
+) {
+    if !viewer.active {
+        return;
+    }
+    let (start, end) = (replay.start(), replay.end());
+    if keyboard.just_pressed(KeyCode::Space) {
+        if !viewer.playing && viewer.cursor >= end {
+            viewer.cursor = start;
+        }
+        viewer.playing = !viewer.playing;
+    }
+    let direction =
+        keyboard.pressed(KeyCode::Right) as i32 - keyboard.pressed(KeyCode::Left) as i32;
+    if direction != 0 {
+        viewer.playing = false;
+        let step = direction as f32 * SCRUB_RATE * time.delta_seconds();
+        viewer.cursor = (viewer.cursor + step).clamp(start, end);
+    }
+}
+
+fn replay_playback_system(
+    replay: Res<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+    time: Res<Time>,
+    mut tf_query: Query<&mut Transform>,
+    mut was_active: Local<bool>,
+) {
+    // one last pass after closing the viewer restores the live frame
+    if !viewer.active && !*was_active {
+        return;
+    }
+    *was_active = viewer.active;
+
+    if viewer.active && viewer.playing {
+        viewer.cursor += time.delta_seconds() * viewer.speed;
+        if viewer.cursor >= replay.end() {
+            viewer.cursor = replay.end();
+            viewer.playing = false;
+        }
+    }
+
+    for (entity, pose) in replay.transforms_at(viewer.cursor) {
+        let Ok(mut tf) = tf_query.get_mut(entity) else {
+            continue;
+        };
+        tf.translation = pose.translation;
+        tf.rotation = pose.rotation;
+    }
+}
+
+fn replay_timeline_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    replay: Res<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+) {
+    if !viewer.active {
+        return;
+    }
+    let (start, end) = (replay.start(), replay.end());
+
+    egui::Window::new("Replay")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -16.0])
+        .resizable(false)
+        .collapsible(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            let size = egui::vec2(480.0, 24.0);
+            let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+            let x_for = |time: f32| {
+                let t = if end > start {
+                    (time - start) / (end - start)
+                } else {
+                    0.0
+                };
+                egui::lerp(rect.left()..=rect.right(), t)
+            };
+
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let t = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                viewer.cursor = start + t * (end - start);
+                viewer.playing = false;
+            }
+
+            let painter = ui.painter_at(rect);
+            let y = rect.center().y;
+            painter.line_segment(
+                [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
+                egui::Stroke::new(4.0, egui::Color32::DARK_GRAY),
+            );
+            painter.line_segment(
+                [
+                    egui::pos2(rect.left(), y),
+                    egui::pos2(x_for(viewer.cursor), y),
+                ],
+                egui::Stroke::new(4.0, egui::Color32::LIGHT_BLUE),
+            );
+            for goal in replay.goals.iter() {
+                let x = x_for(*goal);
+                painter.line_segment(
+                    [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                    egui::Stroke::new(2.0, egui::Color32::YELLOW),
+                );
+            }
+            painter.circle_filled(
+                egui::pos2(x_for(viewer.cursor), y),
+                7.0,
+                egui::Color32::WHITE,
+            );
+
+            ui.horizontal(|ui| {
+                let label = if viewer.playing { "Pause" } else { "Play" };
+                if ui.button(label).clicked() {
+                    if !viewer.playing && viewer.cursor >= end {
+                        viewer.cursor = start;
+                    }
+                    viewer.playing = !viewer.playing;
+                }
+                for speed in SPEEDS {
+                    ui.selectable_value(&mut viewer.speed, speed, format!("{}x", speed));
+                }
+                ui.label(format!(
+                    "{:.1} / {:.1} s",
+                    viewer.cursor - start,
+                    end - start
+                ));
+                ui.label("←/→ scrub, Space pause, F6 to return");
+            });
+        });
+}
@@ -0,0 +1,381 @@
+use super::cli::StartupArgs;
+use super::clock::MatchClock;
+use super::config::GameConfig;
+use super::game_state::{app_exiting, GameState};
+use super::marks::{Ball, EnemyGoal, Player, Team};
+use super::mode::MatchFinishedEvent;
+use super::player::KickEvent;
+use super::rematch::RematchEvent;
+use super::replay::{replay_reset_system, Replay, ReplayFrame, ReplayViewer};
+use super::storage;
+use super::tape::TapeSample;
+use super::Score;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const REPLAY_DIR: &str = "replays";
+
+pub struct ReplayBrowserPlugin;
+
+impl Plugin for ReplayBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        // --replay <file> starts a match and watches a saved replay in it straight away
+        let pending = app
+            .world
+            .get_resource::<StartupArgs>()
+            .and_then(|args| args.replay.clone());
+        if pending.is_some() {
+            app.add_startup_system(start_match_system);
+        }
+
+        app.insert_resource(ReplayBrowser {
+            pending,
+            ..default()
+        })
+        .add_system(long_shot_system)
+        .add_system(pending_replay_system)
+        .add_system(auto_save_system.before(replay_reset_system))
+        .add_system_to_stage(CoreStage::Last, exit_save_system)
+        .add_system(replay_browser_ui_system);
+    }
+}
+
+// entities are stored by name, and matched against the live arena by name when loaded
+#[derive(Serialize, Deserialize)]
+pub struct SavedReplay {
+    pub names: Vec<String>,
+    pub goals: Vec<f32>,
+    pub frames: Vec<SavedFrame>,
+}
+
+impl storage::Versioned for SavedReplay {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedFrame {
+    pub time: f32,
+    pub transforms: Vec<(usize, [f32; 3], [f32; 4])>,
+    // missing from replays saved before inputs were recorded
+    #[serde(default)]
+    pub input: TapeSample,
+}
+
+#[derive(Resource, Default)]
+struct ReplayBrowser {
+    open: bool,
+    files: Vec<String>,
+    // per match bookkeeping for the auto-save rules
+    long_shot: bool,
+    saved: bool,
+    kick_spot: Option<Vec3>,
+    last_goals: u32,
+    // a replay asked for on the command line, loaded once the match has spawned
+    pending: Option<String>,
+}
+
+impl SavedReplay {
+    fn capture(replay: &Replay, name_query: &Query<&Name>) -> Self {
+        let mut slots = HashMap::new();
+        let mut names = Vec::new();
+        let frames = replay
+            .frames
+            .iter()
+            .map(|frame| SavedFrame {
+                time: frame.time,
+                transforms: frame
+                    .transforms
+                    .iter()
+                    .map(|(entity, tf)| {
+                        let slot = *slots.entry(*entity).or_insert_with(|| {
+                            let name = name_query
+                                .get(*entity)
+                                .map(|n| n.as_str().to_string())
+                                .unwrap_or_default();
+                            names.push(name);
+                            names.len() - 1
+                        });
+                        (slot, tf.translation.to_array(), tf.rotation.to_array())
+                    })
+                    .collect(),
+                input: frame.input,
+            })
+            .collect();
+
+        Self {
+            names,
+            goals: replay.goals.clone(),
+            frames,
+        }
+    }
+
+    // each saved name claims the first live entity carrying it that is still free
+    fn restore(&self, live: &[(Entity, String)]) -> Replay {
+        let mut used = Vec::new();
+        let entities: Vec<Option<Entity>> = self
+            .names
+            .iter()
+            .map(|name| {
+                let (entity, _) = live.iter().find(|(e, n)| n == name && !used.contains(e))?;
+                used.push(*entity);
+                Some(*entity)
+            })
+            .collect();
+
+        let frames: VecDeque<_> = self
+            .frames
+            .iter()
+            .map(|frame| ReplayFrame {
+                time: frame.time,
+                transforms: frame
+                    .transforms
+                    .iter()
+                    .filter_map(|(slot, translation, rotation)| {
+                        let entity = entities.get(*slot).copied().flatten()?;
+                        let tf = Transform::from_translation(Vec3::from_array(*translation))
+                            .with_rotation(Quat::from_array(*rotation));
+                        Some((entity, tf))
+                    })
+                    .collect(),
+                input: frame.input,
+            })
+            .collect();
+        Replay::from_frames(frames, self.goals.clone())
+    }
+}
+
+fn list_replays() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(storage::data_dir().join(REPLAY_DIR)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".ron"))
+        .collect();
+    // newest first, file names carry a timestamp
+    files.sort_unstable_by(|a, b| b.cmp(a));
+    files
+}
+
+fn save_replay(replay: &Replay, name_query: &Query<&Name>, reason: &str) {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let name = format!("{}/match-{}-{}.ron", REPLAY_DIR, stamp, reason);
+    storage::save(&name, &SavedReplay::capture(replay, name_query));
+    info!("Saved replay {}", name);
+}
+
+fn long_shot_system(
+    mut browser: ResMut<ReplayBrowser>,
+    mut kick_events: EventReader<KickEvent>,
+    config: Res<GameConfig>,
+    score: Res<Score>,
+    player_query: Query<&Transform, (With<Player>, Without<Ball>)>,
+    goal_query: Query<&GlobalTransform, With<EnemyGoal>>,
+) {
+    if kick_events.iter().any(|ev| ev.team == Team::Home) {
+        browser.kick_spot = player_query.get_single().ok().map(|tf| tf.translation);
+    }
+
+    if score.home > browser.last_goals {
+        if let (Some(spot), Ok(goal)) = (browser.kick_spot, goal_query.get_single()) {
+            let distance = (goal.translation().z - spot.z).abs();
+            if distance >= config.replays.long_shot_distance {
+                info!("Long shot goal from {:.1}m", distance);
+                browser.long_shot = true;
+            }
+        }
+    }
+    browser.last_goals = score.home;
+}
+
+fn auto_save_system(
+    mut browser: ResMut<ReplayBrowser>,
+    mut finished_events: EventReader<MatchFinishedEvent>,
+    mut rematch_events: EventReader<RematchEvent>,
+    mut was_over: Local<bool>,
+    config: Res<GameConfig>,
+    replay: Res<Replay>,
+    viewer: Res<ReplayViewer>,
+    clock: Res<MatchClock>,
+    score: Res<Score>,
+    name_query: Query<&Name>,
+) {
+    let won_series_match = finished_events.iter().any(|ev| ev.winner == Team::Home);
+    let won_on_time = !*was_over && clock.full_time() && score.home > score.away;
+    *was_over = clock.full_time();
+    let rematch = rematch_events.iter().count() > 0;
+
+    if !browser.saved && !viewer.from_file {
+        if (won_series_match || won_on_time) && config.replays.auto_save_wins {
+            save_replay(&replay, &name_query, "win");
+            browser.saved = true;
+        } else if rematch && browser.long_shot && config.replays.auto_save_long_shots {
+            save_replay(&replay, &name_query, "long-shot");
+            browser.saved = true;
+        } else if rematch && config.replays.record_matches {
+            save_replay(&replay, &name_query, "match");
+            browser.saved = true;
+        }
+    }
+
+    if rematch {
+        browser.long_shot = false;
+        browser.saved = false;
+        browser.kick_spot = None;
+        browser.last_goals = 0;
+    }
+}
+
+// the recording of a match still going when the app closes, see `app_exiting` for why
+// this waits until the Last stage
+fn exit_save_system(
+    mut browser: ResMut<ReplayBrowser>,
+    mut exit_events: EventReader<AppExit>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    config: Res<GameConfig>,
+    replay: Res<Replay>,
+    viewer: Res<ReplayViewer>,
+    name_query: Query<&Name>,
+) {
+    if !app_exiting(&mut exit_events, &mut close_events) {
+        return;
+    }
+    if !browser.saved && !viewer.from_file && config.replays.record_matches {
+        save_replay(&replay, &name_query, "match");
+        browser.saved = true;
+    }
+}
+
+fn replay_browser_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut browser: ResMut<ReplayBrowser>,
+    mut config: ResMut<GameConfig>,
+    mut replay: ResMut<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+    mut state: ResMut<State<GameState>>,
+    keyboard: Res<Input<KeyCode>>,
+    live_query: Query<(Entity, &Name), Or<(With<Ball>, With<Team>)>>,
+) {
+    if keyboard.just_pressed(KeyCode::F7) {
+        browser.open = !browser.open;
+        if browser.open {
+            browser.files = list_replays();
+        }
+    }
+    if !browser.open {
+        return;
+    }
+
+    let mut settings = config.replays.clone();
+    let mut launch = None;
+    let mut open = browser.open;
+    egui::Window::new("Replays")
+        .open(&mut open)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.checkbox(&mut settings.auto_save_wins, "Auto-save matches I win");
+            ui.checkbox(
+                &mut settings.auto_save_long_shots,
+                "Auto-save matches with long shots",
+            );
+            ui.checkbox(&mut settings.record_matches, "Record every match");
+            ui.separator();
+            if browser.files.is_empty() {
+                ui.label("No saved replays yet");
+            }
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    for file in browser.files.iter() {
+                        ui.horizontal(|ui| {
+                            ui.label(file);
+                            if ui.button("Watch").clicked() {
+                                launch = Some(file.clone());
+                            }
+                        });
+                    }
+                });
+            if ui.button("Refresh").clicked() {
+                browser.files = list_replays();
+            }
+        });
+    browser.open = open;
+
+    if settings != config.replays {
+        config.replays = settings;
+        config.save();
+    }
+
+    let Some(file) = launch else {
+        return;
+    };
+    if watch_replay(&file, &live_query, &mut replay, &mut viewer, &mut state) {
+        browser.open = false;
+    }
+}
+
+// the match is set aside in GameState::Playback while watching, entities only
+// follow the recorded transforms
+fn watch_replay(
+    file: &str,
+    live_query: &Query<(Entity, &Name), Or<(With<Ball>, With<Team>)>>,
+    replay: &mut Replay,
+    viewer: &mut ReplayViewer,
+    state: &mut State<GameState>,
+) -> bool {
+    let Some(saved) = storage::load::<SavedReplay>(&format!("{}/{}", REPLAY_DIR, file)) else {
+        warn!("No replay named {}", file);
+        return false;
+    };
+    let live: Vec<_> = live_query
+        .iter()
+        .map(|(e, n)| (e, n.as_str().to_string()))
+        .collect();
+    let restored = saved.restore(&live);
+    if !viewer.open(&restored, state) {
+        warn!("Replays can only be watched over a match");
+        return false;
+    }
+    *replay = restored;
+    viewer.from_file = true;
+    true
+}
+
+fn start_match_system(mut rematch_events: EventWriter<RematchEvent>) {
+    rematch_events.send(RematchEvent);
+}
+
+fn pending_replay_system(
+    mut browser: ResMut<ReplayBrowser>,
+    mut replay: ResMut<Replay>,
+    mut viewer: ResMut<ReplayViewer>,
+    mut state: ResMut<State<GameState>>,
+    live_query: Query<(Entity, &Name), Or<(With<Ball>, With<Team>)>>,
+    player_query: Query<(), With<Player>>,
+) {
+    // waits for the match to spawn and the state to have switched to Playing
+    if browser.pending.is_none()
+        || player_query.is_empty()
+        || state.current() != &GameState::Playing
+    {
+        return;
+    }
+    let Some(file) = browser.pending.take() else {
+        return;
+    };
+    // accept names with or without the directory and extension
+    let file = file.trim_start_matches(&format!("{}/", REPLAY_DIR));
+    let file = if file.ends_with(".ron") {
+        file.to_string()
+    } else {
+        format!("{}.ron", file)
+    };
+    watch_replay(&file, &live_query, &mut replay, &mut viewer, &mut state);
+}
@@ -0,0 +1,213 @@
+use super::clock::MatchClock;
+use super::marks::{Ball, Team};
+use super::player::KickEvent;
+use super::progression::ShotKind;
+use super::rematch::RematchEvent;
+use super::Score;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub struct RulesPlugin;
+
+impl Plugin for RulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LastTouch::default())
+            .add_system(last_touch_system)
+            .add_system(last_touch_reset_system);
+    }
+}
+
+// declarative scoring rules, picked per mode from the game config
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct RuleSet {
+    // goals only stand when the last touch came from one of these teams
+    pub can_score: Vec<Team>,
+    pub on_goal: GoalReset,
+    pub bonuses: Vec<BonusRule>,
+    // first side to this many goals wins, on top of whatever the mode decides
+    pub score_limit: Option<u32>,
+}
+
+impl RuleSet {
+    pub fn limit_winner(&self, score: &Score) -> Option<Team> {
+        let limit = self.score_limit?;
+        if score.home >= limit {
+            Some(Team::Home)
+        } else if score.away >= limit {
+            Some(Team::Away)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            can_score: vec![Team::Home, Team::Away],
+            on_goal: GoalReset::default(),
+            bonuses: Vec::new(),
+            score_limit: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct GoalReset {
+    pub ball: bool,
+    pub players: bool,
+    pub kickoff: bool,
+}
+
+impl Default for GoalReset {
+    fn default() -> Self {
+        Self {
+            ball: true,
+            players: false,
+            kickoff: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct BonusRule {
+    pub condition: Condition,
+    pub points: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Condition {
+    // kicked from at least this far out, in metres along the pitch
+    LongShot(f32),
+    // scored with the named shot, see `ShotKind::name`
+    Shot(String),
+    // scored with this many seconds or fewer left on the clock
+    FinalSeconds(f32),
+    // the scoring team was behind before the goal
+    Trailing,
+}
+
+// what the rules need to know about a goal when judging it
+pub struct GoalContext<'a> {
+    pub scorer: Team,
+    pub goal_position: Vec3,
+    // the last touch of the ball that went in
+    pub touch: Touch,
+    pub clock: &'a MatchClock,
+    pub score: &'a Score,
+}
+
+impl RuleSet {
+    pub fn allows(&self, ctx: &GoalContext) -> bool {
+        self.can_score.contains(&ctx.scorer)
+    }
+
+    // one for the goal itself plus every bonus whose condition holds
+    pub fn points(&self, ctx: &GoalContext) -> u32 {
+        1 + self
+            .bonuses
+            .iter()
+            .filter(|bonus| bonus.condition.holds(ctx))
+            .map(|bonus| bonus.points)
+            .sum::<u32>()
+    }
+}
+
+impl Condition {
+    fn holds(&self, ctx: &GoalContext) -> bool {
+        match self {
+            Condition::LongShot(distance) => ctx.touch.kick.map_or(false, |(_, origin)| {
+                (ctx.goal_position.z - origin.z).abs() >= *distance
+            }),
+            Condition::Shot(name) => ctx
+                .touch
+                .kick
+                .map_or(false, |(shot, _)| shot.name().eq_ignore_ascii_case(name)),
+            Condition::FinalSeconds(seconds) => ctx.clock.remaining() <= *seconds,
+            Condition::Trailing => match ctx.scorer {
+                Team::Home => ctx.score.home < ctx.score.away,
+                Team::Away => ctx.score.away < ctx.score.home,
+            },
+        }
+    }
+}
+
+// who touched a ball last, and the kick that sent it if it was a kick
+#[derive(Clone, Copy, Default)]
+pub struct Touch {
+    pub team: Option<Team>,
+    pub kick: Option<(ShotKind, Vec3)>,
+}
+
+// every ball keeps its own last touch, so with several in play a goal is judged on
+// the one that went in
+#[derive(Resource, Default)]
+pub struct LastTouch {
+    pub balls: HashMap<Entity, Touch>,
+    // whoever touched any ball most recently
+    pub latest: Option<Team>,
+}
+
+impl LastTouch {
+    pub fn of(&self, ball: Entity) -> Touch {
+        self.balls.get(&ball).copied().unwrap_or_default()
+    }
+}
+
+fn last_touch_system(
+    mut touch: ResMut<LastTouch>,
+    mut kick_events: EventReader<KickEvent>,
+    mut collision_events: EventReader<CollisionEvent>,
+    ball_query: Query<&Transform, With<Ball>>,
+    team_query: Query<&Team>,
+) {
+    for ev in collision_events.iter() {
+        let CollisionEvent::Started(a, b, _) = ev else {
+            continue;
+        };
+        let (ball, other) = if ball_query.contains(*a) {
+            (*a, *b)
+        } else if ball_query.contains(*b) {
+            (*b, *a)
+        } else {
+            continue;
+        };
+        if let Ok(team) = team_query.get(other) {
+            touch.balls.insert(
+                ball,
+                Touch {
+                    team: Some(*team),
+                    kick: None,
+                },
+            );
+            touch.latest = Some(*team);
+        }
+    }
+
+    for ev in kick_events.iter() {
+        let Ok(ball_tf) = ball_query.get(ev.ball) else {
+            continue;
+        };
+        touch.balls.insert(
+            ev.ball,
+            Touch {
+                team: Some(ev.team),
+                kick: Some((ev.shot, ball_tf.translation)),
+            },
+        );
+        touch.latest = Some(ev.team);
+    }
+}
+
+fn last_touch_reset_system(
+    mut rematch_events: EventReader<RematchEvent>,
+    mut touch: ResMut<LastTouch>,
+) {
+    if rematch_events.iter().count() > 0 {
+        *touch = LastTouch::default();
+    }
+}
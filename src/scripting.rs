@@ -0,0 +1,199 @@
+use super::arena_events::ArenaEvent;
+use super::player::KickEvent;
+use super::rematch::{rematch_system, RematchEvent};
+use super::{goal_score_system, GoalScoredEvent, Score};
+use bevy::prelude::*;
+use rhai::{Dynamic, Engine, FuncArgs, Scope, AST};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const SCRIPTS_DIR: &str = "scripts";
+const PRE: &str = "pre";
+const POST: &str = "post";
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        let scripts = Scripts::load(Path::new(SCRIPTS_DIR));
+        if scripts.scripts.is_empty() {
+            return;
+        }
+
+        app.insert_resource(scripts)
+            .add_system(post_hooks_system.after(goal_score_system))
+            .add_system(script_commands_system.after(post_hooks_system))
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                pre_match_start_system.before(rematch_system),
+            );
+    }
+}
+
+// what a script may ask for, applied once the hook returns
+#[derive(Debug)]
+enum ScriptCommand {
+    MultiBall,
+    BigBall,
+    Wind(f32, f32),
+    AddScore(i64, i64),
+}
+
+// scripts/*.rhai, each may define any of the hooks, called with stage "pre" before
+// the event happens and "post" after it:
+//   on_goal(stage, home, away)   "pre" returns false to disallow the goal
+//   on_kick(stage, power)        "pre" returns a multiplier for the kick impulse
+//   on_match_start(stage)        "pre" runs before the match entities are rebuilt
+#[derive(Resource)]
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<(String, AST)>,
+    commands: Arc<Mutex<Vec<ScriptCommand>>>,
+}
+
+impl Scripts {
+    fn load(dir: &Path) -> Self {
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        // scripts only get a couple of game verbs, no file or network access
+        engine.set_max_operations(100_000);
+        let sink = commands.clone();
+        engine.register_fn("multiball", move || {
+            sink.lock().unwrap().push(ScriptCommand::MultiBall)
+        });
+        let sink = commands.clone();
+        engine.register_fn("big_ball", move || {
+            sink.lock().unwrap().push(ScriptCommand::BigBall)
+        });
+        let sink = commands.clone();
+        engine.register_fn("wind", move |x: f64, z: f64| {
+            sink.lock()
+                .unwrap()
+                .push(ScriptCommand::Wind(x as f32, z as f32))
+        });
+        let sink = commands.clone();
+        engine.register_fn("add_score", move |team: i64, amount: i64| {
+            sink.lock()
+                .unwrap()
+                .push(ScriptCommand::AddScore(team, amount))
+        });
+
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| Some(entry.ok()?.path()))
+                    .filter(|path| path.extension().map_or(false, |ext| ext == "rhai"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        let scripts = paths
+            .into_iter()
+            .filter_map(|path| match engine.compile_file(path.clone()) {
+                Ok(ast) => {
+                    info!("Loaded script {}", path.display());
+                    Some((path.display().to_string(), ast))
+                }
+                Err(err) => {
+                    warn!("Skipping script {}: {}", path.display(), err);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            engine,
+            scripts,
+            commands,
+        }
+    }
+
+    // runs the hook in every script that defines it, returning what each produced
+    fn call(&self, hook: &str, args: impl FuncArgs + Clone) -> Vec<Dynamic> {
+        self.scripts
+            .iter()
+            .filter(|(_, ast)| ast.iter_functions().any(|f| f.name == hook))
+            .filter_map(|(name, ast)| {
+                let mut scope = Scope::new();
+                match self
+                    .engine
+                    .call_fn::<Dynamic>(&mut scope, ast, hook, args.clone())
+                {
+                    Ok(result) => Some(result),
+                    Err(err) => {
+                        warn!("Script {} failed in {}: {}", name, hook, err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn pre_goal(&self, home: u32, away: u32) -> bool {
+        self.call("on_goal", (PRE, home as i64, away as i64))
+            .iter()
+            .all(|result| result.as_bool().unwrap_or(true))
+    }
+
+    pub fn pre_kick(&self, power: f32) -> f32 {
+        self.call("on_kick", (PRE, power as f64))
+            .iter()
+            .filter_map(|result| result.as_float().ok())
+            .product::<f64>() as f32
+    }
+}
+
+fn pre_match_start_system(scripts: Res<Scripts>, mut rematch_events: EventReader<RematchEvent>) {
+    if rematch_events.iter().count() > 0 {
+        scripts.call("on_match_start", (PRE,));
+    }
+}
+
+fn post_hooks_system(
+    scripts: Res<Scripts>,
+    score: Res<Score>,
+    mut goal_events: EventReader<GoalScoredEvent>,
+    mut kick_events: EventReader<KickEvent>,
+    mut rematch_events: EventReader<RematchEvent>,
+    mut started: Local<bool>,
+) {
+    // rematch events are read a frame after the rebuild in PostUpdate
+    if !*started || rematch_events.iter().count() > 0 {
+        scripts.call("on_match_start", (POST,));
+        *started = true;
+    }
+    // only real goals, points a script adds with add_score don't raise the hook again
+    if goal_events.iter().count() > 0 {
+        scripts.call("on_goal", (POST, score.home as i64, score.away as i64));
+    }
+    for ev in kick_events.iter() {
+        scripts.call("on_kick", (POST, ev.impulse.length() as f64));
+    }
+}
+
+fn script_commands_system(
+    scripts: Res<Scripts>,
+    mut arena_events: EventWriter<ArenaEvent>,
+    mut score: ResMut<Score>,
+) {
+    let commands: Vec<_> = scripts.commands.lock().unwrap().drain(..).collect();
+    for command in commands {
+        debug!("Script command {:?}", command);
+        match command {
+            ScriptCommand::MultiBall => arena_events.send(ArenaEvent::MultiBall),
+            ScriptCommand::BigBall => arena_events.send(ArenaEvent::BigBall),
+            ScriptCommand::Wind(x, z) => {
+                arena_events.send(ArenaEvent::WindGust(Vec3::new(x, 0.0, z)))
+            }
+            ScriptCommand::AddScore(team, amount) => {
+                let tally = if team == 0 {
+                    &mut score.home
+                } else {
+                    &mut score.away
+                };
+                *tally = tally.saturating_add_signed(amount as i32);
+            }
+        }
+    }
+}
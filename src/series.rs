@@ -0,0 +1,294 @@
+use super::cli::StartupArgs;
+use super::goalkeeper::GoalkeeperPlugin;
+use super::heatmap::{shot_chart_ui, Heatmap, ShotLog};
+use super::marks::Team;
+use super::mode::{GameMode, MatchFinishedEvent, ModeContext};
+use super::net::networked;
+use super::opponent::OpponentPlugin;
+use super::rematch::RematchEvent;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+
+pub const DEFAULT_BEST_OF: u32 = 3;
+// unless the rules set a score limit of their own
+pub const GOALS_TO_WIN_MATCH: u32 = 3;
+
+pub struct SeriesPlugin;
+
+impl Plugin for SeriesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Series::new(DEFAULT_BEST_OF))
+            .add_system(series_progress_system)
+            .add_system(interstitial_ui_system)
+            .add_system(series_summary_ui_system);
+    }
+}
+
+// first to three goals, or whoever leads at full time, inside a best-of series
+pub struct ClassicMode;
+
+impl GameMode for ClassicMode {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+
+    fn title(&self) -> &'static str {
+        "Classic"
+    }
+
+    fn description(&self) -> &'static str {
+        "First to three goals, best of three matches."
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_plugin(SeriesPlugin);
+        // over the network the away side is the peer, not the computer
+        let networked = app
+            .world
+            .get_resource::<StartupArgs>()
+            .map_or(false, networked);
+        if !networked {
+            app.add_plugin(OpponentPlugin).add_plugin(GoalkeeperPlugin);
+        }
+    }
+
+    // rematches go through the series interstitial so the series keeps count
+    fn own_results(&self) -> bool {
+        true
+    }
+
+    fn winner(&self, ctx: &ModeContext) -> Option<Team> {
+        let limit = ctx.rules.score_limit.unwrap_or(GOALS_TO_WIN_MATCH);
+        if ctx.score.home >= limit {
+            Some(Team::Home)
+        } else if ctx.score.away >= limit {
+            Some(Team::Away)
+        } else if ctx.clock.full_time() {
+            // the clock only stops at full time with somebody ahead
+            Some(if ctx.score.home > ctx.score.away {
+                Team::Home
+            } else {
+                Team::Away
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SeriesPhase {
+    Playing,
+    Interstitial,
+    Finished,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MatchResult {
+    pub winner: Team,
+    pub home_goals: u32,
+    pub away_goals: u32,
+    pub duration: f32,
+}
+
+#[derive(Resource)]
+pub struct Series {
+    pub best_of: u32,
+    pub home_wins: u32,
+    pub away_wins: u32,
+    pub results: Vec<MatchResult>,
+    pub phase: SeriesPhase,
+}
+
+impl Series {
+    pub fn new(best_of: u32) -> Self {
+        Self {
+            best_of,
+            home_wins: 0,
+            away_wins: 0,
+            results: Vec::new(),
+            phase: SeriesPhase::Playing,
+        }
+    }
+
+    pub fn wins_needed(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    pub fn wins(&self, team: Team) -> u32 {
+        match team {
+            Team::Home => self.home_wins,
+            Team::Away => self.away_wins,
+        }
+    }
+
+    pub fn leader(&self) -> Option<Team> {
+        if self.home_wins >= self.wins_needed() {
+            Some(Team::Home)
+        } else if self.away_wins >= self.wins_needed() {
+            Some(Team::Away)
+        } else {
+            None
+        }
+    }
+
+    pub fn total_goals(&self, team: Team) -> u32 {
+        self.results
+            .iter()
+            .map(|r| match team {
+                Team::Home => r.home_goals,
+                Team::Away => r.away_goals,
+            })
+            .sum()
+    }
+}
+
+fn series_progress_system(
+    mut finished_events: EventReader<MatchFinishedEvent>,
+    mut series: ResMut<Series>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    for ev in finished_events.iter() {
+        if series.phase != SeriesPhase::Playing {
+            continue;
+        }
+
+        match ev.winner {
+            Team::Home => series.home_wins += 1,
+            Team::Away => series.away_wins += 1,
+        }
+        series.results.push(MatchResult {
+            winner: ev.winner,
+            home_goals: ev.home_goals,
+            away_goals: ev.away_goals,
+            duration: ev.duration,
+        });
+
+        series.phase = if series.leader().is_some() {
+            SeriesPhase::Finished
+        } else {
+            SeriesPhase::Interstitial
+        };
+        rapier_config.physics_pipeline_active = false;
+    }
+}
+
+fn interstitial_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut series: ResMut<Series>,
+    mut rematch_events: EventWriter<RematchEvent>,
+    shot_log: Res<ShotLog>,
+    heatmap: Res<Heatmap>,
+) {
+    if series.phase != SeriesPhase::Interstitial {
+        return;
+    }
+
+    let mut next_match = false;
+    egui::Window::new("Match complete")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            if let Some(last) = series.results.last() {
+                ui.heading(format!("{} wins the match", last.winner.name()));
+                ui.label(format!("{} - {}", last.home_goals, last.away_goals));
+            }
+            ui.separator();
+            ui.label(format!(
+                "Series (best of {}): Home {} - {} Away",
+                series.best_of, series.home_wins, series.away_wins
+            ));
+            ui.label(format!("Next up: match {}", series.results.len() + 1));
+            ui.collapsing("Shot chart", |ui| shot_chart_ui(ui, &shot_log, &heatmap));
+            next_match = ui.button("Next match").clicked();
+        });
+
+    if next_match {
+        series.phase = SeriesPhase::Playing;
+        rematch_events.send(RematchEvent);
+    }
+}
+
+fn series_summary_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut series: ResMut<Series>,
+    mut rematch_events: EventWriter<RematchEvent>,
+    shot_log: Res<ShotLog>,
+    heatmap: Res<Heatmap>,
+) {
+    if series.phase != SeriesPhase::Finished {
+        return;
+    }
+
+    let mut new_series = None;
+    egui::Window::new("Series complete")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            if let Some(winner) = series.leader() {
+                ui.heading(format!(
+                    "{} takes the series {} - {}",
+                    winner.name(),
+                    series.wins(winner),
+                    series.results.len() as u32 - series.wins(winner)
+                ));
+            }
+            ui.separator();
+
+            egui::Grid::new("series_results")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Match");
+                    ui.label("Winner");
+                    ui.label("Score");
+                    ui.label("Duration");
+                    ui.end_row();
+                    for (i, result) in series.results.iter().enumerate() {
+                        ui.label(format!("{}", i + 1));
+                        ui.label(result.winner.name());
+                        ui.label(format!("{} - {}", result.home_goals, result.away_goals));
+                        ui.label(format!("{:.0}s", result.duration));
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+
+            let total_time: f32 = series.results.iter().map(|r| r.duration).sum();
+            let longest = series
+                .results
+                .iter()
+                .map(|r| r.duration)
+                .fold(0.0, f32::max);
+            ui.label(format!(
+                "Goals: Home {} - {} Away",
+                series.total_goals(Team::Home),
+                series.total_goals(Team::Away)
+            ));
+            ui.label(format!(
+                "Average match: {:.0}s, longest: {:.0}s",
+                total_time / series.results.len().max(1) as f32,
+                longest
+            ));
+            ui.collapsing("Last match shot chart", |ui| {
+                shot_chart_ui(ui, &shot_log, &heatmap)
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("New best of 3").clicked() {
+                    new_series = Some(3);
+                }
+                if ui.button("New best of 5").clicked() {
+                    new_series = Some(5);
+                }
+            });
+        });
+
+    if let Some(best_of) = new_series {
+        *series = Series::new(best_of);
+        rematch_events.send(RematchEvent);
+    }
+}
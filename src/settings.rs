@@ -0,0 +1,194 @@
+use super::input::ControllerSettingsOpen;
+use super::mods::ModRegistry;
+use super::power::PowerSaving;
+use super::storage::{self, Versioned};
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_FILE: &str = "settings.ron";
+// orbit camera pan speed at a sensitivity of 1
+#[cfg(feature = "develop")]
+pub const ORBIT_PAN_SENSITIVITY: f32 = 40.0;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<GameSettings>(SETTINGS_FILE).unwrap_or_default())
+            .insert_resource(SettingsOpen(false))
+            .add_system(settings_ui_system)
+            .add_system(window_mode_system)
+            .add_system(shadow_system);
+
+        #[cfg(feature = "develop")]
+        app.add_system(orbit_sensitivity_system);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+// player facing audio and video options, kept in the data dir between runs
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    pub master_volume: f32,
+    pub shadows: bool,
+    pub display: DisplayMode,
+    pub camera_sensitivity: f32,
+    // how quickly the follow camera catches up, per second
+    pub follow_stiffness: f32,
+    pub follow_height: f32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            shadows: true,
+            display: DisplayMode::Windowed,
+            camera_sensitivity: 1.0,
+            follow_stiffness: 3.0,
+            follow_height: 6.0,
+        }
+    }
+}
+
+impl Versioned for GameSettings {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Resource)]
+pub struct SettingsOpen(pub bool);
+
+fn settings_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    mut settings: ResMut<GameSettings>,
+    mut open: ResMut<SettingsOpen>,
+    mut controls_open: ResMut<ControllerSettingsOpen>,
+    registry: Res<ModRegistry>,
+) {
+    if !open.0 {
+        return;
+    }
+
+    let mut edited = settings.clone();
+    let mut close = false;
+    egui::Window::new("Settings")
+        .collapsible(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            // the only sounds the game plays are the ones mods bring
+            if !registry.sounds.is_empty() {
+                ui.heading("Audio");
+                ui.add(
+                    egui::Slider::new(&mut edited.master_volume, 0.0..=1.0).text("master volume"),
+                );
+                ui.separator();
+            }
+            ui.heading("Video");
+            ui.checkbox(&mut edited.shadows, "Shadows");
+            egui::ComboBox::from_label("Window")
+                .selected_text(format!("{:?}", edited.display))
+                .show_ui(ui, |ui| {
+                    for display in DisplayMode::ALL {
+                        ui.selectable_value(&mut edited.display, display, format!("{:?}", display));
+                    }
+                });
+            ui.separator();
+            ui.heading("Camera");
+            // only the orbit camera takes mouse input
+            #[cfg(feature = "develop")]
+            ui.add(
+                egui::Slider::new(&mut edited.camera_sensitivity, 0.1..=3.0).text("sensitivity"),
+            );
+            // develop builds fly the orbit camera instead of the follow camera
+            #[cfg(not(feature = "develop"))]
+            {
+                ui.add(
+                    egui::Slider::new(&mut edited.follow_stiffness, 0.5..=10.0)
+                        .text("follow stiffness"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut edited.follow_height, 2.0..=15.0).text("follow height"),
+                );
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Controls").clicked() {
+                    controls_open.0 = true;
+                }
+                close = ui.button("Close").clicked();
+            });
+        });
+
+    if edited != *settings {
+        *settings = edited;
+    }
+    // written once on close rather than on every slider step
+    if close {
+        storage::save(SETTINGS_FILE, &*settings);
+        open.0 = false;
+    }
+}
+
+fn window_mode_system(settings: Res<GameSettings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+    let mode = settings.display.window_mode();
+    if window.mode() != mode {
+        window.set_mode(mode);
+    }
+}
+
+// power saving turns shadows off too, whatever the setting says
+fn shadow_system(
+    settings: Res<GameSettings>,
+    power: Res<PowerSaving>,
+    mut lights: Query<&mut PointLight>,
+) {
+    let enabled = settings.shadows && !power.active;
+    for mut light in lights.iter_mut() {
+        if light.shadows_enabled != enabled {
+            light.shadows_enabled = enabled;
+        }
+    }
+}
+
+#[cfg(feature = "develop")]
+fn orbit_sensitivity_system(
+    settings: Res<GameSettings>,
+    mut camera_query: Query<&mut simula_camera::orbitcam::OrbitCamera>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut camera in camera_query.iter_mut() {
+        camera.pan_sensitivity = ORBIT_PAN_SENSITIVITY * settings.camera_sensitivity;
+    }
+}
@@ -0,0 +1,93 @@
+use super::arena::{Arena, SkySettings};
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+
+// well inside the camera's far plane, well outside anything in the arena
+const SKY_RADIUS: f32 = 400.0;
+
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(sky_system);
+    }
+}
+
+#[derive(Component)]
+struct SkyDome;
+
+fn rgb([r, g, b]: [f32; 3]) -> Color {
+    Color::rgb(r, g, b)
+}
+
+// a sphere seen from inside, coloured ground to horizon to zenith
+fn dome_mesh(sky: &SkySettings) -> Mesh {
+    let mut mesh = Mesh::from(shape::UVSphere {
+        radius: SKY_RADIUS,
+        sectors: 32,
+        stacks: 16,
+    });
+    let Some(positions) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return mesh;
+    };
+    let colors: Vec<[f32; 4]> = positions
+        .as_float3()
+        .unwrap_or_default()
+        .iter()
+        .map(|[_, y, _]| {
+            let height = y / SKY_RADIUS;
+            let (from, to, t) = if height >= 0.0 {
+                (sky.horizon, sky.zenith, height.powf(0.5))
+            } else {
+                (sky.horizon, sky.ground, (-height).powf(0.5))
+            };
+            let from = Vec3::from(from);
+            from.lerp(Vec3::from(to), t).extend(1.0).to_array()
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh
+}
+
+// rebuilds the dome and relights the scene whenever the arena's sky differs
+// from the one last applied, editor tweaks and reloads included
+fn sky_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut applied: Local<Option<SkySettings>>,
+    arena: Res<Arena>,
+    dome_query: Query<Entity, With<SkyDome>>,
+) {
+    let sky = &arena.descriptor.sky;
+    if applied.as_ref() == Some(sky) {
+        return;
+    }
+    *applied = Some(sky.clone());
+
+    for entity in dome_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(ClearColor(rgb(sky.horizon)));
+    // bevy has no image based lighting yet, an ambient term tinted like the sky
+    // overhead stands in for it
+    let ambient = Vec3::from(sky.zenith).lerp(Vec3::from(sky.horizon), 0.5);
+    commands.insert_resource(AmbientLight {
+        color: rgb(ambient.to_array()),
+        brightness: sky.ambient,
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(dome_mesh(sky)),
+            material: materials.add(StandardMaterial {
+                unlit: true,
+                cull_mode: None,
+                ..default()
+            }),
+            ..default()
+        },
+        NotShadowCaster,
+        SkyDome,
+        Name::new("sky"),
+    ));
+}
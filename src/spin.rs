@@ -0,0 +1,43 @@
+use super::config::GameConfig;
+use super::marks::Ball;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub const MAGNUS_STAGE: &str = "magnus";
+
+pub struct SpinPlugin;
+
+impl Plugin for SpinPlugin {
+    fn build(&self, app: &mut App) {
+        // rapier's stages are only in the main schedule with its default setup, the
+        // fixed and rollback schedules add the magnus stage to their own
+        if app
+            .schedule
+            .get_stage::<SystemStage>(PhysicsStages::SyncBackend)
+            .is_some()
+        {
+            app.add_stage_before(PhysicsStages::SyncBackend, MAGNUS_STAGE, magnus_stage());
+        }
+    }
+}
+
+// runs ahead of rapier syncing forces to its bodies, so every step gets the force
+// from the velocity it starts with
+pub fn magnus_stage() -> SystemStage {
+    SystemStage::single(magnus_system)
+}
+
+// a spinning ball is pushed along spin x velocity, so sidespin bends the flight
+// and topspin dips it
+fn magnus_system(
+    config: Res<GameConfig>,
+    mut ball_query: Query<(&Velocity, &mut ExternalForce), With<Ball>>,
+) {
+    for (velocity, mut force) in ball_query.iter_mut() {
+        force.force = if config.ball.curve {
+            velocity.angvel.cross(velocity.linvel) * config.ball.magnus
+        } else {
+            Vec3::ZERO
+        };
+    }
+}
@@ -0,0 +1,140 @@
+use super::marks::{GameCamera, Player, PlayerIndex};
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+
+// behind and above the player, on the home side so the controls keep their directions
+const FOLLOW_OFFSET: Vec3 = Vec3::new(-1.0, 7.0, -11.0);
+const FOLLOW_STIFFNESS: f32 = 4.0;
+
+pub struct SplitScreenPlugin;
+
+impl Plugin for SplitScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SplitScreen::default())
+            .add_system(split_screen_system)
+            .add_system(split_viewport_system.after(split_screen_system))
+            .add_system(follow_player_system.after(split_screen_system));
+    }
+}
+
+// whether the window is currently split between two local players
+#[derive(Resource, Default)]
+pub struct SplitScreen {
+    pub active: bool,
+    // where the shared camera sat before the split, put back when it ends
+    restore: Option<Transform>,
+}
+
+// the player a GameCamera follows while the screen is split
+#[derive(Component)]
+pub struct PlayerView(pub usize);
+
+fn split_screen_system(
+    mut commands: Commands,
+    mut split: ResMut<SplitScreen>,
+    player_query: Query<&PlayerIndex, With<Player>>,
+    mut camera_query: Query<
+        (Entity, &mut Camera, &mut Transform, Option<&PlayerView>),
+        With<GameCamera>,
+    >,
+) {
+    let wanted = player_query.iter().any(|index| index.0 == 1);
+    if wanted == split.active {
+        return;
+    }
+
+    if wanted {
+        let Some((entity, _, tf, _)) = camera_query.iter().find(|(.., view)| view.is_none()) else {
+            return;
+        };
+        split.restore = Some(*tf);
+        commands.entity(entity).insert(PlayerView(0));
+        commands.spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    priority: 1,
+                    ..default()
+                },
+                // the first camera already cleared the whole window
+                camera_3d: Camera3d {
+                    clear_color: ClearColorConfig::None,
+                    ..default()
+                },
+                transform: *tf,
+                ..default()
+            },
+            // the hud is laid out over the whole window once
+            UiCameraConfig { show_ui: false },
+            GameCamera,
+            PlayerView(1),
+            Name::new("player 2 camera"),
+        ));
+    } else {
+        for (entity, mut camera, mut tf, view) in camera_query.iter_mut() {
+            match view {
+                Some(PlayerView(0)) => {
+                    commands.entity(entity).remove::<PlayerView>();
+                    camera.viewport = None;
+                    if let Some(restore) = split.restore.take() {
+                        *tf = restore;
+                    }
+                }
+                Some(_) => commands.entity(entity).despawn_recursive(),
+                None => {}
+            }
+        }
+    }
+    split.active = wanted;
+}
+
+// left half for the first player, right half for the second, kept up with resizes
+fn split_viewport_system(
+    split: Res<SplitScreen>,
+    windows: Res<Windows>,
+    mut camera_query: Query<(&mut Camera, &PlayerView), With<GameCamera>>,
+) {
+    if !split.active {
+        return;
+    }
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+
+    let half = UVec2::new(window.physical_width() / 2, window.physical_height());
+    for (mut camera, view) in camera_query.iter_mut() {
+        let viewport = Viewport {
+            physical_position: UVec2::new(half.x * view.0 as u32, 0),
+            physical_size: half,
+            ..default()
+        };
+        let current = camera
+            .viewport
+            .as_ref()
+            .map(|v| (v.physical_position, v.physical_size));
+        if current != Some((viewport.physical_position, viewport.physical_size)) {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+fn follow_player_system(
+    split: Res<SplitScreen>,
+    mut camera_query: Query<(&PlayerView, &mut Transform), (With<GameCamera>, Without<Player>)>,
+    player_query: Query<(&PlayerIndex, &Transform), With<Player>>,
+    time: Res<Time>,
+) {
+    if !split.active {
+        return;
+    }
+
+    let t = (FOLLOW_STIFFNESS * time.delta_seconds()).min(1.0);
+    for (view, mut tf) in camera_query.iter_mut() {
+        let Some((_, player_tf)) = player_query.iter().find(|(index, _)| index.0 == view.0) else {
+            continue;
+        };
+        let focus = player_tf.translation;
+        tf.translation = tf.translation.lerp(focus + FOLLOW_OFFSET, t);
+        tf.look_at(focus, Vec3::Y);
+    }
+}
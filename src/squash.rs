@@ -0,0 +1,109 @@
+use super::marks::Ball;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+// velocity change in one frame that counts as a hard contact
+const IMPACT_THRESHOLD: f32 = 3.0;
+const IMPACT_SQUASH_PER_MPS: f32 = 0.03;
+const MAX_SQUASH: f32 = 0.35;
+const SQUASH_RECOVERY: f32 = 6.0;
+// speed above which the ball starts to visibly stretch
+const STRETCH_SPEED: f32 = 8.0;
+const STRETCH_PER_MPS: f32 = 0.015;
+const MAX_STRETCH: f32 = 0.25;
+
+pub struct SquashPlugin;
+
+impl Plugin for SquashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(impact_squash_system)
+            .add_system(squash_stretch_system.after(impact_squash_system));
+    }
+}
+
+#[derive(Component, Default)]
+pub struct SquashStretch {
+    prev_vel: Vec3,
+    squash: f32,
+    axis: Vec3,
+}
+
+// rotates into the deformation axis and scales along it
+#[derive(Component)]
+pub struct BallDeform;
+
+// undoes the deform rotation so the texture keeps following the physics body
+#[derive(Component)]
+pub struct BallMesh;
+
+fn impact_squash_system(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut ball_query: Query<(Entity, &Velocity, &mut SquashStretch), With<Ball>>,
+) {
+    let collided: Vec<Entity> = collision_events
+        .iter()
+        .filter_map(|ev| match ev {
+            CollisionEvent::Started(a, b, _) => Some([*a, *b]),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+
+    for (entity, velocity, mut squash) in ball_query.iter_mut() {
+        // the impact normal is the direction the contact pushed the ball
+        let delta = velocity.linvel - squash.prev_vel;
+        squash.prev_vel = velocity.linvel;
+
+        if collided.contains(&entity) && delta.length() > IMPACT_THRESHOLD {
+            let amount = (delta.length() * IMPACT_SQUASH_PER_MPS).min(MAX_SQUASH);
+            if amount > squash.squash {
+                squash.squash = amount;
+                squash.axis = delta.normalize();
+            }
+        }
+    }
+}
+
+fn squash_stretch_system(
+    mut ball_query: Query<(&Transform, &Velocity, &mut SquashStretch, &Children), With<Ball>>,
+    mut deform_query: Query<(&mut Transform, &Children), (With<BallDeform>, Without<Ball>)>,
+    mut mesh_query: Query<&mut Transform, (With<BallMesh>, Without<BallDeform>, Without<Ball>)>,
+    time: Res<Time>,
+) {
+    for (ball_tf, velocity, mut squash, children) in ball_query.iter_mut() {
+        squash.squash =
+            (squash.squash - SQUASH_RECOVERY * squash.squash * time.delta_seconds()).max(0.0);
+
+        let speed = velocity.linvel.length();
+        let stretch = ((speed - STRETCH_SPEED) * STRETCH_PER_MPS).clamp(0.0, MAX_STRETCH);
+
+        // squash wins while it's fresh, otherwise stretch along the flight path
+        let (axis, amount) = if squash.squash > 0.01 {
+            (squash.axis, -squash.squash)
+        } else if stretch > 0.0 {
+            (velocity.linvel / speed, stretch)
+        } else {
+            (Vec3::Y, 0.0)
+        };
+
+        // deformation is specified in world space, the children inherit the body rotation
+        let local_axis = ball_tf.rotation.inverse() * axis;
+        let align = Quat::from_rotation_arc(Vec3::Y, local_axis.normalize_or_zero());
+        // keep the volume roughly constant
+        let side = 1.0 / (1.0 + amount).sqrt();
+        let scale = Vec3::new(side, 1.0 + amount, side);
+
+        for child in children.iter() {
+            let Ok((mut deform_tf, grandchildren)) = deform_query.get_mut(*child) else {
+                continue;
+            };
+            deform_tf.rotation = align;
+            deform_tf.scale = scale;
+            for grandchild in grandchildren.iter() {
+                if let Ok(mut mesh_tf) = mesh_query.get_mut(*grandchild) {
+                    mesh_tf.rotation = align.inverse();
+                }
+            }
+        }
+    }
+}
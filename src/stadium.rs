@@ -0,0 +1,104 @@
+use super::arena::{Arena, StadiumColliders, StadiumSettings};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+// nodes named like this are collision proxies, never drawn
+const PROXY_SUFFIX: &str = "-col";
+
+pub struct StadiumPlugin;
+
+impl Plugin for StadiumPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(stadium_system)
+            .add_system(stadium_colliders_system);
+    }
+}
+
+// root of the scene around the board
+#[derive(Component)]
+struct Stadium(StadiumColliders);
+
+// respawns the scene whenever the arena asks for a different one, or none
+fn stadium_system(
+    mut commands: Commands,
+    mut applied: Local<Option<Option<StadiumSettings>>>,
+    asset_server: Res<AssetServer>,
+    arena: Res<Arena>,
+    stadium_query: Query<Entity, With<Stadium>>,
+) {
+    let stadium = &arena.descriptor.stadium;
+    if applied.as_ref() == Some(stadium) {
+        return;
+    }
+    *applied = Some(stadium.clone());
+
+    for entity in stadium_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(stadium) = stadium else {
+        return;
+    };
+    commands.spawn((
+        SceneBundle {
+            scene: asset_server.load(format!("{}#Scene0", stadium.scene)),
+            transform: Transform::from_translation(Vec3::from(stadium.position))
+                .with_scale(Vec3::splat(stadium.scale)),
+            ..default()
+        },
+        Stadium(stadium.colliders),
+        Name::new("stadium"),
+    ));
+}
+
+// meshes show up once the scene is spawned, proxies get hidden and whatever the
+// stadium's collider setting picks gets a trimesh
+fn stadium_colliders_system(
+    mut commands: Commands,
+    mut visibility_query: Query<&mut Visibility>,
+    mesh_query: Query<(Entity, &Handle<Mesh>), Added<Handle<Mesh>>>,
+    parent_query: Query<&Parent>,
+    name_query: Query<&Name>,
+    stadium_query: Query<&Stadium>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for (entity, handle) in mesh_query.iter() {
+        let mut proxy = false;
+        let mut colliders = None;
+        let mut current = entity;
+        while let Ok(parent) = parent_query.get(current) {
+            current = parent.get();
+            if let Ok(name) = name_query.get(current) {
+                proxy |= name.as_str().ends_with(PROXY_SUFFIX);
+            }
+            if let Ok(Stadium(setting)) = stadium_query.get(current) {
+                colliders = Some(*setting);
+                break;
+            }
+        }
+        let Some(colliders) = colliders else {
+            continue;
+        };
+
+        if proxy {
+            if let Ok(mut visibility) = visibility_query.get_mut(entity) {
+                visibility.is_visible = false;
+            }
+        }
+        let wanted = match colliders {
+            StadiumColliders::None => false,
+            StadiumColliders::Proxies => proxy,
+            StadiumColliders::Meshes => !proxy,
+        };
+        if !wanted {
+            continue;
+        }
+        let collider = meshes
+            .get(handle)
+            .and_then(|mesh| Collider::from_bevy_mesh(mesh, &ComputedColliderShape::TriMesh));
+        let Some(collider) = collider else {
+            warn!("Stadium mesh has no usable triangles for a collider");
+            continue;
+        };
+        commands.entity(entity).insert(collider);
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// everything persisted by the game lives under one directory, overridable for tests
+pub fn data_dir() -> PathBuf {
+    std::env::var("PLAYGROUND_DATA")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("saves"))
+}
+
+// a persisted format, written as `(version: N, data: ...)` so later releases can
+// tell old files apart and upgrade them instead of throwing them away
+pub trait Versioned: Serialize + DeserializeOwned {
+    const VERSION: u32;
+
+    // reads a whole file written at an older `version`, version 0 being the bare
+    // files from before versioning. bump VERSION and override this when a change
+    // can't be covered by serde defaults
+    fn migrate(version: u32, text: &str) -> Result<Self, String> {
+        if version == 0 {
+            ron::from_str(text).map_err(|err| err.to_string())
+        } else {
+            ron::from_str::<Envelope<Self>>(text)
+                .map(|envelope| envelope.data)
+                .map_err(|err| err.to_string())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+pub fn parse<T: Versioned>(text: &str) -> Result<T, String> {
+    // ron skips the fields it isn't asked about, so any envelope yields its header
+    let version = ron::from_str::<Header>(text).map_or(0, |header| header.version);
+    if version > T::VERSION {
+        return Err(format!(
+            "written by a newer version (format {}, this build reads up to {})",
+            version,
+            T::VERSION
+        ));
+    }
+    if version == T::VERSION {
+        return ron::from_str::<Envelope<T>>(text)
+            .map(|envelope| envelope.data)
+            .map_err(|err| err.to_string());
+    }
+    T::migrate(version, text).map_err(|err| format!("migrating from format {}: {}", version, err))
+}
+
+pub fn to_string<T: Versioned>(value: &T) -> Result<String, String> {
+    let envelope = Envelope {
+        version: T::VERSION,
+        data: value,
+    };
+    ron::ser::to_string_pretty(&envelope, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+}
+
+// reads a versioned file, upgrading it if needed. anything that isn't current is
+// copied aside first, the next save would otherwise overwrite the only copy
+pub fn read<T: Versioned>(path: &Path) -> Result<T, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let version = ron::from_str::<Header>(&text).map_or(0, |header| header.version);
+    let result = parse(&text);
+    if version != T::VERSION || result.is_err() {
+        let backup = PathBuf::from(format!("{}.v{}.bak", path.display(), version));
+        if !backup.exists() {
+            match std::fs::write(&backup, &text) {
+                Ok(()) => info!("Kept a copy of {} at {}", path.display(), backup.display()),
+                Err(err) => warn!("Failed to back up {}: {}", path.display(), err),
+            }
+        }
+    }
+    result
+}
+
+pub fn write<T: Versioned>(path: &Path, value: &T) -> Result<(), String> {
+    let text = to_string(value)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(path, text).map_err(|err| err.to_string())
+}
+
+pub fn load<T: Versioned>(name: &str) -> Option<T> {
+    let path = data_dir().join(name);
+    if !path.exists() {
+        return None;
+    }
+    match read(&path) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring unreadable {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+pub fn save<T: Versioned>(name: &str, value: &T) {
+    let path = data_dir().join(name);
+    if let Err(err) = write(&path, value) {
+        warn!("Failed to write {}: {}", path.display(), err);
+    }
+}
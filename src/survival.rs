@@ -0,0 +1,308 @@
+use super::determinism::GameRng;
+use super::difficulty::Difficulty;
+use super::game_state::GameState;
+use super::marks::{Ball, Team};
+use super::mode::{GameMode, ModeContext};
+use super::nametags::NameTag;
+use super::rematch::RematchEvent;
+use super::storage;
+use super::{GoalScoredEvent, BOARD_DIM};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const WAVE_DURATION: f32 = 25.0;
+const STARTING_LIVES: u32 = 3;
+const ATTACKER_BASE_SPEED: f32 = 2.5;
+const ATTACKER_SPEED_PER_WAVE: f32 = 0.6;
+const ATTACKER_MAX_SPEED: f32 = 9.0;
+const HIGH_SCORE_FILE: &str = "survival.ron";
+
+pub struct SurvivalPlugin;
+
+pub struct SurvivalMode;
+
+impl GameMode for SurvivalMode {
+    fn name(&self) -> &'static str {
+        "survival"
+    }
+
+    fn title(&self) -> &'static str {
+        "Survival"
+    }
+
+    fn description(&self) -> &'static str {
+        "Defend your goal against ever faster waves of attackers."
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_plugin(SurvivalPlugin);
+    }
+
+    fn timed(&self) -> bool {
+        false
+    }
+
+    // the Overrun window
+    fn own_results(&self) -> bool {
+        true
+    }
+
+    // there is no beating the waves, the run just ends when the lives are gone
+    fn winner(&self, ctx: &ModeContext) -> Option<Team> {
+        (ctx.score.away >= STARTING_LIVES).then_some(Team::Away)
+    }
+}
+
+impl Plugin for SurvivalPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SurvivalRun::default())
+            .insert_resource(
+                storage::load::<SurvivalHighScore>(HIGH_SCORE_FILE).unwrap_or_default(),
+            )
+            .add_startup_system(setup_survival)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(wave_system.after(survival_reset_system))
+                    .with_system(attacker_steering_system)
+                    .with_system(conceded_system),
+            )
+            .add_system(survival_reset_system)
+            .add_system(survival_ui_system);
+    }
+}
+
+#[derive(Component)]
+pub struct Attacker {
+    speed: f32,
+}
+
+#[derive(Resource)]
+pub struct SurvivalRun {
+    pub wave: u32,
+    pub lives: u32,
+    pub wave_timer: Timer,
+    pub over: bool,
+}
+
+impl Default for SurvivalRun {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            lives: STARTING_LIVES,
+            wave_timer: Timer::from_seconds(WAVE_DURATION, TimerMode::Once),
+            over: false,
+        }
+    }
+}
+
+impl SurvivalRun {
+    // waves fully survived, which is what the high score tracks
+    pub fn waves_cleared(&self) -> u32 {
+        self.wave.saturating_sub(1)
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct SurvivalHighScore {
+    pub best_wave: u32,
+}
+
+impl storage::Versioned for SurvivalHighScore {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Resource)]
+struct AttackerAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_survival(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(AttackerAssets {
+        mesh: meshes.add(Mesh::from(shape::Capsule::default())),
+        material: materials.add(StandardMaterial {
+            base_color: Color::LIME_GREEN,
+            ..default()
+        }),
+    });
+}
+
+fn wave_system(
+    mut commands: Commands,
+    mut run: ResMut<SurvivalRun>,
+    attacker_assets: Res<AttackerAssets>,
+    mut game_rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    if run.over {
+        return;
+    }
+
+    run.wave_timer.tick(time.delta());
+    if run.wave > 0 && !run.wave_timer.finished() {
+        return;
+    }
+
+    run.wave += 1;
+    run.wave_timer.reset();
+
+    // one more attacker every other wave, and everyone gets faster
+    let count = 1 + run.wave / 2;
+    let speed =
+        (ATTACKER_BASE_SPEED + ATTACKER_SPEED_PER_WAVE * run.wave as f32).min(ATTACKER_MAX_SPEED);
+    let rng = &mut game_rng.rng;
+    for _ in 0..count {
+        let x = rng.gen_range(-BOARD_DIM.0 / 2.0 + 1.0..BOARD_DIM.0 / 2.0 - 1.0);
+        commands.spawn((
+            PbrBundle {
+                mesh: attacker_assets.mesh.clone(),
+                material: attacker_assets.material.clone(),
+                transform: Transform::from_xyz(x, 1.1, BOARD_DIM.2 / 2.0 - 2.0),
+                ..default()
+            },
+            Collider::capsule_y(0.5, 0.5),
+            RigidBody::KinematicPositionBased,
+            Restitution::coefficient(1.5),
+            Attacker { speed },
+            Team::Away,
+            NameTag("CPU".to_string()),
+            Name::new("Attacker"),
+        ));
+    }
+    info!(
+        "Wave {}: {} attackers at speed {:.1}",
+        run.wave, count, speed
+    );
+}
+
+// get behind the ball relative to the home goal, then drive through it
+fn attacker_steering_system(
+    mut attacker_query: Query<(&Attacker, &mut Transform), Without<Ball>>,
+    ball_query: Query<&Transform, With<Ball>>,
+    run: Res<SurvivalRun>,
+    difficulty: Res<Difficulty>,
+    time: Res<Time>,
+) {
+    let Ok(ball_tf) = ball_query.get_single() else {
+        return;
+    };
+    if run.over {
+        return;
+    }
+
+    let goal = Vec3::new(0.0, 0.0, -(BOARD_DIM.2 / 2.0));
+    let mut to_goal = goal - ball_tf.translation;
+    to_goal.y = 0.0;
+    let to_goal = to_goal.normalize_or_zero();
+
+    for (attacker, mut tf) in attacker_query.iter_mut() {
+        let behind = ball_tf.translation - to_goal * 1.2;
+        let mut offset = behind - tf.translation;
+        offset.y = 0.0;
+
+        let target = if offset.length() < 0.5 {
+            ball_tf.translation + to_goal
+        } else {
+            behind
+        };
+        let mut direction = target - tf.translation;
+        direction.y = 0.0;
+
+        tf.translation += direction.normalize_or_zero()
+            * attacker.speed
+            * difficulty.speed_scale()
+            * time.delta_seconds();
+        tf.translation.x = tf
+            .translation
+            .x
+            .clamp(-BOARD_DIM.0 / 2.0 + 0.5, BOARD_DIM.0 / 2.0 - 0.5);
+        tf.translation.z = tf
+            .translation
+            .z
+            .clamp(-BOARD_DIM.2 / 2.0 + 0.5, BOARD_DIM.2 / 2.0 - 0.5);
+    }
+}
+
+// the arena's home goal counts and resets like any other, a goal there costs a life
+fn conceded_system(
+    mut goal_events: EventReader<GoalScoredEvent>,
+    mut run: ResMut<SurvivalRun>,
+    mut high_score: ResMut<SurvivalHighScore>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    for ev in goal_events.iter() {
+        if ev.against != Team::Home {
+            continue;
+        }
+        run.lives = run.lives.saturating_sub(1);
+        if run.lives == 0 && !run.over {
+            run.over = true;
+            rapier_config.physics_pipeline_active = false;
+            if run.waves_cleared() > high_score.best_wave {
+                high_score.best_wave = run.waves_cleared();
+                storage::save(HIGH_SCORE_FILE, &*high_score);
+            }
+        }
+    }
+}
+
+fn survival_reset_system(
+    mut commands: Commands,
+    mut rematch_events: EventReader<RematchEvent>,
+    mut run: ResMut<SurvivalRun>,
+    attacker_query: Query<Entity, With<Attacker>>,
+) {
+    if rematch_events.iter().count() == 0 {
+        return;
+    }
+
+    *run = SurvivalRun::default();
+    for entity in attacker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn survival_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    run: Res<SurvivalRun>,
+    high_score: Res<SurvivalHighScore>,
+    mut rematch_events: EventWriter<RematchEvent>,
+) {
+    let ctx = egui_context.ctx_mut();
+    egui::Area::new("survival_hud")
+        .anchor(egui::Align2::RIGHT_TOP, [-16.0, 16.0])
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Wave {}  Lives {}  Next wave in {:.0}s",
+                    run.wave,
+                    run.lives,
+                    run.wave_timer.remaining_secs()
+                ))
+                .size(20.0)
+                .color(egui::Color32::WHITE),
+            );
+            ui.label(format!("Best: wave {}", high_score.best_wave));
+        });
+
+    if run.over {
+        egui::Window::new("Overrun")
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("You held out for {} waves", run.waves_cleared()));
+                ui.label(format!("High score: {} waves", high_score.best_wave));
+                if ui.button("Try again").clicked() {
+                    rematch_events.send(RematchEvent);
+                }
+            });
+    }
+}
@@ -0,0 +1,420 @@
+use super::cli::StartupArgs;
+use super::input::{gather_input_system, PlayerInput};
+use super::marks::Ball;
+use super::storage;
+use super::Score;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::winit::WinitSettings;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const TICK_RATE: f32 = 60.0;
+const TAPE_DIR: &str = "tapes";
+// physics is not bit-for-bit repeatable across frame timings, so the ball only has
+// to land near where it did when the tape was recorded
+const BALL_TOLERANCE: f32 = 0.5;
+
+pub struct TapePlugin;
+
+impl Plugin for TapePlugin {
+    fn build(&self, app: &mut App) {
+        let Some(args) = app.world.get_resource::<StartupArgs>().cloned() else {
+            return;
+        };
+
+        if let Some(name) = args.play_tape {
+            let Some(tape) = storage::load::<InputTape>(&tape_file(&name)) else {
+                warn!("No input tape named {}", name);
+                return;
+            };
+            info!("Playing input tape {} ({} ticks)", name, tape.samples.len());
+            // main turns the verdict into the exit code, so the runner has to hand back
+            if let Some(mut winit) = app.world.get_resource_mut::<WinitSettings>() {
+                winit.return_from_run = true;
+            }
+            app.insert_resource(TapePlayer::new(tape))
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                tape_playback_system.after(gather_input_system),
+            );
+        } else if let Some(name) = args.record_tape {
+            info!("Recording input tape {}", name);
+            app.insert_resource(TapeRecorder::new(name))
+            .add_system(tape_record_system)
+            .add_system(tape_save_system);
+        }
+    }
+}
+
+fn tape_file(name: &str) -> String {
+    format!("{}/{}.ron", TAPE_DIR, name)
+}
+
+// recording and playback count ticks with the same timer, so frames that come at the
+// same pace as when the tape was made get back exactly the input they had
+fn tick_timer() -> Timer {
+    Timer::from_seconds(1.0 / TICK_RATE, TimerMode::Repeating)
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TapeSample {
+    pub movement: [f32; 2],
+    pub aim: [f32; 2],
+    pub kick: bool,
+    // tapes from before sprinting never sprint
+    #[serde(default)]
+    pub sprint: bool,
+    #[serde(default)]
+    pub dash: bool,
+    #[serde(default)]
+    pub jump: bool,
+    #[serde(default)]
+    pub boost: bool,
+}
+
+impl From<&PlayerInput> for TapeSample {
+    fn from(input: &PlayerInput) -> Self {
+        Self {
+            movement: input.movement.to_array(),
+            aim: input.aim.to_array(),
+            kick: input.kick,
+            sprint: input.sprint,
+            dash: input.dash_pressed,
+            jump: input.jump_pressed,
+            boost: input.boost,
+        }
+    }
+}
+
+// what the recorded session ended with, checked again at the end of playback
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TapeOutcome {
+    pub goals: u32,
+    pub conceded: u32,
+    pub ball: [f32; 3],
+}
+
+impl TapeOutcome {
+    fn capture(score: &Score, ball: Option<&Transform>) -> Self {
+        Self {
+            goals: score.home,
+            conceded: score.away,
+            ball: ball.map(|tf| tf.translation.to_array()).unwrap_or_default(),
+        }
+    }
+
+    fn matches(&self, other: &TapeOutcome) -> bool {
+        self.goals == other.goals
+            && self.conceded == other.conceded
+            && Vec3::from(self.ball).distance(Vec3::from(other.ball)) <= BALL_TOLERANCE
+    }
+}
+
+// the player's action stream, one sample per tick at TICK_RATE
+#[derive(Default, Serialize, Deserialize)]
+pub struct InputTape {
+    pub samples: Vec<TapeSample>,
+    pub outcome: Option<TapeOutcome>,
+}
+
+impl storage::Versioned for InputTape {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Resource)]
+struct TapeRecorder {
+    name: String,
+    tape: InputTape,
+    timer: Timer,
+    // a tap shorter than a tick still has to make it onto the tape
+    kick_latched: bool,
+    dash_latched: bool,
+    jump_latched: bool,
+}
+
+impl TapeRecorder {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            tape: InputTape::default(),
+            timer: tick_timer(),
+            kick_latched: false,
+            dash_latched: false,
+            jump_latched: false,
+        }
+    }
+}
+
+fn tape_record_system(
+    mut recorder: ResMut<TapeRecorder>,
+    input: Res<PlayerInput>,
+    time: Res<Time>,
+) {
+    recorder.kick_latched |= input.kick_pressed;
+    recorder.dash_latched |= input.dash_pressed;
+    recorder.jump_latched |= input.jump_pressed;
+    recorder.timer.tick(time.delta());
+    for _ in 0..recorder.timer.times_finished_this_tick() {
+        let sample = TapeSample {
+            kick: input.kick || recorder.kick_latched,
+            dash: recorder.dash_latched,
+            jump: recorder.jump_latched,
+            ..TapeSample::from(&*input)
+        };
+        recorder.tape.samples.push(sample);
+        recorder.kick_latched = false;
+        recorder.dash_latched = false;
+        recorder.jump_latched = false;
+    }
+}
+
+fn tape_save_system(
+    mut exit_events: EventReader<AppExit>,
+    mut recorder: ResMut<TapeRecorder>,
+    ball_query: Query<&Transform, With<Ball>>,
+    score: Res<Score>,
+) {
+    if exit_events.iter().count() == 0 {
+        return;
+    }
+    recorder.tape.outcome = Some(TapeOutcome::capture(&score, ball_query.iter().next()));
+    storage::save(&tape_file(&recorder.name), &recorder.tape);
+    info!(
+        "Saved input tape {} ({} ticks)",
+        recorder.name,
+        recorder.tape.samples.len()
+    );
+}
+
+// set when a played tape ended somewhere else than it was recorded; main keeps a
+// clone and fails the process with it once the app has shut down
+#[derive(Resource, Clone, Default)]
+pub struct TapeVerdict(Arc<AtomicBool>);
+
+impl TapeVerdict {
+    pub fn drifted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Resource)]
+struct TapePlayer {
+    tape: InputTape,
+    timer: Timer,
+    // ticks played so far, the sample in use is the last of them
+    ticks: usize,
+}
+
+impl TapePlayer {
+    fn new(tape: InputTape) -> Self {
+        Self {
+            tape,
+            timer: tick_timer(),
+            ticks: 0,
+        }
+    }
+}
+
+// overrides whatever the devices produced this frame with the taped sample, then
+// quits once the tape runs out, with the verdict set if the outcome drifted
+fn tape_playback_system(
+    mut player: ResMut<TapePlayer>,
+    mut input: ResMut<PlayerInput>,
+    mut exit: EventWriter<AppExit>,
+    verdict: Res<TapeVerdict>,
+    ball_query: Query<&Transform, With<Ball>>,
+    score: Res<Score>,
+    time: Res<Time>,
+) {
+    player.timer.tick(time.delta());
+    let finished = player.timer.times_finished_this_tick() as usize;
+    player.ticks += finished;
+
+    // nothing was recorded before the first tick
+    let Some(tick) = player.ticks.checked_sub(1) else {
+        *input = PlayerInput::default();
+        return;
+    };
+    let Some(sample) = player.tape.samples.get(tick).copied() else {
+        let outcome = TapeOutcome::capture(&score, ball_query.iter().next());
+        match player.tape.outcome {
+            Some(expected) if !expected.matches(&outcome) => {
+                error!(
+                    "Input tape outcome drifted: expected {:?}, got {:?}",
+                    expected, outcome
+                );
+                verdict.0.store(true, Ordering::Relaxed);
+            }
+            Some(_) => info!("Input tape outcome matches: {:?}", outcome),
+            None => info!("Input tape finished: {:?}", outcome),
+        }
+        exit.send(AppExit);
+        return;
+    };
+
+    // edges only fire on the first frame of a tick
+    let new_tick = finished > 0;
+    let previous = (player.ticks - finished)
+        .checked_sub(1)
+        .and_then(|t| player.tape.samples.get(t))
+        .map(|s| s.kick)
+        .unwrap_or_default();
+
+    input.movement = Vec2::from(sample.movement);
+    input.aim = Vec2::from(sample.aim);
+    input.kick = sample.kick;
+    input.kick_pressed = new_tick && sample.kick && !previous;
+    input.kick_released = new_tick && !sample.kick && previous;
+    input.sprint = sample.sprint;
+    input.dash_pressed = new_tick && sample.dash;
+    input.jump_pressed = new_tick && sample.jump;
+    input.boost = sample.boost;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::StartupArgs;
+    use crate::determinism::GameRng;
+    use crate::game_state::GameState;
+    use crate::headless::build_headless_match;
+    use crate::input::LocalInputs;
+    use crate::marks::{Player, PlayerIndex, Team};
+    use crate::mode::ModeRegistry;
+    use crate::mutators::Mutators;
+    use crate::player::{Dash, Facing, Jump, KickCharge, PlayerPlugin, Stamina};
+    use crate::progression::SelectedShot;
+    use crate::{MatchEntities, PLAYER_SPAWN};
+    use bevy::time::TimePlugin;
+    use bevy_rapier3d::prelude::*;
+    use std::time::{Duration, Instant};
+
+    const FRAMES: usize = 180;
+    // where the ball waits, in front of the far goal, and the player behind it
+    const BALL_FROM_GOAL: f32 = 3.5;
+    const PLAYER_FROM_BALL: f32 = 2.5;
+
+    // the frame clock steps exactly one tick at a time, the same in both runs
+    fn frame_clock_system(mut time: ResMut<Time>, mut now: Local<Option<Instant>>) {
+        let next = now.map_or_else(Instant::now, |now| {
+            now + Duration::from_secs_f32(1.0 / TICK_RATE)
+        });
+        *now = Some(next);
+        time.update_with_instant(next);
+    }
+
+    // stand through the first frame like the tape does, walk up to the ball, then
+    // hold kick for a second and let it go
+    fn scripted_input_system(mut input: ResMut<PlayerInput>, mut frame: Local<usize>) {
+        *frame += 1;
+        let f = *frame;
+        *input = PlayerInput::default();
+        if (2..12).contains(&f) {
+            input.movement = Vec2::Y;
+        }
+        input.kick = (14..74).contains(&f);
+        input.kick_pressed = f == 14;
+        input.kick_released = f == 74;
+    }
+
+    // the headless match with a real player in it, moving and kicking through the
+    // same systems as the game
+    fn match_app() -> App {
+        let mode = ModeRegistry::default().get("classic").unwrap();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins.build().disable::<TimePlugin>())
+            .init_resource::<Time>()
+            .add_system_to_stage(CoreStage::First, frame_clock_system);
+        build_headless_match(&mut app, mode, StartupArgs::default());
+        app.insert_resource(GameRng::new(0))
+            .add_state(GameState::Playing)
+            .add_plugin(PlayerPlugin)
+            .init_resource::<PlayerInput>()
+            .init_resource::<LocalInputs>()
+            .init_resource::<Mutators>()
+            .init_resource::<SelectedShot>()
+            .init_resource::<TapeVerdict>();
+        app
+    }
+
+    // the first frame spawns the arena and places the goals, then the ball is put
+    // still in front of the far goal with the player lined up behind it
+    fn line_up(app: &mut App) -> Entity {
+        app.update();
+        let entities = app.world.resource::<MatchEntities>();
+        let (ball, goal) = (entities.ball.unwrap(), entities.goal.unwrap());
+        let goal_at = app.world.get::<GlobalTransform>(goal).unwrap().translation();
+        let ball_at = Vec3::new(goal_at.x, 0.6, goal_at.z - BALL_FROM_GOAL);
+        app.world.get_mut::<Transform>(ball).unwrap().translation = ball_at;
+        *app.world.get_mut::<Velocity>(ball).unwrap() = Velocity::zero();
+        app.world.spawn((
+            TransformBundle::from(Transform::from_xyz(
+                ball_at.x,
+                PLAYER_SPAWN.y,
+                ball_at.z - PLAYER_FROM_BALL,
+            )),
+            Collider::capsule_y(0.5, 0.5),
+            RigidBody::KinematicPositionBased,
+            KinematicCharacterController {
+                autostep: None,
+                ..default()
+            },
+            Player,
+            PlayerIndex(0),
+            Facing::default(),
+            KickCharge::default(),
+            Stamina::default(),
+            Dash::default(),
+            Jump::default(),
+            Team::Home,
+        ));
+        ball
+    }
+
+    fn outcome(app: &mut App) -> TapeOutcome {
+        let ball = *app
+            .world
+            .query_filtered::<&Transform, With<Ball>>()
+            .single(&app.world);
+        TapeOutcome::capture(app.world.resource::<Score>(), Some(&ball))
+    }
+
+    #[test]
+    fn replayed_tape_scores_the_recorded_goal() {
+        let mut recording = match_app();
+        recording
+            .insert_resource(TapeRecorder::new("test".to_string()))
+            .add_system_to_stage(CoreStage::PreUpdate, scripted_input_system)
+            .add_system(tape_record_system);
+        let ball = line_up(&mut recording);
+        let start = recording.world.get::<Transform>(ball).unwrap().translation;
+        for _ in 1..FRAMES {
+            recording.update();
+        }
+        let recorded = outcome(&mut recording);
+        // the walk and the kick put the ball in the far goal
+        assert_eq!((recorded.goals, recorded.conceded), (1, 0));
+        assert!(recorded.ball[2] > start.z + BALL_FROM_GOAL);
+
+        let mut recorder = recording.world.remove_resource::<TapeRecorder>().unwrap();
+        recorder.tape.outcome = Some(recorded);
+        let mut playback = match_app();
+        playback
+            .insert_resource(TapePlayer::new(recorder.tape))
+            .add_system_to_stage(CoreStage::PreUpdate, tape_playback_system);
+        line_up(&mut playback);
+        for _ in 1..FRAMES {
+            playback.update();
+        }
+        let replayed = outcome(&mut playback);
+        assert_eq!((replayed.goals, replayed.conceded), (1, 0));
+        assert!(recorded.matches(&replayed), "{:?} != {:?}", recorded, replayed);
+
+        // one frame past the tape checks the outcome and quits without a drift
+        playback.update();
+        assert!(!playback.world.resource::<TapeVerdict>().drifted());
+        assert_eq!(playback.world.resource::<Events<AppExit>>().len(), 1);
+    }
+}
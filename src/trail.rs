@@ -0,0 +1,96 @@
+use super::MatchEntities;
+use bevy::prelude::*;
+use simula_viz::lines::{LineMesh, Lines, LinesBundle, LinesMaterial};
+use std::collections::VecDeque;
+
+// samples kept at full speed, a slow ball keeps only a stub of them
+const MAX_SAMPLES: usize = 40;
+const MIN_SAMPLES: usize = 4;
+// speed at which the trail reaches full length and colour
+const FULL_SPEED: f32 = 25.0;
+// a jump this big in one frame is a reset, not a shot
+const TELEPORT_DISTANCE: f32 = 3.0;
+const SLOW_COLOR: Color = Color::rgb(0.6, 0.6, 0.7);
+const FAST_COLOR: Color = Color::rgb(1.0, 0.45, 0.1);
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_trail).add_system(trail_system);
+    }
+}
+
+// lives at the origin, the samples are world positions
+#[derive(Component, Default)]
+struct BallTrail {
+    // newest first, with the ball's speed at the time
+    samples: VecDeque<(Vec3, f32)>,
+}
+
+fn setup_trail(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut lines_materials: ResMut<Assets<LinesMaterial>>,
+    line_mesh: Res<LineMesh>,
+) {
+    commands.spawn((
+        LinesBundle {
+            mesh: meshes.add(line_mesh.clone()),
+            material: lines_materials.add(LinesMaterial {}),
+            ..default()
+        },
+        BallTrail::default(),
+        Name::new("ball trail"),
+    ));
+}
+
+fn trail_system(
+    mut trail_query: Query<(&mut BallTrail, &mut Lines)>,
+    ball_query: Query<&Transform>,
+    entities: Res<MatchEntities>,
+    time: Res<Time>,
+) {
+    let Ok((mut trail, mut lines)) = trail_query.get_single_mut() else {
+        return;
+    };
+    let Some(Ok(ball_tf)) = entities.ball.map(|ball| ball_query.get(ball)) else {
+        trail.samples.clear();
+        return;
+    };
+
+    let position = ball_tf.translation;
+    let moved = trail
+        .samples
+        .front()
+        .map(|(last, _)| position.distance(*last))
+        .unwrap_or_default();
+    if moved > TELEPORT_DISTANCE {
+        trail.samples.clear();
+    }
+    let speed = moved / time.delta_seconds().max(f32::EPSILON);
+    trail.samples.push_front((position, speed));
+
+    let t = (speed / FULL_SPEED).clamp(0.0, 1.0);
+    let length = MIN_SAMPLES + ((MAX_SAMPLES - MIN_SAMPLES) as f32 * t) as usize;
+    trail.samples.truncate(length);
+
+    // fades toward the floor's black as it gets older
+    let count = trail.samples.len();
+    for (i, pair) in trail
+        .samples
+        .iter()
+        .collect::<Vec<_>>()
+        .windows(2)
+        .enumerate()
+    {
+        let (start, start_speed) = *pair[0];
+        let (end, _) = *pair[1];
+        let heat = (start_speed / FULL_SPEED).clamp(0.0, 1.0);
+        let fade = 1.0 - i as f32 / count as f32;
+        let slow = Vec4::from(SLOW_COLOR.as_rgba_f32());
+        let fast = Vec4::from(FAST_COLOR.as_rgba_f32());
+        let color = slow.lerp(fast, heat) * fade;
+        lines.line_colored(start, end, Color::rgb(color.x, color.y, color.z));
+    }
+}
@@ -0,0 +1,134 @@
+use super::arena_events::ArenaEvent;
+use super::determinism::GameRng;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+
+const TWITCH_IRC: &str = "irc.chat.twitch.tv:6667";
+const VOTE_WINDOW: f32 = 30.0;
+
+pub struct TwitchPlugin;
+
+impl Plugin for TwitchPlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(channel_name) = std::env::var("TWITCH_CHANNEL") else {
+            info!("TWITCH_CHANNEL not set, chat voting disabled");
+            return;
+        };
+
+        app.insert_resource(TwitchChat {
+            messages: Mutex::new(connect(channel_name.to_lowercase())),
+        })
+        .insert_resource(ChatVote::default())
+        .add_system(chat_vote_system)
+        .add_system(chat_vote_ui_system);
+    }
+}
+
+#[derive(Resource)]
+pub struct TwitchChat {
+    messages: Mutex<Receiver<String>>,
+}
+
+#[derive(Resource)]
+pub struct ChatVote {
+    timer: Timer,
+    // wind, multiball, bigball
+    tally: [u32; 3],
+}
+
+impl Default for ChatVote {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(VOTE_WINDOW, TimerMode::Repeating),
+            tally: [0; 3],
+        }
+    }
+}
+
+const CHOICES: [&str; 3] = ["!wind", "!multiball", "!bigball"];
+
+// anonymous read-only login, chat lines are forwarded to the game through a channel
+fn connect(channel_name: String) -> Receiver<String> {
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        let result = (|| -> std::io::Result<()> {
+            let mut stream = TcpStream::connect(TWITCH_IRC)?;
+            write!(stream, "NICK justinfan{}\r\n", rand::random::<u16>())?;
+            write!(stream, "JOIN #{}\r\n", channel_name)?;
+
+            let reader = BufReader::new(stream.try_clone()?);
+            for line in reader.lines() {
+                let line = line?;
+                if line.starts_with("PING") {
+                    write!(stream, "PONG :tmi.twitch.tv\r\n")?;
+                    continue;
+                }
+                // :nick!nick@nick.tmi.twitch.tv PRIVMSG #channel :message
+                if let Some((_, message)) = line.split_once(" PRIVMSG ") {
+                    if let Some((_, text)) = message.split_once(" :") {
+                        if sender.send(text.trim().to_lowercase()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })();
+        if let Err(err) = result {
+            warn!("Twitch chat disconnected: {}", err);
+        }
+    });
+    receiver
+}
+
+fn chat_vote_system(
+    chat: Res<TwitchChat>,
+    mut vote: ResMut<ChatVote>,
+    mut arena_events: EventWriter<ArenaEvent>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    if let Ok(messages) = chat.messages.lock() {
+        for text in messages.try_iter() {
+            if let Some(choice) = CHOICES.iter().position(|c| text.starts_with(c)) {
+                vote.tally[choice] += 1;
+            }
+        }
+    }
+
+    if !vote.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let tally = vote.tally;
+    vote.tally = [0; 3];
+
+    let Some((winner, votes)) = tally.iter().enumerate().max_by_key(|(_, votes)| **votes) else {
+        return;
+    };
+    if *votes == 0 {
+        return;
+    }
+    arena_events.send(match winner {
+        0 => ArenaEvent::random_gust(&mut rng.rng),
+        1 => ArenaEvent::MultiBall,
+        _ => ArenaEvent::BigBall,
+    });
+}
+
+fn chat_vote_ui_system(mut egui_context: ResMut<EguiContext>, vote: Res<ChatVote>) {
+    egui::Area::new("chat_vote")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!(
+                "Chat vote in {:.0}s",
+                vote.timer.duration().as_secs_f32() - vote.timer.elapsed_secs()
+            ));
+            for (choice, votes) in CHOICES.iter().zip(vote.tally) {
+                ui.label(format!("{} {}", choice, votes));
+            }
+        });
+}
@@ -0,0 +1,197 @@
+use super::arena::{spawn_piece, Arena, ArenaPiece, ArenaPieceId, ArenaReloadedEvent, PieceKind};
+use super::editor::EditorSelection;
+use super::MatchEntities;
+use bevy::prelude::*;
+
+// consecutive tweaks to the same piece this close together collapse into one step,
+// otherwise every frame of a drag would need its own undo
+const MERGE_WINDOW: f64 = 0.5;
+const MAX_HISTORY: usize = 200;
+
+pub struct UndoPlugin;
+
+impl Plugin for UndoPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EditHistory::default())
+            .add_system(undo_keys_system)
+            .add_system_to_stage(CoreStage::PostUpdate, rebuild_arena_system);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EditCommand {
+    Place {
+        index: usize,
+        piece: ArenaPiece,
+    },
+    Delete {
+        index: usize,
+        piece: ArenaPiece,
+    },
+    // moves and property edits alike
+    Change {
+        index: usize,
+        before: ArenaPiece,
+        after: ArenaPiece,
+    },
+}
+
+impl EditCommand {
+    fn apply(&self, arena: &mut Arena) {
+        let pieces = &mut arena.descriptor.pieces;
+        match self {
+            EditCommand::Place { index, piece } => pieces.insert(*index, piece.clone()),
+            EditCommand::Delete { index, .. } => {
+                pieces.remove(*index);
+            }
+            EditCommand::Change { index, after, .. } => pieces[*index] = after.clone(),
+        }
+    }
+
+    fn inverse(&self) -> EditCommand {
+        match self.clone() {
+            EditCommand::Place { index, piece } => EditCommand::Delete { index, piece },
+            EditCommand::Delete { index, piece } => EditCommand::Place { index, piece },
+            EditCommand::Change {
+                index,
+                before,
+                after,
+            } => EditCommand::Change {
+                index,
+                before: after,
+                after: before,
+            },
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    undo: Vec<EditCommand>,
+    redo: Vec<EditCommand>,
+    last_record: f64,
+    // the spawned arena no longer matches the descriptor
+    pub dirty: bool,
+}
+
+impl EditHistory {
+    // applies the command to the descriptor and remembers it. Changes are expected to
+    // already be visible on the entity, everything else gets the arena rebuilt
+    pub fn record(&mut self, command: EditCommand, arena: &mut Arena, now: f64) {
+        command.apply(arena);
+        self.redo.clear();
+
+        let merge = now - self.last_record < MERGE_WINDOW;
+        self.last_record = now;
+        if let (
+            Some(EditCommand::Change {
+                index: last,
+                after: last_after,
+                ..
+            }),
+            EditCommand::Change { index, after, .. },
+        ) = (self.undo.last_mut(), &command)
+        {
+            if merge && last == index {
+                *last_after = after.clone();
+                return;
+            }
+        }
+
+        if !matches!(command, EditCommand::Change { .. }) {
+            self.dirty = true;
+        }
+        self.undo.push(command);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, arena: &mut Arena) {
+        if let Some(command) = self.undo.pop() {
+            command.inverse().apply(arena);
+            self.redo.push(command);
+            self.dirty = true;
+        }
+    }
+
+    pub fn redo(&mut self, arena: &mut Arena) {
+        if let Some(command) = self.redo.pop() {
+            command.apply(arena);
+            self.undo.push(command);
+            self.dirty = true;
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+// ctrl+z undoes, ctrl+y or ctrl+shift+z redoes
+fn undo_keys_system(
+    mut history: ResMut<EditHistory>,
+    mut arena: ResMut<Arena>,
+    keyboard: Res<Input<KeyCode>>,
+) {
+    let ctrl = keyboard.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let shift = keyboard.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    if !ctrl {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Y) || (shift && keyboard.just_pressed(KeyCode::Z)) {
+        history.redo(&mut arena);
+    } else if keyboard.just_pressed(KeyCode::Z) {
+        history.undo(&mut arena);
+    }
+}
+
+// respawns every piece from the descriptor, keeping whichever piece was selected
+fn rebuild_arena_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut history: ResMut<EditHistory>,
+    mut selection: ResMut<EditorSelection>,
+    mut entities: ResMut<MatchEntities>,
+    mut reload_events: EventReader<ArenaReloadedEvent>,
+    arena: Res<Arena>,
+    piece_query: Query<(Entity, &ArenaPieceId)>,
+) {
+    // edits made against the old file can't be undone on the new one
+    if reload_events.iter().count() > 0 {
+        history.undo.clear();
+        history.redo.clear();
+        history.dirty = true;
+    }
+    if !history.dirty {
+        return;
+    }
+    history.dirty = false;
+
+    let selected = selection
+        .0
+        .and_then(|entity| piece_query.get(entity).ok())
+        .map(|(_, ArenaPieceId(id))| *id);
+    for (entity, _) in piece_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    selection.0 = None;
+    entities.goal = None;
+    entities.home_goal = None;
+    for (id, piece) in arena.descriptor.pieces.iter().enumerate() {
+        let entity = spawn_piece(&mut commands, &mut meshes, &mut materials, id, piece);
+        match piece.kind {
+            PieceKind::GoalSensor => entities.goal = Some(entity),
+            PieceKind::HomeGoalSensor => entities.home_goal = Some(entity),
+            _ => {}
+        }
+        if selected == Some(id) {
+            selection.0 = Some(entity);
+        }
+    }
+}
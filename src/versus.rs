@@ -0,0 +1,115 @@
+use super::arena::Arena;
+use super::boost_meter::BoostMeter;
+use super::devices::{DeviceAssignment, InputDevice, KeyboardSet};
+use super::marks::{MatchEntity, Player, PlayerIndex, Team};
+use super::mode::GameMode;
+use super::nametags::NameTag;
+use super::player::{Dash, Facing, Jump, KickCharge, Stamina};
+use super::{MatchAssets, MatchEntities, BOARD_DIM};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub struct VersusPlugin;
+
+// two people on one keyboard, home attacking the far goal and away the arena's home goal;
+// the match is won through the config's score limit
+pub struct VersusMode;
+
+impl GameMode for VersusMode {
+    fn name(&self) -> &'static str {
+        "versus"
+    }
+
+    fn title(&self) -> &'static str {
+        "Versus"
+    }
+
+    fn description(&self) -> &'static str {
+        "Two players, one keyboard. WASD and Space against the arrows and Right Ctrl."
+    }
+
+    fn build(&self, app: &mut App) {
+        app.add_plugin(VersusPlugin);
+    }
+}
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_versus)
+            .add_system(second_player_spawn_system);
+    }
+}
+
+#[derive(Resource)]
+struct SecondPlayerMaterial(Handle<StandardMaterial>);
+
+fn setup_versus(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assignment: ResMut<DeviceAssignment>,
+) {
+    commands.insert_resource(SecondPlayerMaterial(materials.add(StandardMaterial {
+        base_color: Color::ORANGE,
+        ..default()
+    })));
+
+    if assignment.slots.len() < 2 {
+        assignment.slots = vec![
+            InputDevice::Keyboard(KeyboardSet::A),
+            InputDevice::Keyboard(KeyboardSet::B),
+        ];
+    }
+}
+
+// the second player is a match entity like the first, so it comes back after a rematch
+fn second_player_spawn_system(
+    mut commands: Commands,
+    player_query: Query<&PlayerIndex, With<Player>>,
+    match_assets: Option<Res<MatchAssets>>,
+    material: Res<SecondPlayerMaterial>,
+    arena: Res<Arena>,
+    entities: Res<MatchEntities>,
+) {
+    let Some(match_assets) = match_assets else {
+        return;
+    };
+    // the first player arrives with the menu's Play, the second follows it
+    if entities.player.is_none() || player_query.iter().any(|index| index.0 == 1) {
+        return;
+    }
+
+    let spawn = arena
+        .descriptor
+        .spawns
+        .iter()
+        .find(|spawn| spawn.team == Team::Away)
+        .map(|spawn| Vec3::from(spawn.position))
+        .unwrap_or(Vec3::new(0.0, 1.1, BOARD_DIM.2 / 2.0 - 1.5));
+    commands.spawn((
+        PbrBundle {
+            mesh: match_assets.player_mesh.clone(),
+            material: material.0.clone(),
+            transform: Transform::from_translation(spawn),
+            ..default()
+        },
+        Collider::capsule_y(0.5, 0.5),
+        RigidBody::KinematicPositionBased,
+        Restitution::coefficient(1.5),
+        KinematicCharacterController {
+            autostep: None,
+            ..default()
+        },
+        Player,
+        PlayerIndex(1),
+        Facing::toward_goal(Team::Away),
+        KickCharge::default(),
+        Stamina::default(),
+        Dash::default(),
+        Jump::default(),
+        BoostMeter::default(),
+        Team::Away,
+        MatchEntity,
+        NameTag("Player 2".to_string()),
+        Name::new("player 2"),
+    ));
+}
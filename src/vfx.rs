@@ -0,0 +1,144 @@
+use super::marks::Team;
+use super::{GoalScoredEvent, MatchEntities};
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use rand::Rng;
+
+const BURST_PARTICLES: usize = 80;
+const PARTICLE_LIFETIME: f32 = 1.6;
+const PARTICLE_SIZE: f32 = 0.08;
+const BURST_SPEED: f32 = 6.0;
+const GRAVITY: f32 = 9.8;
+// confetti flutters down instead of dropping like a stone
+const DRAG: f32 = 2.5;
+const CONFETTI: [Color; 5] = [
+    Color::YELLOW,
+    Color::ORANGE_RED,
+    Color::CYAN,
+    Color::LIME_GREEN,
+    Color::WHITE,
+];
+
+pub struct VfxPlugin;
+
+impl Plugin for VfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_vfx)
+            .add_system(goal_burst_system)
+            .add_system(particle_system);
+    }
+}
+
+// shared by every particle so a burst is one mesh and a handful of materials
+#[derive(Resource)]
+struct VfxAssets {
+    quad: Handle<Mesh>,
+    confetti: Vec<Handle<StandardMaterial>>,
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec3,
+    spin: Vec3,
+    timer: Timer,
+}
+
+fn setup_vfx(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let confetti = CONFETTI
+        .iter()
+        .map(|color| {
+            materials.add(StandardMaterial {
+                base_color: *color,
+                unlit: true,
+                cull_mode: None,
+                double_sided: true,
+                ..default()
+            })
+        })
+        .collect();
+    commands.insert_resource(VfxAssets {
+        quad: meshes.add(Mesh::from(shape::Quad::new(Vec2::splat(PARTICLE_SIZE)))),
+        confetti,
+    });
+}
+
+// bursts from the goal that was scored in, the ball's spot if it's gone
+fn goal_burst_system(
+    mut commands: Commands,
+    mut goal_events: EventReader<GoalScoredEvent>,
+    vfx: Res<VfxAssets>,
+    entities: Res<MatchEntities>,
+    transform_query: Query<&GlobalTransform>,
+) {
+    // purely cosmetic, drawing from GameRng would shift every roll after a goal
+    let rng = &mut rand::thread_rng();
+    for ev in goal_events.iter() {
+        let goal = match ev.against {
+            Team::Away => entities
+                .goal
+                .and_then(|goal| transform_query.get(goal).ok()),
+            Team::Home => entities
+                .home_goal
+                .and_then(|goal| transform_query.get(goal).ok()),
+        };
+        let origin = goal.map(|tf| tf.translation()).unwrap_or(ev.position);
+
+        for _ in 0..BURST_PARTICLES {
+            let direction = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(0.3..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize();
+            let material = vfx.confetti[rng.gen_range(0..vfx.confetti.len())].clone();
+            commands.spawn((
+                PbrBundle {
+                    mesh: vfx.quad.clone(),
+                    material,
+                    transform: Transform::from_translation(origin),
+                    ..default()
+                },
+                NotShadowCaster,
+                Particle {
+                    velocity: direction * BURST_SPEED * rng.gen_range(0.5..1.0),
+                    spin: Vec3::new(
+                        rng.gen_range(-10.0..10.0),
+                        rng.gen_range(-10.0..10.0),
+                        rng.gen_range(-10.0..10.0),
+                    ),
+                    timer: Timer::from_seconds(
+                        PARTICLE_LIFETIME * rng.gen_range(0.7..1.0),
+                        TimerMode::Once,
+                    ),
+                },
+                Name::new("particle"),
+            ));
+        }
+    }
+}
+
+fn particle_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut tf) in particle_query.iter_mut() {
+        particle.timer.tick(time.delta());
+        if particle.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        particle.velocity.y -= GRAVITY * dt;
+        particle.velocity *= 1.0 / (1.0 + DRAG * dt);
+        tf.translation += particle.velocity * dt;
+        let spin = particle.spin * dt;
+        tf.rotate(Quat::from_euler(EulerRot::XYZ, spin.x, spin.y, spin.z));
+        // shrink away over the last bit of life
+        tf.scale = Vec3::splat((particle.timer.percent_left() * 4.0).min(1.0));
+    }
+}
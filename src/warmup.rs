@@ -0,0 +1,177 @@
+use super::mods::ModRegistry;
+use super::worldtext::{WorldText, POPUP_SIZE};
+use bevy::asset::LoadState;
+use bevy::audio::PlaybackSettings;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier3d::prelude::*;
+
+// frames the warm-up objects stay on screen, enough for every pipeline to be
+// specialized and compiled before play starts
+const WARMUP_FRAMES: u32 = 3;
+// below the pitch but inside the camera frustum, so it is drawn and then hidden by the floor
+const HIDDEN_SPOT: Vec3 = Vec3::new(0.0, -2.0, 0.0);
+
+pub struct WarmupPlugin;
+
+impl Plugin for WarmupPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Warmup::default())
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_warmup_system)
+            .add_system(warmup_system);
+    }
+}
+
+// the loading phase: physics is held until every lazily created material, sound
+// and glyph has been through the renderer once
+#[derive(Resource, Default)]
+pub struct Warmup {
+    pub done: bool,
+    frames: u32,
+    entities: Vec<Entity>,
+    resume_physics: bool,
+}
+
+// the materials effects create on the fly, one of each pipeline they end up needing
+fn effect_materials() -> Vec<StandardMaterial> {
+    vec![
+        // boss shockwave
+        StandardMaterial {
+            base_color: Color::rgba(1.0, 0.6, 0.1, 0.8),
+            emissive: Color::ORANGE,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        },
+        // drill zones
+        StandardMaterial {
+            base_color: Color::rgba(0.2, 1.0, 0.4, 0.25),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        },
+        // attackers and arena props
+        StandardMaterial {
+            base_color: Color::LIME_GREEN,
+            ..default()
+        },
+    ]
+}
+
+fn effect_meshes() -> Vec<Mesh> {
+    vec![
+        Mesh::from(shape::Torus {
+            radius: 1.0,
+            ring_radius: 0.1,
+            ..default()
+        }),
+        Mesh::from(shape::Box::new(1.0, 1.0, 1.0)),
+        Mesh::from(shape::Capsule::default()),
+        Mesh::from(shape::UVSphere {
+            radius: 0.5,
+            ..default()
+        }),
+    ]
+}
+
+fn spawn_warmup_system(
+    mut commands: Commands,
+    mut warmup: ResMut<Warmup>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    audio: Res<Audio>,
+    registry: Option<Res<ModRegistry>>,
+) {
+    warmup.resume_physics = rapier_config.physics_pipeline_active;
+    rapier_config.physics_pipeline_active = false;
+
+    let materials: Vec<_> = effect_materials()
+        .into_iter()
+        .map(|material| materials.add(material))
+        .collect();
+    for mesh in effect_meshes() {
+        let mesh = meshes.add(mesh);
+        for material in materials.iter() {
+            let entity = commands
+                .spawn((
+                    PbrBundle {
+                        mesh: mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_translation(HIDDEN_SPOT)
+                            .with_scale(Vec3::splat(0.1)),
+                        ..default()
+                    },
+                    Name::new("Warmup"),
+                ))
+                .id();
+            warmup.entities.push(entity);
+        }
+    }
+
+    // decoding happens on first play, get it out of the way silently
+    if let Some(registry) = registry {
+        for sound in registry.sounds.values() {
+            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.0));
+        }
+    }
+}
+
+fn warmup_system(
+    mut commands: Commands,
+    mut warmup: ResMut<Warmup>,
+    mut egui_context: ResMut<EguiContext>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    asset_server: Res<AssetServer>,
+    registry: Option<Res<ModRegistry>>,
+) {
+    if warmup.done {
+        return;
+    }
+
+    // lay out every glyph world text will use at the sizes it uses them
+    let ctx = egui_context.ctx_mut();
+    egui::Area::new("warmup_glyphs")
+        .fixed_pos(egui::pos2(-10000.0, -10000.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            let sample =
+                "GOAL! +0123456789 ABCDEFGHIJKLMNOPQRSTUVWXYZ abcdefghijklmnopqrstuvwxyz.:-";
+            for size in [WorldText::new("").size, POPUP_SIZE] {
+                ui.painter().text(
+                    egui::pos2(-10000.0, -10000.0),
+                    egui::Align2::LEFT_TOP,
+                    sample,
+                    egui::FontId::proportional(size),
+                    egui::Color32::WHITE,
+                );
+            }
+        });
+    egui::Area::new("warmup_loading")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.heading("Loading...");
+        });
+
+    warmup.frames += 1;
+    let assets_loading = registry
+        .map(|registry| {
+            let handles = registry
+                .sounds
+                .values()
+                .map(|h| h.id())
+                .chain(registry.ball_skins.values().map(|h| h.id()));
+            asset_server.get_group_load_state(handles) == LoadState::Loading
+        })
+        .unwrap_or_default();
+    if warmup.frames < WARMUP_FRAMES || assets_loading {
+        return;
+    }
+
+    for entity in warmup.entities.drain(..) {
+        commands.entity(entity).despawn_recursive();
+    }
+    rapier_config.physics_pipeline_active = warmup.resume_physics;
+    warmup.done = true;
+}
@@ -0,0 +1,230 @@
+use super::arena_events::ArenaEvent;
+use super::player::KickEvent;
+use super::rematch::RematchEvent;
+use super::Score;
+use bevy::prelude::*;
+use std::path::Path;
+use wasmi::{Caller, Config, Engine, Func, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+const MODS_DIR: &str = "mods";
+// host functions live under this import module name
+const HOST_MODULE: &str = "playground";
+// instructions a hook may run before it is stopped, a stuck loop can't hang a frame
+const FUEL_PER_HOOK: u64 = 1_000_000;
+const MAX_MEMORY: usize = 16 * 1024 * 1024;
+
+pub struct WasmModsPlugin;
+
+impl Plugin for WasmModsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let mutators = load_mutators(&engine, Path::new(MODS_DIR));
+        if mutators.is_empty() {
+            return;
+        }
+
+        app.insert_resource(WasmMutators(mutators))
+            .add_system(wasm_events_system);
+    }
+}
+
+// the only things a mutator may ask of the game
+#[derive(Debug)]
+enum MutatorCommand {
+    SpawnPowerUp(i32),
+    SetWind(f32, f32),
+    AdjustScore(i32, i32),
+}
+
+struct MutatorHost {
+    commands: Vec<MutatorCommand>,
+    limits: StoreLimits,
+}
+
+impl Default for MutatorHost {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY)
+                .instances(1)
+                .build(),
+        }
+    }
+}
+
+struct Mutator {
+    name: String,
+    store: Store<MutatorHost>,
+    instance: wasmi::Instance,
+}
+
+impl Mutator {
+    fn new(engine: &Engine, name: String, bytes: &[u8]) -> Result<Self, String> {
+        let module = Module::new(engine, &mut &bytes[..]).map_err(|err| err.to_string())?;
+        let mut store = Store::new(engine, MutatorHost::default());
+        store.limiter(|host| &mut host.limits);
+        let mut linker = Linker::<MutatorHost>::new(engine);
+
+        let spawn_powerup = Func::wrap(
+            &mut store,
+            |mut caller: Caller<'_, MutatorHost>, kind: i32| {
+                caller
+                    .data_mut()
+                    .commands
+                    .push(MutatorCommand::SpawnPowerUp(kind));
+            },
+        );
+        let set_wind = Func::wrap(
+            &mut store,
+            |mut caller: Caller<'_, MutatorHost>, x: f32, z: f32| {
+                caller
+                    .data_mut()
+                    .commands
+                    .push(MutatorCommand::SetWind(x, z));
+            },
+        );
+        let adjust_score = Func::wrap(
+            &mut store,
+            |mut caller: Caller<'_, MutatorHost>, team: i32, delta: i32| {
+                caller
+                    .data_mut()
+                    .commands
+                    .push(MutatorCommand::AdjustScore(team, delta));
+            },
+        );
+        for (name, func) in [
+            ("spawn_powerup", spawn_powerup),
+            ("set_wind", set_wind),
+            ("adjust_score", adjust_score),
+        ] {
+            linker
+                .define(HOST_MODULE, name, func)
+                .map_err(|err| err.to_string())?;
+        }
+
+        // anything importing more than the functions above fails to link
+        refuel(&mut store)?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|err| err.to_string())?
+            .start(&mut store)
+            .map_err(|err| err.to_string())?;
+        Ok(Self {
+            name,
+            store,
+            instance,
+        })
+    }
+
+    // hooks are optional exports, a mutator only implements what it cares about;
+    // false once it has trapped, run out of fuel or hit the memory limit
+    fn call<P: wasmi::WasmParams>(&mut self, hook: &str, params: P) -> bool {
+        let Ok(func) = self.instance.get_typed_func::<P, ()>(&self.store, hook) else {
+            return true;
+        };
+        if let Err(err) = refuel(&mut self.store) {
+            warn!("Mutator {} can't be refuelled: {}", self.name, err);
+            return false;
+        }
+        if let Err(err) = func.call(&mut self.store, params) {
+            warn!("Mutator {} trapped in {}: {}", self.name, hook, err);
+            return false;
+        }
+        true
+    }
+}
+
+// every hook starts from the same budget, whatever the last one left over
+fn refuel(store: &mut Store<MutatorHost>) -> Result<(), String> {
+    let left = store.consume_fuel(0).map_err(|err| err.to_string())?;
+    store
+        .add_fuel(FUEL_PER_HOOK.saturating_sub(left))
+        .map_err(|err| err.to_string())
+}
+
+// each mutator owns its store, so a misbehaving module can only trap itself and
+// is unloaded when it does
+#[derive(Resource)]
+struct WasmMutators(Vec<Mutator>);
+
+fn load_mutators(engine: &Engine, dir: &Path) -> Vec<Mutator> {
+    let Ok(packages) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = packages
+        .filter_map(|entry| std::fs::read_dir(entry.ok()?.path()).ok())
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "wasm"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let bytes = std::fs::read(&path).ok()?;
+            match Mutator::new(engine, path.display().to_string(), &bytes) {
+                Ok(mutator) => {
+                    info!("Loaded mutator {}", mutator.name);
+                    Some(mutator)
+                }
+                Err(err) => {
+                    warn!("Skipping mutator {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn wasm_events_system(
+    mut wasm: ResMut<WasmMutators>,
+    mut kick_events: EventReader<KickEvent>,
+    mut rematch_events: EventReader<RematchEvent>,
+    mut arena_events: EventWriter<ArenaEvent>,
+    mut score: ResMut<Score>,
+    mut last_total: Local<Option<u32>>,
+) {
+    let match_start = last_total.is_none() || rematch_events.iter().count() > 0;
+    let goal = last_total.map_or(false, |total| score.total() > total);
+    let kicks: Vec<f32> = kick_events.iter().map(|ev| ev.impulse.length()).collect();
+    let (home, away) = (score.home as i32, score.away as i32);
+
+    let mut commands = Vec::new();
+    wasm.0.retain_mut(|mutator| {
+        let mut healthy = !match_start || mutator.call("on_match_start", ());
+        healthy = healthy && (!goal || mutator.call("on_goal", (home, away)));
+        for power in kicks.iter() {
+            healthy = healthy && mutator.call("on_kick", (*power,));
+        }
+        if !healthy {
+            warn!("Unloading mutator {}", mutator.name);
+            return false;
+        }
+        commands.append(&mut mutator.store.data_mut().commands);
+        true
+    });
+
+    for command in commands {
+        debug!("Mutator command {:?}", command);
+        match command {
+            MutatorCommand::SpawnPowerUp(0) => arena_events.send(ArenaEvent::MultiBall),
+            MutatorCommand::SpawnPowerUp(_) => arena_events.send(ArenaEvent::BigBall),
+            MutatorCommand::SetWind(x, z) => {
+                arena_events.send(ArenaEvent::WindGust(Vec3::new(x, 0.0, z)))
+            }
+            MutatorCommand::AdjustScore(team, delta) => {
+                let tally = if team == 0 {
+                    &mut score.home
+                } else {
+                    &mut score.away
+                };
+                *tally = tally.saturating_add_signed(delta);
+            }
+        }
+    }
+    *last_total = Some(score.total());
+}
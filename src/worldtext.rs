@@ -0,0 +1,184 @@
+use super::marks::GameCamera;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+// size used by the goal and score popups
+pub const POPUP_SIZE: f32 = 32.0;
+
+pub struct WorldTextPlugin;
+
+impl Plugin for WorldTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(world_popup_system)
+            .add_system(world_text_ui_system.after(world_popup_system));
+    }
+}
+
+// text pinned to an entity in world space, drawn over the GameCamera view
+#[derive(Component, Clone)]
+pub struct WorldText {
+    pub text: String,
+    // in the entity's local units, so scaled entities keep their labels clear
+    pub offset: Vec3,
+    pub size: f32,
+    pub color: Color,
+    // distance at which the label starts fading and where it is gone
+    pub fade: Option<(f32, f32)>,
+    // draws a dot at the anchor point, for markers without much text
+    pub marker: bool,
+}
+
+impl WorldText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            offset: Vec3::ZERO,
+            size: 16.0,
+            color: Color::WHITE,
+            fade: None,
+            marker: false,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_fade(mut self, start: f32, end: f32) -> Self {
+        self.fade = Some((start, end));
+        self
+    }
+
+    pub fn with_marker(mut self) -> Self {
+        self.marker = true;
+        self
+    }
+}
+
+// short lived text that drifts upwards and fades out
+#[derive(Component)]
+pub struct WorldPopup {
+    pub timer: Timer,
+    pub rise: f32,
+}
+
+pub fn spawn_popup(commands: &mut Commands, position: Vec3, text: WorldText, lifetime: f32) {
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_translation(position)),
+        text,
+        WorldPopup {
+            timer: Timer::from_seconds(lifetime, TimerMode::Once),
+            rise: 1.0,
+        },
+        Name::new("popup"),
+    ));
+}
+
+fn world_popup_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popup_query: Query<(Entity, &mut WorldPopup, &mut Transform)>,
+) {
+    for (entity, mut popup, mut tf) in popup_query.iter_mut() {
+        popup.timer.tick(time.delta());
+        tf.translation.y += popup.rise * time.delta_seconds();
+        if popup.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn to_color32(color: Color, alpha: f32) -> egui::Color32 {
+    let [r, g, b, a] = color.as_rgba_f32();
+    egui::Color32::from_rgba_unmultiplied(
+        (r * 255.0) as u8,
+        (g * 255.0) as u8,
+        (b * 255.0) as u8,
+        (a * alpha * 255.0) as u8,
+    )
+}
+
+// the camera's part of the window, in egui points
+pub fn viewport_rect(camera: &Camera) -> Option<egui::Rect> {
+    let (min, max) = camera.logical_viewport_rect()?;
+    Some(egui::Rect::from_min_max(
+        egui::pos2(min.x, min.y),
+        egui::pos2(max.x, max.y),
+    ))
+}
+
+// where a world point lands on the window through this camera, viewport
+// coordinates start at the bottom left of the camera's own rect
+pub fn to_screen(camera: &Camera, camera_tf: &GlobalTransform, point: Vec3) -> Option<egui::Pos2> {
+    let pos = camera.world_to_viewport(camera_tf, point)?;
+    let rect = viewport_rect(camera)?;
+    Some(egui::pos2(rect.min.x + pos.x, rect.max.y - pos.y))
+}
+
+fn world_text_ui_system(
+    mut egui_context: ResMut<EguiContext>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    text_query: Query<(&WorldText, &GlobalTransform, Option<&WorldPopup>)>,
+) {
+    egui::Area::new("world_text")
+        .fixed_pos(egui::pos2(0.0, 0.0))
+        .interactable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            // once per view, clipped to it, so split screen labels both halves
+            for (camera, camera_tf) in camera_query.iter() {
+                let Some(rect) = viewport_rect(camera) else {
+                    continue;
+                };
+                let painter = ui.painter().with_clip_rect(rect);
+                for (text, tf, popup) in text_query.iter() {
+                    let (scale, rotation, translation) = tf.to_scale_rotation_translation();
+                    let anchor = translation + rotation * (text.offset * scale);
+                    let Some(pos) = to_screen(camera, camera_tf, anchor) else {
+                        continue;
+                    };
+
+                    let mut alpha = 1.0;
+                    if let Some((start, end)) = text.fade {
+                        let distance = camera_tf.translation().distance(anchor);
+                        alpha -= ((distance - start) / (end - start)).clamp(0.0, 1.0);
+                    }
+                    if let Some(popup) = popup {
+                        alpha *= popup.timer.percent_left();
+                    }
+                    if alpha <= 0.0 {
+                        continue;
+                    }
+
+                    let font = egui::FontId::proportional(text.size);
+                    if text.marker {
+                        painter.circle_filled(pos, text.size * 0.25, to_color32(text.color, alpha));
+                    }
+                    painter.text(
+                        pos + egui::vec2(1.0, 1.0),
+                        egui::Align2::CENTER_BOTTOM,
+                        &text.text,
+                        font.clone(),
+                        to_color32(Color::BLACK, alpha),
+                    );
+                    painter.text(
+                        pos,
+                        egui::Align2::CENTER_BOTTOM,
+                        &text.text,
+                        font,
+                        to_color32(text.color, alpha),
+                    );
+                }
+            }
+        });
+}